@@ -135,6 +135,60 @@ pub struct GraphQLConfig {
     pub endpoint: String,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub struct WitnessConfig {
+    /// Private key of the witness, in hex. Leave empty to disable block producing.
+    #[serde(default = "Default::default")]
+    pub private_key: String,
+    /// Witness address. Defaults to the address of the private key, set it when signing with the witness
+    /// permission key.
+    #[serde(default = "Default::default")]
+    pub address: String,
+}
+
+/// How to handle transaction results mismatched with the ones embedded in blocks.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResultMismatchMode {
+    /// Log the mismatch and go on.
+    Log,
+    /// Log and write the mismatch to the report file, then go on.
+    Report,
+    /// Write the mismatch to the report file, then fail the block.
+    Halt,
+}
+
+impl Default for ResultMismatchMode {
+    fn default() -> Self {
+        ResultMismatchMode::Log
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub struct VerificationConfig {
+    #[serde(default = "Default::default")]
+    pub result_mismatch: ResultMismatchMode,
+    /// Path to the mismatch report, one tab-separated line per mismatch. Leave empty to disable.
+    #[serde(default = "Default::default")]
+    pub report_file: String,
+}
+
+impl VerificationConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.result_mismatch != ResultMismatchMode::Log && self.report_file.is_empty() {
+            return Err(format!(
+                "verification.report-file is required by result-mismatch = {:?}",
+                self.result_mismatch
+            ));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct Config {
@@ -142,16 +196,22 @@ pub struct Config {
     pub storage: StorageConfig,
     pub protocol: ProtocolConfig,
     pub graphql: GraphQLConfig,
+    #[serde(default = "Default::default")]
+    pub witness: WitnessConfig,
+    #[serde(default = "Default::default")]
+    pub verification: VerificationConfig,
 }
 
 impl Config {
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
         let content = fs::read_to_string(path)?;
-        Ok(toml::from_str(&content)?)
+        Config::load_from_str(&content)
     }
 
     pub fn load_from_str(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(toml::from_str(content)?)
+        let config: Config = toml::from_str(content)?;
+        config.verification.validate()?;
+        Ok(config)
     }
 }
 
@@ -163,4 +223,26 @@ mod tests {
     fn test_load_default_mainnet_config() {
         assert!(Config::load_from_str(include_str!("../../etc/conf.toml")).is_ok());
     }
+
+    #[test]
+    fn test_report_mode_requires_report_file() {
+        let conf = include_str!("../../etc/conf.toml");
+        let without_verification = conf.split("[verification]").next().unwrap();
+
+        let with_mode = |mode: &str, report_file: Option<&str>| {
+            let mut content = format!(
+                "{}\n[verification]\nresult-mismatch = \"{}\"\n",
+                without_verification, mode
+            );
+            if let Some(path) = report_file {
+                content.push_str(&format!("report-file = \"{}\"\n", path));
+            }
+            Config::load_from_str(&content)
+        };
+        assert!(with_mode("log", None).is_ok());
+        assert!(with_mode("report", None).is_err());
+        assert!(with_mode("report", Some("")).is_err());
+        assert!(with_mode("halt", None).is_err());
+        assert!(with_mode("report", Some("./mismatch.tsv")).is_ok());
+    }
 }
@@ -53,6 +53,9 @@ pub const MAX_NUM_OF_VOTES: usize = 30;
 /// 1d, in ms.
 pub const MAX_TRANSACTION_EXPIRATION: i64 = 24 * 60 * 60 * 1_000;
 
+/// Max number of transactions waiting to be packed, same as java-tron's default `maxTransactionPendingSize`.
+pub const MAX_NUM_OF_PENDING_TRANSACTIONS: usize = 2_000;
+
 pub const DEFAULT_ORIGIN_ENERGY_LIMIT: usize = 10_000_000;
 
 pub const MAX_NUM_OF_FROZEN_DAYS_FOR_RESOURCE: i64 = 3;
@@ -2,15 +2,20 @@ use std::collections::HashSet;
 use std::error::Error;
 use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicU32};
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
 
+use chain::IndexedTransaction;
 use chain_db::ChainDB;
 use config::genesis::GenesisConfig;
 use config::Config;
 use log::info;
 use primitive_types::H256;
+use proto::channel::PbftMessage;
 use proto::common::BlockId;
+use state::StateError;
 use tokio::sync::broadcast;
+use manager::event::ChainEvent;
+use manager::txpool::TransactionPool;
 use manager::Manager;
 
 pub struct AppContext {
@@ -27,6 +32,14 @@ pub struct AppContext {
     pub recent_blk_ids: RwLock<HashSet<H256>>,
     /// The termination signal is used to close all connections and services.
     pub termination_signal: broadcast::Sender<()>,
+    /// Notifies the block id of newly produced blocks, to be broadcasted.
+    pub new_block_signal: broadcast::Sender<BlockId>,
+    /// Transactions waiting to be packed into a block, only filled when block producing is enabled.
+    pub pending_transactions: Mutex<TransactionPool>,
+    /// Chain events emitted by the manager, i.e. applied, solidified and rolled back blocks.
+    pub chain_events: broadcast::Sender<ChainEvent>,
+    /// PBFT messages to be broadcasted, signed by this node or relayed.
+    pub pbft_messages: broadcast::Sender<PbftMessage>,
     pub manager: RwLock<Manager>,
 }
 
@@ -61,8 +74,19 @@ impl AppContext {
         info!("chain-db loaded");
 
         let mut db_manager = Manager::new(&config, &genesis_config);
-        let ref_block_hashes = chain_db.ref_block_hashes_of_block_num(db_manager.latest_block_number());
+        let ref_block_hashes = chain_db.ref_block_hashes_of_block_num(db_manager.latest_block_number()?);
         db_manager.init_ref_blocks(ref_block_hashes);
+        let latest_block = chain_db.get_block_by_hash(&db_manager.latest_block_hash()?)?;
+        db_manager.init_fork_db(latest_block)?;
+
+        let chain_events = broadcast::channel(1024).0;
+        {
+            let chain_events = chain_events.clone();
+            db_manager.set_event_handler(move |event| {
+                // No receiver is not an error.
+                let _ = chain_events.send(event);
+            });
+        }
 
         Ok(AppContext {
             chain_db,
@@ -77,7 +101,26 @@ impl AppContext {
             num_passive_connections: AtomicU32::new(0),
             recent_blk_ids: RwLock::new(HashSet::new()),
             termination_signal: broadcast::channel(1024).0,
+            new_block_signal: broadcast::channel(1024).0,
+            pending_transactions: Mutex::new(TransactionPool::default()),
+            chain_events,
+            pbft_messages: broadcast::channel(1024).0,
             manager: RwLock::new(db_manager),
         })
     }
+
+    /// Is block producing enabled, i.e. a witness private key is configured.
+    pub fn is_producing_enabled(&self) -> bool {
+        !self.config.witness.private_key.is_empty()
+    }
+
+    /// Add a transaction to the pending pool. Returns false if it is not accepted, i.e. block producing is
+    /// disabled, or the transaction is duplicated, expired or the pool is full.
+    pub fn add_pending_transaction(&self, txn: IndexedTransaction) -> Result<bool, StateError> {
+        if !self.is_producing_enabled() {
+            return Ok(false);
+        }
+        let now = self.manager.read().unwrap().latest_block_timestamp()?;
+        Ok(self.pending_transactions.lock().unwrap().push(txn, now))
+    }
 }
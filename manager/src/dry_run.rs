@@ -0,0 +1,100 @@
+//! Dry run results of whole blocks, for debugging consensus mismatches.
+
+use std::convert::TryInto;
+
+use ::keys::Address;
+use primitive_types::H256;
+use proto::state::{Account, TransactionReceipt};
+use state::db::{KeyValueDB, StateDB, COL_DEFAULT};
+use state::keys;
+use state::StateError;
+
+/// Local bookkeeping properties, not part of the consensus state.
+const NON_CONSENSUS_PROPERTIES: &[&str] = &["DbVersion", "DbMigrationStep", "TransactionLogIndexStart"];
+
+/// Receipts and state changes of a dry-run block. Nothing is written to the state-db.
+#[derive(Debug)]
+pub struct BlockDryRun {
+    pub receipts: Vec<(H256, TransactionReceipt)>,
+    pub diff: StateDiff,
+}
+
+/// State changes made by a block, ordered by key.
+///
+/// Only consensus state is compared. Non-consensus columns like witness performance, transaction log and
+/// internal transaction indexes, and local bookkeeping properties are left out.
+#[derive(Debug, Default)]
+pub struct StateDiff {
+    pub accounts: Vec<AccountDiff>,
+    pub storage: Vec<StorageDiff>,
+    pub properties: Vec<PropertyDiff>,
+}
+
+#[derive(Debug)]
+pub struct AccountDiff {
+    pub address: Address,
+    pub before: Option<Account>,
+    pub after: Option<Account>,
+}
+
+#[derive(Debug)]
+pub struct StorageDiff {
+    pub address: Address,
+    pub slot: H256,
+    pub before: Option<H256>,
+    pub after: Option<H256>,
+}
+
+/// Change of a dynamic property, named as in `DynamicProperty`.
+#[derive(Debug)]
+pub struct PropertyDiff {
+    pub name: String,
+    pub before: Option<i64>,
+    pub after: Option<i64>,
+}
+
+impl StateDiff {
+    /// Collect changes made in state-db layers above the given depth.
+    pub fn since(state_db: &StateDB<dyn KeyValueDB>, depth: usize) -> Result<StateDiff, StateError> {
+        let accounts = state_db
+            .changes_since::<_, keys::Account>(depth)?
+            .into_iter()
+            .map(|(keys::Account(address), before, after)| AccountDiff { address, before, after })
+            .collect();
+        let storage = state_db
+            .changes_since::<_, keys::ContractStorage>(depth)?
+            .into_iter()
+            .map(|(keys::ContractStorage(address, slot), before, after)| StorageDiff {
+                address,
+                slot,
+                before,
+                after,
+            })
+            .collect();
+        // Dynamic properties are stored as `k{Name}` => i64 in the default column, along with other
+        // non-i64 values like `kWitnessSchedule`.
+        let parse_i64 = |raw: &Option<Vec<u8>>| match raw {
+            Some(raw) => raw[..].try_into().ok().map(|raw| Some(i64::from_be_bytes(raw))),
+            None => Some(None),
+        };
+        let properties = state_db
+            .raw_changes_since(depth, COL_DEFAULT)?
+            .into_iter()
+            .filter(|(key, _, _)| key.starts_with(b"k"))
+            .filter_map(|(key, before, after)| {
+                Some(PropertyDiff {
+                    name: String::from_utf8_lossy(&key[1..]).into_owned(),
+                    before: parse_i64(&before)?,
+                    after: parse_i64(&after)?,
+                })
+            })
+            .filter(|change| !NON_CONSENSUS_PROPERTIES.contains(&&*change.name))
+            .collect();
+
+        Ok(StateDiff {
+            accounts,
+            storage,
+            properties,
+        })
+    }
+}
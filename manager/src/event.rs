@@ -0,0 +1,20 @@
+//! Chain events emitted by the manager.
+
+use primitive_types::H256;
+
+/// Typed chain events, in the order they happen.
+///
+/// Consumers only interested in irreversible data should act on `BlockSolidified`. Applied blocks can be
+/// rolled back by a chain fork before solidified.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainEvent {
+    /// The block is applied to the state-db as the latest block.
+    BlockApplied { number: i64, hash: H256 },
+    /// The block becomes the solid(irreversible) block.
+    BlockSolidified { number: i64, hash: H256 },
+    /// The block is rolled back, when switching to a longer fork.
+    BlockRolledBack { number: i64, hash: H256 },
+}
+
+/// Receives chain events.
+pub type ChainEventHandler = Box<dyn Fn(ChainEvent) + Send + Sync>;
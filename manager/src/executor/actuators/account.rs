@@ -12,12 +12,12 @@ use proto::state::{Account, ActivePermission, OwnerPermission, PermissionKey};
 use state::keys;
 
 use crate::Manager;
-use super::super::TransactionContext;
+use super::super::{ExecutionError, TransactionContext};
 use super::BuiltinContractExecutorExt;
 
 // Set account's name.
 impl BuiltinContractExecutorExt for contract_pb::AccountUpdateContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
         // validAccountName
@@ -29,23 +29,27 @@ impl BuiltinContractExecutorExt for contract_pb::AccountUpdateContract {
         let acct = state_db
             .get(&keys::Account(owner_address))
             .map_err(|_| "db query error")?
-            .ok_or_else(|| "account not exists")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_address))?;
 
-        let allow_update_account_name = state_db.must_get(&keys::ChainParameter::AllowUpdateAccountName) != 0;
+        let allow_update_account_name = state_db.must_get(&keys::ChainParameter::AllowUpdateAccountName)? != 0;
         if !acct.name.is_empty() && !allow_update_account_name {
             return Err("account name already exists".into());
         }
 
-        if !allow_update_account_name && find_account_by_name(manager, &self.account_name).is_some() {
+        if !allow_update_account_name && find_account_by_name(manager, &self.account_name)?.is_some() {
             return Err("the same account name already exists".into());
         }
 
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).unwrap();
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address))?;
 
         owner_acct.name = self.account_name.clone();
 
@@ -64,10 +68,10 @@ impl BuiltinContractExecutorExt for contract_pb::AccountUpdateContract {
 
 // Update account's permission for multisig or transfering ownership.
 impl BuiltinContractExecutorExt for contract_pb::AccountPermissionUpdateContract {
-    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
-        if state_db.must_get(&keys::ChainParameter::AllowMultisig) == 0 {
+        if state_db.must_get(&keys::ChainParameter::AllowMultisig)? == 0 {
             return Err("multisig is disabled on chain".into());
         }
 
@@ -75,7 +79,7 @@ impl BuiltinContractExecutorExt for contract_pb::AccountPermissionUpdateContract
         let acct = state_db
             .get(&keys::Account(owner_address))
             .map_err(|_| "db query error")?
-            .ok_or_else(|| "account not exists")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_address))?;
 
         if self.owner.is_none() {
             return Err("missing owner permission".into());
@@ -108,18 +112,22 @@ impl BuiltinContractExecutorExt for contract_pb::AccountPermissionUpdateContract
             check_permission(active, PermissionType::Active)?;
         }
 
-        let fee = self.fee(manager);
+        let fee = self.fee(manager)?;
         if acct.balance < fee {
-            return Err("insufficient balance to set account permission".into());
+            return Err(ExecutionError::InsufficientBalance);
         }
         ctx.contract_fee = fee;
 
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).unwrap();
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address))?;
 
         // updatePermissions
         if let Some(owner_perm) = self.owner.as_ref() {
@@ -155,7 +163,7 @@ impl BuiltinContractExecutorExt for contract_pb::AccountPermissionUpdateContract
             .collect();
 
         if let Some(wit_perm) = self.witness.as_ref() {
-            let mut wit = manager.state_db.must_get(&keys::Witness(owner_address));
+            let mut wit = manager.state_db.must_get(&keys::Witness(owner_address))?;
             wit.signature_key = wit_perm.keys[0].address.clone();
 
             manager
@@ -176,10 +184,10 @@ impl BuiltinContractExecutorExt for contract_pb::AccountPermissionUpdateContract
         Ok(TransactionResult::success())
     }
 
-    fn fee(&self, manager: &Manager) -> i64 {
-        manager
+    fn fee(&self, manager: &Manager) -> Result<i64, ExecutionError> {
+        Ok(manager
             .state_db
-            .must_get(&keys::ChainParameter::AccountPermissionUpdateFee)
+            .must_get(&keys::ChainParameter::AccountPermissionUpdateFee)?)
     }
 }
 
@@ -187,10 +195,10 @@ impl BuiltinContractExecutorExt for contract_pb::AccountPermissionUpdateContract
 //
 // NOTE: This is a bad desgin, and is still vulnerable. One can create a contract of any type, which is meanningless.
 impl BuiltinContractExecutorExt for contract_pb::AccountCreateContract {
-    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
-        let fee = self.fee(manager);
+        let fee = self.fee(manager)?;
 
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
         let new_address = Address::try_from(&self.account_address).map_err(|_| "invalid account_address")?;
@@ -198,7 +206,7 @@ impl BuiltinContractExecutorExt for contract_pb::AccountCreateContract {
         let owner_acct = state_db
             .get(&keys::Account(owner_address))
             .map_err(|_| "db query error")?
-            .ok_or_else(|| "account not exists")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_address))?;
 
         let maybe_new_acct = state_db
             .get(&keys::Account(new_address))
@@ -208,7 +216,7 @@ impl BuiltinContractExecutorExt for contract_pb::AccountCreateContract {
         }
 
         if owner_acct.balance < fee {
-            return Err("insufficient balance to create an account".into());
+            return Err(ExecutionError::InsufficientBalance);
         }
 
         // NOTE: type is not checked here!
@@ -217,16 +225,20 @@ impl BuiltinContractExecutorExt for contract_pb::AccountCreateContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).unwrap();
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address))?;
 
         let new_address = Address::try_from(&self.account_address).unwrap();
 
         let fee = ctx.contract_fee;
 
         // NOTE: Account's creation_time is not current block timestamp, it's previous.
-        let mut new_acct = Account::new(manager.latest_block_timestamp());
+        let mut new_acct = Account::new(manager.latest_block_timestamp()?);
         if let Some(acct_type) = AccountType::from_i32(self.r#type as i32) {
             if acct_type != AccountType::Normal {
                 warn!("create account with type={:?}", acct_type);
@@ -254,18 +266,18 @@ impl BuiltinContractExecutorExt for contract_pb::AccountCreateContract {
         Ok(TransactionResult::success())
     }
 
-    fn fee(&self, manager: &Manager) -> i64 {
+    fn fee(&self, manager: &Manager) -> Result<i64, ExecutionError> {
         // NOTE: CreateNewAccountFeeInSystemContract is 0.
         // Account creation fee(bandwidth) is handled by BandwidthProcessor.
-        manager
+        Ok(manager
             .state_db
-            .must_get(&keys::ChainParameter::CreateNewAccountFeeInSystemContract)
+            .must_get(&keys::ChainParameter::CreateNewAccountFeeInSystemContract)?)
     }
 }
 
 // Deprecated but not removed.
 impl BuiltinContractExecutorExt for contract_pb::SetAccountIdContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
@@ -284,7 +296,7 @@ impl BuiltinContractExecutorExt for contract_pb::SetAccountIdContract {
         let acct = state_db
             .get(&keys::Account(owner_address))
             .map_err(|_| "db query error")?
-            .ok_or("account not exists")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_address))?;
 
         if !acct.account_id.is_empty() {
             return Err("account id is already set".into());
@@ -295,9 +307,13 @@ impl BuiltinContractExecutorExt for contract_pb::SetAccountIdContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).unwrap();
-        let mut acct = manager.state_db.must_get(&keys::Account(owner_address));
+        let mut acct = manager.state_db.must_get(&keys::Account(owner_address))?;
 
         acct.account_id = self.account_id.clone();
 
@@ -311,13 +327,11 @@ impl BuiltinContractExecutorExt for contract_pb::SetAccountIdContract {
 }
 
 /// Find an account in state-db by its name.
-fn find_account_by_name(manager: &Manager, acct_name: &str) -> Option<Account> {
-    let maybe_addr = manager
-        .state_db
-        .get(&keys::AccountIndex(acct_name.to_owned()))
-        .ok()
-        .flatten();
-    maybe_addr.map(|addr| manager.state_db.must_get(&keys::Account(addr)))
+fn find_account_by_name(manager: &Manager, acct_name: &str) -> Result<Option<Account>, String> {
+    match manager.state_db.get(&keys::AccountIndex(acct_name.to_owned()))? {
+        Some(addr) => Ok(Some(manager.state_db.must_get(&keys::Account(addr))?)),
+        None => Ok(None),
+    }
 }
 
 /// Check permission pb definition.
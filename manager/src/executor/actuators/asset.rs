@@ -14,16 +14,16 @@ use proto::state::{asset::FrozenSupply, Account, Asset};
 use state::keys;
 
 use crate::Manager;
-use super::super::TransactionContext;
+use super::super::{ExecutionError, TransactionContext};
 use super::BuiltinContractExecutorExt;
 
 impl BuiltinContractExecutorExt for contract_pb::AssetIssueContract {
-    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = manager.state();
 
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
 
-        let fee = self.fee(manager);
+        let fee = self.fee(manager)?;
         ctx.contract_fee = fee;
 
         // validAssetName
@@ -31,7 +31,7 @@ impl BuiltinContractExecutorExt for contract_pb::AssetIssueContract {
             return Err("invalid asset name".into());
         }
 
-        let allow_same_token_name = state_db.must_get(&keys::ChainParameter::AllowSameTokenName) != 0;
+        let allow_same_token_name = state_db.must_get(&keys::ChainParameter::AllowSameTokenName)? != 0;
 
         if allow_same_token_name && self.name.to_lowercase() == "trx" {
             return Err("asset name cannot be 'TRX'".into());
@@ -69,11 +69,11 @@ impl BuiltinContractExecutorExt for contract_pb::AssetIssueContract {
         if self.end_time <= self.start_time {
             return Err("asset end time should be greater than start time".into());
         }
-        if self.start_time <= manager.latest_block_timestamp() {
+        if self.start_time <= manager.latest_block_timestamp()? {
             return Err("asset start time should be greater than latest block timestamp".into());
         }
 
-        if !allow_same_token_name && find_asset_by_name(manager, &self.name).is_some() {
+        if !allow_same_token_name && find_asset_by_name(manager, &self.name)?.is_some() {
             return Err("asset name already exists".into());
         }
 
@@ -124,7 +124,8 @@ impl BuiltinContractExecutorExt for contract_pb::AssetIssueContract {
                     "frozen days should be in the range [{}, {}]",
                     constants::MIN_NUM_OF_FROZEN_DAYS_IN_ASSET_ISSUE,
                     constants::MAX_NUM_OF_FROZEN_DAYS_IN_ASSET_ISSUE
-                ));
+                )
+                .into());
             }
             remain_supply -= frozen_supply.frozen_amount;
         }
@@ -134,7 +135,7 @@ impl BuiltinContractExecutorExt for contract_pb::AssetIssueContract {
             .get(&keys::Account(owner_address))
             .map_err(|_| "db query error")?;
         if maybe_acct.is_none() {
-            return Err("account not exists".into());
+            return Err(ExecutionError::AccountNotFound(owner_address));
         }
         let acct = maybe_acct.unwrap();
 
@@ -143,7 +144,7 @@ impl BuiltinContractExecutorExt for contract_pb::AssetIssueContract {
         }
 
         if acct.balance < fee {
-            return Err("insufficient balance".into());
+            return Err(ExecutionError::InsufficientBalance);
         }
 
         // NOTE: The `order` field is not used(commented out in java-tron).
@@ -151,16 +152,20 @@ impl BuiltinContractExecutorExt for contract_pb::AssetIssueContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         const DAY_IN_MS: i64 = 86_400_000;
 
         let owner_address = Address::try_from(&self.owner_address).unwrap();
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address))?;
 
-        let token_id = manager.state_db.must_get(&keys::DynamicProperty::LatestTokenId) + 1;
+        let token_id = manager.state_db.must_get(&keys::DynamicProperty::LatestTokenId)? + 1;
         owner_acct.issued_asset_id = token_id;
 
-        let allow_same_token_name = manager.state_db.must_get(&keys::ChainParameter::AllowSameTokenName) != 0;
+        let allow_same_token_name = manager.state_db.must_get(&keys::ChainParameter::AllowSameTokenName)? != 0;
 
         // NOTE: `state::Asset` is not the same as `contract::AssetIssue`.
         let mut asset = Asset {
@@ -215,24 +220,24 @@ impl BuiltinContractExecutorExt for contract_pb::AssetIssueContract {
             .put_key(keys::Account(owner_address), owner_acct)
             .map_err(|_| "db insert error")?;
 
-        // NOTE: `assetIssueID` of TransactionResult is not filled.
+        ctx.asset_created_token_id = token_id;
         Ok(TransactionResult::success())
     }
 
-    fn fee(&self, manager: &Manager) -> i64 {
-        manager.state_db.must_get(&keys::ChainParameter::AssetIssueFee)
+    fn fee(&self, manager: &Manager) -> Result<i64, ExecutionError> {
+        Ok(manager.state_db.must_get(&keys::ChainParameter::AssetIssueFee)?)
     }
 }
 
 // Transfer TRC10(Asset) tokens, creating to_account when it is not on chain.
 impl BuiltinContractExecutorExt for contract_pb::TransferAssetContract {
-    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = manager.state();
 
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
         let to_address = Address::try_from(&self.to_address).map_err(|_| "invalid to_address")?;
 
-        let mut fee = self.fee(manager);
+        let mut fee = self.fee(manager)?;
 
         if self.amount <= 0 {
             return Err("transfer amount must be greater than 0".into());
@@ -242,15 +247,15 @@ impl BuiltinContractExecutorExt for contract_pb::TransferAssetContract {
             return Err("cannot transfer to oneself".into());
         }
 
-        let allow_same_token_name = manager.state_db.must_get(&keys::ChainParameter::AllowSameTokenName) != 0;
+        let allow_same_token_name = manager.state_db.must_get(&keys::ChainParameter::AllowSameTokenName)? != 0;
         let maybe_asset = if allow_same_token_name {
             let token_id = self.asset_name.parse().map_err(|_| "invalid asset name")?;
             state_db.get(&keys::Asset(token_id)).map_err(|_| "db query error")?
         } else {
-            find_asset_by_name(manager, &self.asset_name)
+            find_asset_by_name(manager, &self.asset_name)?
         };
         if maybe_asset.is_none() {
-            return Err(format!("asset name {} not found", self.asset_name));
+            return Err(format!("asset name {} not found", self.asset_name).into());
         }
         let asset = maybe_asset.unwrap();
 
@@ -259,13 +264,13 @@ impl BuiltinContractExecutorExt for contract_pb::TransferAssetContract {
             .get(&keys::Account(owner_address))
             .map_err(|_| "db query error")?;
         if maybe_owner_acct.is_none() {
-            return Err("account not exists".into());
+            return Err(ExecutionError::AccountNotFound(owner_address));
         }
         let owner_acct = maybe_owner_acct.unwrap();
 
         let token_balance = owner_acct.token_balance.get(&asset.id).copied().unwrap_or(0);
         if token_balance < self.amount {
-            return Err("insufficient token balance".into());
+            return Err(ExecutionError::InsufficientBalance);
         }
 
         let maybe_to_acct = state_db
@@ -273,7 +278,7 @@ impl BuiltinContractExecutorExt for contract_pb::TransferAssetContract {
             .map_err(|_| "error while querying db")?;
         if let Some(to_acct) = maybe_to_acct {
             if to_acct.r#type == AccountType::Contract as i32 &&
-                state_db.must_get(&keys::ChainParameter::ForbidTransferToContract) == 1
+                state_db.must_get(&keys::ChainParameter::ForbidTransferToContract)? == 1
             {
                 return Err("cannot transfer to a smart contract".into());
             }
@@ -291,11 +296,11 @@ impl BuiltinContractExecutorExt for contract_pb::TransferAssetContract {
         } else {
             ctx.new_account_created = true;
             // NOTE: CreateNewAccountFeeInSystemContract is 0, account creation fee is handled by BandwidthProcessor.
-            fee += state_db.must_get(&keys::ChainParameter::CreateNewAccountFeeInSystemContract);
+            fee += state_db.must_get(&keys::ChainParameter::CreateNewAccountFeeInSystemContract)?;
         }
 
         if fee != 0 && owner_acct.balance < fee {
-            return Err("insufficient balance".into());
+            return Err(ExecutionError::InsufficientBalance);
         }
 
         ctx.contract_fee = fee;
@@ -303,25 +308,32 @@ impl BuiltinContractExecutorExt for contract_pb::TransferAssetContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).unwrap();
         let to_address = Address::try_from(&self.to_address).unwrap();
 
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address))?;
 
         let fee = ctx.contract_fee;
 
-        let mut to_acct = manager
+        let maybe_to_acct = manager
             .state_db
             .get(&keys::Account(to_address))
-            .map_err(|e| format!("state-db error: {:?}", e))?
-            .unwrap_or_else(|| Account::new(manager.latest_block_timestamp()));
+            .map_err(|e| format!("state-db error: {:?}", e))?;
+        let mut to_acct = match maybe_to_acct {
+            Some(acct) => acct,
+            None => Account::new(manager.latest_block_timestamp()?),
+        };
 
-        let allow_same_token_name = manager.state_db.must_get(&keys::ChainParameter::AllowSameTokenName) != 0;
+        let allow_same_token_name = manager.state_db.must_get(&keys::ChainParameter::AllowSameTokenName)? != 0;
         let token_id: i64 = if allow_same_token_name {
             self.asset_name.parse().map_err(|_| "invalid asset name")?
         } else {
-            find_asset_by_name(manager, &self.asset_name).unwrap().id
+            find_asset_by_name(manager, &self.asset_name)?.unwrap().id
         };
 
         if fee != 0 {
@@ -350,7 +362,7 @@ impl BuiltinContractExecutorExt for contract_pb::TransferAssetContract {
 
 // Participate asset issuing while asset is in issuing period. Buy new TRC10 token using TRX.
 impl BuiltinContractExecutorExt for contract_pb::ParticipateAssetIssueContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = manager.state();
 
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
@@ -368,32 +380,32 @@ impl BuiltinContractExecutorExt for contract_pb::ParticipateAssetIssueContract {
             .get(&keys::Account(owner_address))
             .map_err(|_| "error while querying db")?;
         if maybe_owner_acct.is_none() {
-            return Err("owner account is not on chain".into());
+            return Err(ExecutionError::AccountNotFound(owner_address));
         }
         let owner_acct = maybe_owner_acct.unwrap();
 
         if owner_acct.balance < self.amount {
-            return Err("insufficient balance".into());
+            return Err(ExecutionError::InsufficientBalance);
         }
 
-        let allow_same_token_name = manager.state_db.must_get(&keys::ChainParameter::AllowSameTokenName) != 0;
+        let allow_same_token_name = manager.state_db.must_get(&keys::ChainParameter::AllowSameTokenName)? != 0;
         let maybe_asset = if allow_same_token_name {
             let token_id = self.asset_name.parse().map_err(|_| "invalid asset name")?;
             state_db.get(&keys::Asset(token_id)).map_err(|_| "db query error")?
         } else {
-            find_asset_by_name(manager, &self.asset_name)
+            find_asset_by_name(manager, &self.asset_name)?
         };
         if maybe_asset.is_none() {
-            return Err(format!("asset name {} not found", self.asset_name));
+            return Err(format!("asset name {} not found", self.asset_name).into());
         }
         let asset = maybe_asset.unwrap();
 
         if to_address.as_bytes() != &*asset.owner_address {
-            return Err(format!("asset {} is not issued by {}", asset.id, to_address));
+            return Err(format!("asset {} is not issued by {}", asset.id, to_address).into());
         }
 
         // exchange feasibility check
-        let now = manager.latest_block_timestamp();
+        let now = manager.latest_block_timestamp()?;
         if now >= asset.end_time || now < asset.start_time {
             return Err("asset is not in issuing period".into());
         }
@@ -413,26 +425,30 @@ impl BuiltinContractExecutorExt for contract_pb::ParticipateAssetIssueContract {
             .get(&keys::Account(to_address))
             .map_err(|_| "error while querying db")?;
         if maybe_to_acct.is_none() {
-            return Err("to account is not on chain".into());
+            return Err(ExecutionError::AccountNotFound(to_address));
         }
         let to_acct = maybe_to_acct.unwrap();
 
         if to_acct.token_balance.get(&asset.id).copied().unwrap_or(0) < exchange_amount {
-            return Err("insufficient balance of target asset".into());
+            return Err(ExecutionError::InsufficientBalance);
         }
 
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).unwrap();
         let to_address = Address::try_from(&self.to_address).unwrap();
 
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address));
-        let mut to_acct = manager.state_db.must_get(&keys::Account(to_address));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address))?;
+        let mut to_acct = manager.state_db.must_get(&keys::Account(to_address))?;
 
         // TODO: might be optimized via ctx, to avoid re-calculation
-        let allow_same_token_name = manager.state_db.must_get(&keys::ChainParameter::AllowSameTokenName) != 0;
+        let allow_same_token_name = manager.state_db.must_get(&keys::ChainParameter::AllowSameTokenName)? != 0;
         let asset = if allow_same_token_name {
             let token_id = self.asset_name.parse().map_err(|_| "invalid asset name")?;
             manager
@@ -441,7 +457,7 @@ impl BuiltinContractExecutorExt for contract_pb::ParticipateAssetIssueContract {
                 .map_err(|_| "db query error")?
                 .unwrap()
         } else {
-            find_asset_by_name(manager, &self.asset_name).unwrap()
+            find_asset_by_name(manager, &self.asset_name)?.unwrap()
         };
         let exchange_amount = self.amount * asset.num as i64 / asset.trx_num as i64;
 
@@ -466,7 +482,7 @@ impl BuiltinContractExecutorExt for contract_pb::ParticipateAssetIssueContract {
 
 // Update an asset' url, description, per-account free bw limit, global free bw limit.
 impl BuiltinContractExecutorExt for contract_pb::UpdateAssetContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = manager.state();
 
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
@@ -475,7 +491,7 @@ impl BuiltinContractExecutorExt for contract_pb::UpdateAssetContract {
             .get(&keys::Account(owner_address))
             .map_err(|_| "error while querying db")?;
         if maybe_owner_acct.is_none() {
-            return Err("owner account is not on chain".into());
+            return Err(ExecutionError::AccountNotFound(owner_address));
         }
         let owner_acct = maybe_owner_acct.unwrap();
         if owner_acct.issued_asset_id == 0 {
@@ -487,10 +503,7 @@ impl BuiltinContractExecutorExt for contract_pb::UpdateAssetContract {
             .get(&keys::Asset(owner_acct.issued_asset_id))
             .map_err(|_| "db query error")?;
         if maybe_asset.is_none() {
-            return Err(format!(
-                "asset for id {} is not found in state-db",
-                owner_acct.issued_asset_id
-            ));
+            return Err(format!("asset for id {} is not found in state-db", owner_acct.issued_asset_id).into());
         }
 
         // validUrl
@@ -514,10 +527,14 @@ impl BuiltinContractExecutorExt for contract_pb::UpdateAssetContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).unwrap();
-        let owner_acct = manager.state_db.must_get(&keys::Account(owner_address));
-        let mut asset = manager.state_db.must_get(&keys::Asset(owner_acct.issued_asset_id));
+        let owner_acct = manager.state_db.must_get(&keys::Account(owner_address))?;
+        let mut asset = manager.state_db.must_get(&keys::Asset(owner_acct.issued_asset_id))?;
 
         asset.url = self.url.clone();
         asset.description = self.description.clone();
@@ -535,7 +552,7 @@ impl BuiltinContractExecutorExt for contract_pb::UpdateAssetContract {
 
 // Unfreeze an asset's frozen_supply.
 impl BuiltinContractExecutorExt for contract_pb::UnfreezeAssetContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = manager.state();
 
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
@@ -544,7 +561,7 @@ impl BuiltinContractExecutorExt for contract_pb::UnfreezeAssetContract {
             .get(&keys::Account(owner_address))
             .map_err(|_| "error while querying db")?;
         if maybe_owner_acct.is_none() {
-            return Err("owner account is not on chain".into());
+            return Err(ExecutionError::AccountNotFound(owner_address));
         }
         let owner_acct = maybe_owner_acct.unwrap();
         if owner_acct.issued_asset_id == 0 {
@@ -555,10 +572,7 @@ impl BuiltinContractExecutorExt for contract_pb::UnfreezeAssetContract {
             .get(&keys::Asset(owner_acct.issued_asset_id))
             .map_err(|_| "db query error")?;
         if maybe_asset.is_none() {
-            return Err(format!(
-                "asset for id {} is not found in state-db",
-                owner_acct.issued_asset_id
-            ));
+            return Err(format!("asset for id {} is not found in state-db", owner_acct.issued_asset_id).into());
         }
         let asset = maybe_asset.unwrap();
         if asset.frozen_supply.is_empty() {
@@ -566,7 +580,7 @@ impl BuiltinContractExecutorExt for contract_pb::UnfreezeAssetContract {
         }
 
         log::debug!("frozen_sup => {:?}", asset.frozen_supply);
-        let now = manager.latest_block_timestamp();
+        let now = manager.latest_block_timestamp()?;
         if asset
             .frozen_supply
             .iter()
@@ -579,12 +593,16 @@ impl BuiltinContractExecutorExt for contract_pb::UnfreezeAssetContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).unwrap();
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address));
-        let mut asset = manager.state_db.must_get(&keys::Asset(owner_acct.issued_asset_id));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address))?;
+        let mut asset = manager.state_db.must_get(&keys::Asset(owner_acct.issued_asset_id))?;
 
-        let now = manager.latest_block_timestamp();
+        let now = manager.latest_block_timestamp()?;
 
         let mut unfrozen_amount = 0_i64;
         for sup in asset.frozen_supply.iter_mut() {
@@ -628,10 +646,10 @@ lazy_static! {
 ///
 /// NOTE: This is a design flaw. Actually, one should use an asset's abbr instead of name.
 /// Never mind, use asset id(token id) solves.
-pub fn find_asset_by_name(manager: &Manager, asset_name: &str) -> Option<Asset> {
+pub fn find_asset_by_name(manager: &Manager, asset_name: &str) -> Result<Option<Asset>, String> {
     let mut map = ASSET_ID_CACHE.lock().unwrap();
     if let Some(&token_id) = map.get(asset_name) {
-        manager.state_db.get(&keys::Asset(token_id)).expect("db query error")
+        Ok(manager.state_db.get(&keys::Asset(token_id))?)
     } else {
         let mut found: Option<Asset> = None;
         {
@@ -641,8 +659,8 @@ pub fn find_asset_by_name(manager: &Manager, asset_name: &str) -> Option<Asset>
                     map.insert(asset_name.to_owned(), asset.id);
                     *found = Some(asset.clone());
                 }
-            });
+            })?;
         }
-        found
+        Ok(found)
     }
 }
@@ -11,7 +11,7 @@ use proto::state::Exchange;
 use state::keys;
 
 use crate::Manager;
-use super::super::TransactionContext;
+use super::super::{ExecutionError, TransactionContext};
 use super::asset::find_asset_by_name;
 use super::BuiltinContractExecutorExt;
 
@@ -19,19 +19,19 @@ const EXCHANGE_BALANCE_LIMIT: i64 = 1_000_000_000_000_000;
 
 // Create an exchange pair.
 impl BuiltinContractExecutorExt for contract_pb::ExchangeCreateContract {
-    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
-        let fee = self.fee(manager);
+        let fee = self.fee(manager)?;
 
         let owner_addr = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
         let owner_acct = state_db
             .get(&keys::Account(owner_addr))
             .map_err(|_| "db query error")?
-            .ok_or_else(|| "owner account is not on chain")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_addr))?;
 
         if owner_acct.balance < fee {
-            return Err("insufficient balance".into());
+            return Err(ExecutionError::InsufficientBalance);
         }
 
         if self.first_token_id == self.second_token_id {
@@ -39,7 +39,7 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeCreateContract {
         }
 
         if owner_acct.balance < fee {
-            return Err("insufficient TRX balance".into());
+            return Err(ExecutionError::InsufficientBalance);
         }
 
         let first_token_id = get_exchange_token_id(manager, &self.first_token_id)?;
@@ -47,7 +47,7 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeCreateContract {
 
         if first_token_id == 0 {
             if owner_acct.balance < self.first_token_balance + fee {
-                return Err("insufficient TRX balance".into());
+                return Err(ExecutionError::InsufficientBalance);
             }
         } else {
             if owner_acct
@@ -57,13 +57,13 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeCreateContract {
                 .unwrap_or_default() <
                 self.first_token_balance
             {
-                return Err("insufficient token balance".into());
+                return Err(ExecutionError::InsufficientBalance);
             }
         }
 
         if second_token_id == 0 {
             if owner_acct.balance < self.second_token_balance + fee {
-                return Err("insufficient TRX balance".into());
+                return Err(ExecutionError::InsufficientBalance);
             }
         } else {
             if owner_acct
@@ -73,7 +73,7 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeCreateContract {
                 .unwrap_or_default() <
                 self.second_token_balance
             {
-                return Err("insufficient token balance".into());
+                return Err(ExecutionError::InsufficientBalance);
             }
         }
 
@@ -81,9 +81,13 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeCreateContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_addr = Address::try_from(&self.owner_address).unwrap();
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr))?;
 
         let exchange_id = manager
             .state_db
@@ -111,7 +115,7 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeCreateContract {
         }
         owner_acct.adjust_balance(-ctx.contract_fee).unwrap();
 
-        let now = manager.latest_block_timestamp();
+        let now = manager.latest_block_timestamp()?;
         let exch = Exchange {
             id: exchange_id,
             owner_address: self.owner_address.to_vec(),
@@ -136,17 +140,18 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeCreateContract {
             .map_err(|_| "db insert error")?;
         manager.add_to_blackhole(ctx.contract_fee).unwrap();
 
+        ctx.exchange_created_exchange_id = exchange_id;
         Ok(TransactionResult::success())
     }
 
-    fn fee(&self, manager: &Manager) -> i64 {
-        manager.state_db.must_get(&keys::ChainParameter::ExchangeCreateFee)
+    fn fee(&self, manager: &Manager) -> Result<i64, ExecutionError> {
+        Ok(manager.state_db.must_get(&keys::ChainParameter::ExchangeCreateFee)?)
     }
 }
 
 // Withdraw exchange balance by owner.
 impl BuiltinContractExecutorExt for contract_pb::ExchangeWithdrawContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
         let _ = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
@@ -208,11 +213,15 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeWithdrawContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_addr = Address::try_from(&self.owner_address).unwrap();
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr))?;
 
-        let mut exch = manager.state_db.must_get(&keys::Exchange(self.exchange_id));
+        let mut exch = manager.state_db.must_get(&keys::Exchange(self.exchange_id))?;
 
         let token_id = get_exchange_token_id(manager, &self.token_id).unwrap();
 
@@ -253,6 +262,7 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeWithdrawContract {
             .put_key(keys::Account(owner_addr), owner_acct)
             .map_err(|_| "db insert error")?;
 
+        ctx.exchange_withdrawal_amount = other_token_amount;
         Ok(TransactionResult::success())
     }
 }
@@ -261,14 +271,14 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeWithdrawContract {
 //
 // NOTE: This builtin contract has similar logic as ExchangeWithdrawContract.
 impl BuiltinContractExecutorExt for contract_pb::ExchangeInjectContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
         let owner_addr = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
         let owner_acct = state_db
             .get(&keys::Account(owner_addr))
             .map_err(|_| "db query error")?
-            .ok_or_else(|| "owner account not found on chain")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_addr))?;
         let exch = state_db
             .get(&keys::Exchange(self.exchange_id))
             .map_err(|_| "db query error")?
@@ -293,7 +303,7 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeInjectContract {
             if exch.first_token_balance + self.quant > EXCHANGE_BALANCE_LIMIT ||
                 exch.second_token_balance + other_token_amount > EXCHANGE_BALANCE_LIMIT
             {
-                return Err(format!("token balance in exchange exceeds {}", EXCHANGE_BALANCE_LIMIT));
+                return Err(format!("token balance in exchange exceeds {}", EXCHANGE_BALANCE_LIMIT).into());
             }
             (exch.second_token_id, other_token_amount)
         } else if token_id == exch.second_token_id {
@@ -302,7 +312,7 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeInjectContract {
             if exch.second_token_balance + self.quant > EXCHANGE_BALANCE_LIMIT ||
                 exch.first_token_balance + other_token_amount > EXCHANGE_BALANCE_LIMIT
             {
-                return Err(format!("token balance in exchange exceeds {}", EXCHANGE_BALANCE_LIMIT));
+                return Err(format!("token balance in exchange exceeds {}", EXCHANGE_BALANCE_LIMIT).into());
             }
             (exch.first_token_id, other_token_amount)
         } else {
@@ -317,17 +327,17 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeInjectContract {
 
         if token_id == 0 {
             if owner_acct.balance < self.quant {
-                return Err("insufficient balance".into());
+                return Err(ExecutionError::InsufficientBalance);
             }
         } else {
             if owner_acct.token_balance.get(&token_id).copied().unwrap_or_default() < self.quant {
-                return Err("insufficient token balance".into());
+                return Err(ExecutionError::InsufficientBalance);
             }
         }
 
         if other_token_id == 0 {
             if owner_acct.balance < other_token_amount {
-                return Err("insufficient balance".into());
+                return Err(ExecutionError::InsufficientBalance);
             }
         } else {
             if owner_acct
@@ -337,18 +347,22 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeInjectContract {
                 .unwrap_or_default() <
                 other_token_amount
             {
-                return Err("insufficient token balance".into());
+                return Err(ExecutionError::InsufficientBalance);
             }
         }
 
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_addr = Address::try_from(&self.owner_address).unwrap();
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr))?;
 
-        let mut exch = manager.state_db.must_get(&keys::Exchange(self.exchange_id));
+        let mut exch = manager.state_db.must_get(&keys::Exchange(self.exchange_id))?;
         let token_id = get_exchange_token_id(manager, &self.token_id).unwrap();
 
         let (other_token_id, other_token_amount) = if token_id == exch.first_token_id {
@@ -389,19 +403,20 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeInjectContract {
             .put_key(keys::Account(owner_addr), owner_acct)
             .map_err(|_| "db insert error")?;
 
+        ctx.exchange_injected_amount = other_token_amount;
         Ok(TransactionResult::success())
     }
 }
 
 impl BuiltinContractExecutorExt for contract_pb::ExchangeTransactionContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
         let owner_addr = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
         let owner_acct = state_db
             .get(&keys::Account(owner_addr))
             .map_err(|_| "db query error")?
-            .ok_or_else(|| "owner account not found on chain")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_addr))?;
         let exch = state_db
             .get(&keys::Exchange(self.exchange_id))
             .map_err(|_| "db query error")?
@@ -426,16 +441,16 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeTransactionContract {
             return Err("token is not in the exchange".into());
         };
         if token_balance + self.quant > EXCHANGE_BALANCE_LIMIT {
-            return Err(format!("token balance in exchange exceeds {}", EXCHANGE_BALANCE_LIMIT));
+            return Err(format!("token balance in exchange exceeds {}", EXCHANGE_BALANCE_LIMIT).into());
         }
 
         if token_id == 0 {
             if owner_acct.balance < self.quant {
-                return Err("insufficient balance".into());
+                return Err(ExecutionError::InsufficientBalance);
             }
         } else {
             if owner_acct.token_balance.get(&token_id).copied().unwrap_or_default() < self.quant {
-                return Err("insufficient token balance".into());
+                return Err(ExecutionError::InsufficientBalance);
             }
         }
 
@@ -452,11 +467,15 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeTransactionContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_addr = Address::try_from(&self.owner_address).unwrap();
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr))?;
 
-        let mut exch = manager.state_db.must_get(&keys::Exchange(self.exchange_id));
+        let mut exch = manager.state_db.must_get(&keys::Exchange(self.exchange_id))?;
         let sell_token_id = get_exchange_token_id(manager, &self.token_id).unwrap();
 
         let supply = 1_000_000_000_000_000_000_i64;
@@ -503,6 +522,7 @@ impl BuiltinContractExecutorExt for contract_pb::ExchangeTransactionContract {
             .put_key(keys::Account(owner_addr), owner_acct)
             .map_err(|_| "db insert error")?;
 
+        ctx.exchange_received_amount = buy_token_amount;
         Ok(TransactionResult::success())
     }
 }
@@ -532,10 +552,10 @@ fn exchange(mut supply: i64, sell_balance: i64, buy_balance: i64, sell_amount: i
 fn get_exchange_token_id(manager: &Manager, token_id: &str) -> Result<i64, String> {
     if token_id == "_" {
         Ok(0)
-    } else if manager.state_db.must_get(&keys::ChainParameter::AllowSameTokenName) != 0 {
+    } else if manager.state_db.must_get(&keys::ChainParameter::AllowSameTokenName)? != 0 {
         token_id.parse().map_err(|_| "invalid token id".into())
     } else {
-        find_asset_by_name(manager, &token_id)
+        find_asset_by_name(manager, &token_id)?
             .map(|asset| asset.id)
             .ok_or_else(|| "invalid token name".into())
     }
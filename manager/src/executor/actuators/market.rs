@@ -0,0 +1,847 @@
+//! Market, the order book DEX since 4.1.
+//!
+//! Orders of a token pair are grouped by price, and matched FIFO within the same price.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+use ::keys::Address;
+use crypto::keccak256;
+use primitive_types::H256;
+use proto::chain::transaction::Result as TransactionResult;
+use proto::contract as contract_pb;
+use proto::state::{market_order::State as OrderState, MarketAccountOrders, MarketOrder};
+use state::keys;
+
+use super::super::{ExecutionError, TransactionContext};
+use super::BuiltinContractExecutorExt;
+use crate::Manager;
+
+const MARKET_QUANTITY_LIMIT: i64 = 1_000_000_000_000_000;
+const MAX_ACTIVE_ORDER_NUM: usize = 100;
+const MAX_MATCH_NUM: usize = 20;
+
+// Place a sell order, match it against the order book, then put the remain into the book.
+impl BuiltinContractExecutorExt for contract_pb::MarketSellAssetContract {
+    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
+        let state_db = &manager.state_db;
+
+        if state_db.must_get(&keys::ChainParameter::AllowMarketTransaction)? == 0 {
+            return Err("market transaction is not enabled".into());
+        }
+
+        let owner_addr = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
+        let owner_acct = state_db
+            .get(&keys::Account(owner_addr))
+            .map_err(|_| "db query error")?
+            .ok_or(ExecutionError::AccountNotFound(owner_addr))?;
+
+        let sell_token_id = get_market_token_id(manager, &self.sell_token_id)?;
+        let buy_token_id = get_market_token_id(manager, &self.buy_token_id)?;
+        if sell_token_id == buy_token_id {
+            return Err("cannot exchange the same tokens".into());
+        }
+
+        if self.sell_token_quantity <= 0 || self.buy_token_quantity <= 0 {
+            return Err("token quantity must be greater than 0".into());
+        }
+        if self.sell_token_quantity > MARKET_QUANTITY_LIMIT || self.buy_token_quantity > MARKET_QUANTITY_LIMIT {
+            return Err(format!("token quantity must be less than {}", MARKET_QUANTITY_LIMIT).into());
+        }
+
+        let num_active_orders = state_db
+            .get(&keys::MarketAccount(owner_addr))
+            .map_err(|_| "db query error")?
+            .map(|orders| orders.order_ids.len())
+            .unwrap_or(0);
+        if num_active_orders >= MAX_ACTIVE_ORDER_NUM {
+            return Err(format!("maximum number of active orders exceeded, {}", MAX_ACTIVE_ORDER_NUM).into());
+        }
+
+        let fee = self.fee(manager)?;
+        if sell_token_id == 0 {
+            if owner_acct.balance < self.sell_token_quantity + fee {
+                return Err(ExecutionError::InsufficientBalance);
+            }
+        } else {
+            if owner_acct.balance < fee {
+                return Err(ExecutionError::InsufficientBalance);
+            }
+            if owner_acct
+                .token_balance
+                .get(&sell_token_id)
+                .copied()
+                .unwrap_or_default() <
+                self.sell_token_quantity
+            {
+                return Err(ExecutionError::InsufficientBalance);
+            }
+        }
+
+        ctx.contract_fee = fee;
+        Ok(())
+    }
+
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
+        let owner_addr = Address::try_from(&self.owner_address).unwrap();
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr))?;
+
+        let sell_token_id = get_market_token_id(manager, &self.sell_token_id).unwrap();
+        let buy_token_id = get_market_token_id(manager, &self.buy_token_id).unwrap();
+
+        owner_acct.adjust_balance(-ctx.contract_fee).unwrap();
+        if sell_token_id == 0 {
+            owner_acct.adjust_balance(-self.sell_token_quantity).unwrap();
+        } else {
+            owner_acct
+                .adjust_token_balance(sell_token_id, -self.sell_token_quantity)
+                .unwrap();
+        }
+        manager
+            .state_db
+            .put_key(keys::Account(owner_addr), owner_acct)
+            .map_err(|_| "db insert error")?;
+        manager.add_to_blackhole(ctx.contract_fee).unwrap();
+
+        let mut account_orders = manager
+            .state_db
+            .get(&keys::MarketAccount(owner_addr))
+            .map_err(|_| "db query error")?
+            .unwrap_or_default();
+        let order_id = keccak256(
+            &[
+                &self.owner_address[..],
+                &self.sell_token_id,
+                &self.buy_token_id,
+                &account_orders.total_count.to_be_bytes(),
+            ]
+            .concat(),
+        );
+        account_orders.order_ids.push(order_id.as_bytes().to_vec());
+        account_orders.total_count += 1;
+        manager
+            .state_db
+            .put_key(keys::MarketAccount(owner_addr), account_orders)
+            .map_err(|_| "db insert error")?;
+
+        let mut order = MarketOrder {
+            order_id: order_id.as_bytes().to_vec(),
+            owner_address: self.owner_address.clone(),
+            creation_time: manager.latest_block_timestamp()?,
+            sell_token_id,
+            sell_token_quantity: self.sell_token_quantity,
+            buy_token_id,
+            buy_token_quantity: self.buy_token_quantity,
+            sell_token_quantity_remain: self.sell_token_quantity,
+            sell_token_quantity_return: 0,
+            state: OrderState::Active as i32,
+        };
+
+        match_order(manager, &mut order)?;
+        if order.sell_token_quantity_remain != 0 {
+            save_remain_order(manager, &order)?;
+        }
+        manager
+            .state_db
+            .put_key(keys::MarketOrder(order_id), order)
+            .map_err(|_| "db insert error")?;
+
+        Ok(TransactionResult::success())
+    }
+
+    fn fee(&self, manager: &Manager) -> Result<i64, ExecutionError> {
+        Ok(manager.state_db.must_get(&keys::ChainParameter::MarketSellFee)?)
+    }
+}
+
+// Cancel an active order, return the remain to owner.
+impl BuiltinContractExecutorExt for contract_pb::MarketCancelOrderContract {
+    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
+        let state_db = &manager.state_db;
+
+        if state_db.must_get(&keys::ChainParameter::AllowMarketTransaction)? == 0 {
+            return Err("market transaction is not enabled".into());
+        }
+
+        let owner_addr = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
+        let owner_acct = state_db
+            .get(&keys::Account(owner_addr))
+            .map_err(|_| "db query error")?
+            .ok_or(ExecutionError::AccountNotFound(owner_addr))?;
+
+        if self.order_id.len() != 32 {
+            return Err("invalid order id".into());
+        }
+        let order = state_db
+            .get(&keys::MarketOrder(H256::from_slice(&self.order_id)))
+            .map_err(|_| "db query error")?
+            .ok_or_else(|| "order not found on chain")?;
+        if order.state != OrderState::Active as i32 {
+            return Err("order is not active".into());
+        }
+        if order.owner_address != self.owner_address {
+            return Err("order does not belong to the owner address".into());
+        }
+
+        let fee = self.fee(manager)?;
+        if owner_acct.balance < fee {
+            return Err(ExecutionError::InsufficientBalance);
+        }
+
+        ctx.contract_fee = fee;
+        Ok(())
+    }
+
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
+        let owner_addr = Address::try_from(&self.owner_address).unwrap();
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr))?;
+        owner_acct.adjust_balance(-ctx.contract_fee).unwrap();
+        manager
+            .state_db
+            .put_key(keys::Account(owner_addr), owner_acct)
+            .map_err(|_| "db insert error")?;
+        manager.add_to_blackhole(ctx.contract_fee).unwrap();
+
+        let order_id = H256::from_slice(&self.order_id);
+        let mut order = manager.state_db.must_get(&keys::MarketOrder(order_id))?;
+
+        return_sell_token_remain(manager, &mut order)?;
+        update_order_state(manager, &mut order, OrderState::Canceled)?;
+
+        // Remove from the order book.
+        let (sell_quantity, buy_quantity) = normalize_price(order.sell_token_quantity, order.buy_token_quantity);
+        let price_key = keys::MarketPriceOrders(order.sell_token_id, order.buy_token_id, sell_quantity, buy_quantity);
+        let mut order_ids = manager.state_db.must_get(&price_key)?;
+        order_ids.retain(|id| *id != order_id);
+        if order_ids.is_empty() {
+            manager.state_db.delete_key(&price_key).map_err(|_| "db delete error")?;
+            remove_pair_price(
+                manager,
+                order.sell_token_id,
+                order.buy_token_id,
+                (sell_quantity, buy_quantity),
+            )?;
+        } else {
+            manager
+                .state_db
+                .put_key(price_key, order_ids)
+                .map_err(|_| "db insert error")?;
+        }
+
+        manager
+            .state_db
+            .put_key(keys::MarketOrder(order_id), order)
+            .map_err(|_| "db insert error")?;
+
+        Ok(TransactionResult::success())
+    }
+
+    fn fee(&self, manager: &Manager) -> Result<i64, ExecutionError> {
+        Ok(manager.state_db.must_get(&keys::ChainParameter::MarketCancelFee)?)
+    }
+}
+
+/// Token id in market contracts, `"_"` for TRX, or the asset id in decimal.
+fn get_market_token_id(manager: &Manager, token_id: &[u8]) -> Result<i64, String> {
+    if token_id == b"_" {
+        return Ok(0);
+    }
+    let id = std::str::from_utf8(token_id)
+        .ok()
+        .and_then(|id| id.parse::<i64>().ok())
+        .filter(|id| id.to_string().as_bytes() == token_id)
+        .ok_or_else(|| "invalid token id")?;
+    if manager
+        .state_db
+        .get(&keys::Asset(id))
+        .map_err(|_| "db query error")?
+        .is_none()
+    {
+        return Err(format!("token #{} does not exist", id));
+    }
+    Ok(id)
+}
+
+/// Match the taker order against makers selling the token it buys, best price first.
+fn match_order(manager: &mut Manager, taker: &mut MarketOrder) -> Result<(), String> {
+    let (maker_sell_token_id, maker_buy_token_id) = (taker.buy_token_id, taker.sell_token_id);
+    let taker_price = (taker.sell_token_quantity, taker.buy_token_quantity);
+
+    let pair_key = keys::MarketPairPrices(maker_sell_token_id, maker_buy_token_id);
+    let mut prices = match manager.state_db.get(&pair_key).map_err(|_| "db query error")? {
+        Some(prices) => prices,
+        None => return Ok(()),
+    };
+    let num_prices = prices.len();
+
+    let mut num_matches = 0;
+    while taker.sell_token_quantity_remain != 0 {
+        let maker_price = match prices.first() {
+            Some(&price) if is_price_matched(taker_price, price) => price,
+            _ => break,
+        };
+        let price_key = keys::MarketPriceOrders(maker_sell_token_id, maker_buy_token_id, maker_price.0, maker_price.1);
+        let mut order_ids = manager.state_db.must_get(&price_key)?;
+
+        // Orders of the same price, FIFO.
+        while taker.sell_token_quantity_remain != 0 && !order_ids.is_empty() {
+            let mut maker = manager.state_db.must_get(&keys::MarketOrder(order_ids[0]))?;
+            match_single_order(manager, taker, &mut maker)?;
+            if maker.sell_token_quantity_remain == 0 {
+                order_ids.remove(0);
+            }
+            num_matches += 1;
+            if num_matches > MAX_MATCH_NUM {
+                return Err(format!("too many matches, max is {}", MAX_MATCH_NUM));
+            }
+        }
+
+        if order_ids.is_empty() {
+            manager.state_db.delete_key(&price_key).map_err(|_| "db delete error")?;
+            prices.remove(0);
+        } else {
+            manager
+                .state_db
+                .put_key(price_key, order_ids)
+                .map_err(|_| "db insert error")?;
+        }
+    }
+
+    if prices.is_empty() {
+        manager.state_db.delete_key(&pair_key).map_err(|_| "db delete error")?;
+    } else if prices.len() != num_prices {
+        manager
+            .state_db
+            .put_key(pair_key, prices)
+            .map_err(|_| "db insert error")?;
+    }
+    Ok(())
+}
+
+/// Match the taker order against one maker order, at the maker's price.
+fn match_single_order(manager: &mut Manager, taker: &mut MarketOrder, maker: &mut MarketOrder) -> Result<(), String> {
+    let taker_sell_remain = taker.sell_token_quantity_remain;
+    let maker_sell_remain = maker.sell_token_quantity_remain;
+
+    // taker_buy / taker_sell_remain = maker_sell / maker_buy
+    let taker_buy_quantity =
+        multiply_and_divide(taker_sell_remain, maker.sell_token_quantity, maker.buy_token_quantity);
+    if taker_buy_quantity == 0 {
+        // Too small to buy anything.
+        return_sell_token_remain(manager, taker)?;
+        return update_order_state(manager, taker, OrderState::Inactive);
+    }
+
+    let (taker_receive, maker_receive) = match taker_buy_quantity.cmp(&maker_sell_remain) {
+        Ordering::Less => {
+            maker.sell_token_quantity_remain -= taker_buy_quantity;
+            taker.sell_token_quantity_remain = 0;
+            update_order_state(manager, taker, OrderState::Inactive)?;
+            (taker_buy_quantity, taker_sell_remain)
+        }
+        Ordering::Equal => {
+            maker.sell_token_quantity_remain = 0;
+            update_order_state(manager, maker, OrderState::Inactive)?;
+            taker.sell_token_quantity_remain = 0;
+            update_order_state(manager, taker, OrderState::Inactive)?;
+            (maker_sell_remain, taker_sell_remain)
+        }
+        Ordering::Greater => {
+            let maker_receive =
+                multiply_and_divide(maker_sell_remain, maker.buy_token_quantity, maker.sell_token_quantity);
+            maker.sell_token_quantity_remain = 0;
+            update_order_state(manager, maker, OrderState::Inactive)?;
+            taker.sell_token_quantity_remain -= maker_receive;
+            (maker_sell_remain, maker_receive)
+        }
+    };
+
+    manager
+        .state_db
+        .put_key(keys::MarketOrder(H256::from_slice(&maker.order_id)), maker.clone())
+        .map_err(|_| "db insert error")?;
+
+    add_trx_or_token(manager, &taker.owner_address, taker.buy_token_id, taker_receive)?;
+    add_trx_or_token(manager, &maker.owner_address, maker.buy_token_id, maker_receive)?;
+    Ok(())
+}
+
+/// Put the order into the order book, after all the orders of the same price.
+fn save_remain_order(manager: &mut Manager, order: &MarketOrder) -> Result<(), String> {
+    let price = normalize_price(order.sell_token_quantity, order.buy_token_quantity);
+    let price_key = keys::MarketPriceOrders(order.sell_token_id, order.buy_token_id, price.0, price.1);
+
+    let mut order_ids = match manager.state_db.get(&price_key).map_err(|_| "db query error")? {
+        Some(order_ids) => order_ids,
+        None => {
+            let pair_key = keys::MarketPairPrices(order.sell_token_id, order.buy_token_id);
+            let mut prices = manager
+                .state_db
+                .get(&pair_key)
+                .map_err(|_| "db query error")?
+                .unwrap_or_default();
+            let pos = prices
+                .binary_search_by(|&other| compare_price(other, price))
+                .unwrap_or_else(|pos| pos);
+            prices.insert(pos, price);
+            manager
+                .state_db
+                .put_key(pair_key, prices)
+                .map_err(|_| "db insert error")?;
+            vec![]
+        }
+    };
+    order_ids.push(H256::from_slice(&order.order_id));
+    manager
+        .state_db
+        .put_key(price_key, order_ids)
+        .map_err(|_| "db insert error")?;
+    Ok(())
+}
+
+/// Remove a price without orders from the token pair.
+fn remove_pair_price(
+    manager: &mut Manager,
+    sell_token_id: i64,
+    buy_token_id: i64,
+    price: (i64, i64),
+) -> Result<(), String> {
+    let pair_key = keys::MarketPairPrices(sell_token_id, buy_token_id);
+    let mut prices = manager.state_db.must_get(&pair_key)?;
+    prices.retain(|&other| other != price);
+    if prices.is_empty() {
+        manager.state_db.delete_key(&pair_key).map_err(|_| "db delete error")?;
+    } else {
+        manager
+            .state_db
+            .put_key(pair_key, prices)
+            .map_err(|_| "db insert error")?;
+    }
+    Ok(())
+}
+
+/// Return the unsold tokens to the order owner.
+fn return_sell_token_remain(manager: &mut Manager, order: &mut MarketOrder) -> Result<(), String> {
+    order.sell_token_quantity_return = order.sell_token_quantity_remain;
+    add_trx_or_token(
+        manager,
+        &order.owner_address,
+        order.sell_token_id,
+        order.sell_token_quantity_remain,
+    )?;
+    order.sell_token_quantity_remain = 0;
+    Ok(())
+}
+
+/// Inactive and canceled orders are removed from the owner's active orders.
+fn update_order_state(manager: &mut Manager, order: &mut MarketOrder, state: OrderState) -> Result<(), String> {
+    order.state = state as i32;
+    if state == OrderState::Inactive || state == OrderState::Canceled {
+        let owner_addr = Address::try_from(&order.owner_address).map_err(|_| "invalid owner_address")?;
+        let mut account_orders: MarketAccountOrders = manager.state_db.must_get(&keys::MarketAccount(owner_addr))?;
+        account_orders.order_ids.retain(|id| *id != order.order_id);
+        manager
+            .state_db
+            .put_key(keys::MarketAccount(owner_addr), account_orders)
+            .map_err(|_| "db insert error")?;
+    }
+    Ok(())
+}
+
+fn add_trx_or_token(manager: &mut Manager, owner_address: &[u8], token_id: i64, amount: i64) -> Result<(), String> {
+    let owner_addr = Address::try_from(owner_address).map_err(|_| "invalid owner_address")?;
+    let mut acct = manager.state_db.must_get(&keys::Account(owner_addr))?;
+    if token_id == 0 {
+        acct.adjust_balance(amount)
+    } else {
+        acct.adjust_token_balance(token_id, amount)
+    }
+    .map_err(|_| "balance overflow")?;
+    manager
+        .state_db
+        .put_key(keys::Account(owner_addr), acct)
+        .map_err(|_| "db insert error")?;
+    Ok(())
+}
+
+fn gcd(mut a: i64, mut b: i64) -> i64 {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a
+}
+
+/// Price as `(sell_quantity, buy_quantity)`, divided by GCD.
+fn normalize_price(sell_quantity: i64, buy_quantity: i64) -> (i64, i64) {
+    let d = gcd(sell_quantity, buy_quantity);
+    (sell_quantity / d, buy_quantity / d)
+}
+
+/// Order prices by `buy_quantity / sell_quantity`, lower is better for the taker.
+fn compare_price(a: (i64, i64), b: (i64, i64)) -> Ordering {
+    (a.1 as i128 * b.0 as i128).cmp(&(b.1 as i128 * a.0 as i128))
+}
+
+/// Whether the taker accepts the maker's price, i.e. `taker_sell / taker_buy >= maker_buy / maker_sell`.
+fn is_price_matched(taker: (i64, i64), maker: (i64, i64)) -> bool {
+    taker.0 as i128 * maker.0 as i128 >= taker.1 as i128 * maker.1 as i128
+}
+
+#[inline]
+fn multiply_and_divide(a: i64, b: i64, c: i64) -> i64 {
+    (a as i128 * b as i128 / c as i128) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use chain::IndexedBlockHeader;
+    use config::{Config, GenesisConfig};
+    use proto::state::{Account, Asset};
+    use state::db::StateDB;
+
+    use super::*;
+
+    const TOKEN_ID: i64 = 1_000_001;
+    const INITIAL_BALANCE: i64 = 1_000_000;
+
+    fn new_market_manager() -> (Manager, IndexedBlockHeader) {
+        let config = Config::load_from_str(include_str!("../../../../etc/conf.toml")).unwrap();
+        let genesis_config = GenesisConfig::load_from_str(include_str!("../../../../etc/genesis.json")).unwrap();
+        let mut manager = Manager::with_state_db(StateDB::new_in_memory(), &config, &genesis_config);
+        let header = genesis_config.to_indexed_block().unwrap().header;
+
+        manager
+            .state_db
+            .put_key(keys::ChainParameter::AllowMarketTransaction, 1)
+            .unwrap();
+        manager
+            .state_db
+            .put_key(keys::ChainParameter::MarketSellFee, 0)
+            .unwrap();
+        manager
+            .state_db
+            .put_key(keys::ChainParameter::MarketCancelFee, 0)
+            .unwrap();
+        manager
+            .state_db
+            .put_key(keys::Asset(TOKEN_ID), Asset::default())
+            .unwrap();
+        (manager, header)
+    }
+
+    /// An account with `INITIAL_BALANCE` of both TRX and the token.
+    fn new_account(manager: &mut Manager, n: u8) -> Address {
+        let addr = Address::from_tvm_bytes(&[n; 20]);
+        let mut acct = Account::new(0);
+        acct.balance = INITIAL_BALANCE;
+        acct.token_balance.insert(TOKEN_ID, INITIAL_BALANCE);
+        manager.state_db.put_key(keys::Account(addr), acct).unwrap();
+        addr
+    }
+
+    /// Validate and execute the contract, all changes are discarded on error.
+    fn apply<C: BuiltinContractExecutorExt>(
+        manager: &mut Manager,
+        header: &IndexedBlockHeader,
+        cntr: &C,
+    ) -> Result<(), ExecutionError> {
+        let mut manager = manager.savepoint();
+        let mut ctx = TransactionContext::dummy(header);
+        cntr.validate(&manager, &mut ctx)?;
+        cntr.execute(&mut manager, &mut ctx)?;
+        manager.commit();
+        Ok(())
+    }
+
+    /// Place a sell order, returns the order id.
+    fn sell(
+        manager: &mut Manager,
+        header: &IndexedBlockHeader,
+        owner: Address,
+        (sell_token_id, sell_token_quantity): (&[u8], i64),
+        (buy_token_id, buy_token_quantity): (&[u8], i64),
+    ) -> Result<H256, ExecutionError> {
+        let total_count = manager
+            .state_db
+            .get(&keys::MarketAccount(owner))
+            .unwrap()
+            .map(|orders| orders.total_count)
+            .unwrap_or(0);
+        let cntr = contract_pb::MarketSellAssetContract {
+            owner_address: owner.as_bytes().to_vec(),
+            sell_token_id: sell_token_id.to_vec(),
+            sell_token_quantity,
+            buy_token_id: buy_token_id.to_vec(),
+            buy_token_quantity,
+        };
+        apply(manager, header, &cntr)?;
+        Ok(keccak256(
+            &[
+                owner.as_bytes(),
+                sell_token_id,
+                buy_token_id,
+                &total_count.to_be_bytes(),
+            ]
+            .concat(),
+        ))
+    }
+
+    fn cancel(
+        manager: &mut Manager,
+        header: &IndexedBlockHeader,
+        owner: Address,
+        order_id: H256,
+    ) -> Result<(), ExecutionError> {
+        let cntr = contract_pb::MarketCancelOrderContract {
+            owner_address: owner.as_bytes().to_vec(),
+            order_id: order_id.as_bytes().to_vec(),
+        };
+        apply(manager, header, &cntr)
+    }
+
+    fn balances(manager: &Manager, addr: Address) -> (i64, i64) {
+        let acct = manager.state_db.must_get(&keys::Account(addr)).unwrap();
+        (acct.balance, acct.token_balance.get(&TOKEN_ID).copied().unwrap_or(0))
+    }
+
+    fn active_orders(manager: &Manager, addr: Address) -> (Vec<H256>, i64) {
+        let orders = manager.state_db.must_get(&keys::MarketAccount(addr)).unwrap();
+        let order_ids = orders.order_ids.iter().map(|id| H256::from_slice(id)).collect();
+        (order_ids, orders.total_count)
+    }
+
+    fn price_orders(manager: &Manager, sell_token_id: i64, buy_token_id: i64, price: (i64, i64)) -> Option<Vec<H256>> {
+        manager
+            .state_db
+            .get(&keys::MarketPriceOrders(sell_token_id, buy_token_id, price.0, price.1))
+            .unwrap()
+    }
+
+    fn pair_prices(manager: &Manager, sell_token_id: i64, buy_token_id: i64) -> Option<Vec<(i64, i64)>> {
+        manager
+            .state_db
+            .get(&keys::MarketPairPrices(sell_token_id, buy_token_id))
+            .unwrap()
+    }
+
+    fn order(manager: &Manager, order_id: H256) -> MarketOrder {
+        manager.state_db.must_get(&keys::MarketOrder(order_id)).unwrap()
+    }
+
+    #[test]
+    fn test_market_sell_and_cancel() {
+        let (mut manager, header) = new_market_manager();
+        let maker = new_account(&mut manager, 1);
+
+        // Sell 100 tokens for 300 TRX, price is normalized to (1, 3).
+        let order_id = sell(&mut manager, &header, maker, (b"1000001", 100), (b"_", 300)).unwrap();
+        assert_eq!(balances(&manager, maker), (INITIAL_BALANCE, INITIAL_BALANCE - 100));
+        assert_eq!(active_orders(&manager, maker), (vec![order_id], 1));
+        assert_eq!(pair_prices(&manager, TOKEN_ID, 0), Some(vec![(1, 3)]));
+        assert_eq!(price_orders(&manager, TOKEN_ID, 0, (1, 3)), Some(vec![order_id]));
+        let placed = order(&manager, order_id);
+        assert_eq!(placed.state, OrderState::Active as i32);
+        assert_eq!(placed.sell_token_quantity_remain, 100);
+
+        // Only the owner can cancel.
+        let other = new_account(&mut manager, 2);
+        assert!(cancel(&mut manager, &header, other, order_id).is_err());
+
+        cancel(&mut manager, &header, maker, order_id).unwrap();
+        assert_eq!(balances(&manager, maker), (INITIAL_BALANCE, INITIAL_BALANCE));
+        assert_eq!(active_orders(&manager, maker), (vec![], 1));
+        assert_eq!(pair_prices(&manager, TOKEN_ID, 0), None);
+        assert_eq!(price_orders(&manager, TOKEN_ID, 0, (1, 3)), None);
+        let canceled = order(&manager, order_id);
+        assert_eq!(canceled.state, OrderState::Canceled as i32);
+        assert_eq!(canceled.sell_token_quantity_remain, 0);
+        assert_eq!(canceled.sell_token_quantity_return, 100);
+
+        assert!(cancel(&mut manager, &header, maker, order_id).is_err());
+    }
+
+    #[test]
+    fn test_market_match_partial_and_exact() {
+        let (mut manager, header) = new_market_manager();
+        let maker = new_account(&mut manager, 1);
+        let taker1 = new_account(&mut manager, 2);
+        let taker2 = new_account(&mut manager, 3);
+
+        let maker_order_id = sell(&mut manager, &header, maker, (b"1000001", 100), (b"_", 300)).unwrap();
+
+        // Partial: the taker buys 50 of the 100 tokens.
+        let taker_order_id = sell(&mut manager, &header, taker1, (b"_", 150), (b"1000001", 50)).unwrap();
+        assert_eq!(
+            balances(&manager, maker),
+            (INITIAL_BALANCE + 150, INITIAL_BALANCE - 100)
+        );
+        assert_eq!(
+            balances(&manager, taker1),
+            (INITIAL_BALANCE - 150, INITIAL_BALANCE + 50)
+        );
+        assert_eq!(active_orders(&manager, maker), (vec![maker_order_id], 1));
+        assert_eq!(active_orders(&manager, taker1), (vec![], 1));
+        assert_eq!(pair_prices(&manager, TOKEN_ID, 0), Some(vec![(1, 3)]));
+        assert_eq!(price_orders(&manager, TOKEN_ID, 0, (1, 3)), Some(vec![maker_order_id]));
+        // The fully matched taker never enters the order book.
+        assert_eq!(pair_prices(&manager, 0, TOKEN_ID), None);
+        assert_eq!(order(&manager, maker_order_id).sell_token_quantity_remain, 50);
+        let taker_order = order(&manager, taker_order_id);
+        assert_eq!(taker_order.state, OrderState::Inactive as i32);
+        assert_eq!(taker_order.sell_token_quantity_remain, 0);
+
+        // Exact: the taker buys the remaining 50 tokens, both orders are done.
+        let taker_order_id = sell(&mut manager, &header, taker2, (b"_", 150), (b"1000001", 50)).unwrap();
+        assert_eq!(
+            balances(&manager, maker),
+            (INITIAL_BALANCE + 300, INITIAL_BALANCE - 100)
+        );
+        assert_eq!(
+            balances(&manager, taker2),
+            (INITIAL_BALANCE - 150, INITIAL_BALANCE + 50)
+        );
+        assert_eq!(active_orders(&manager, maker), (vec![], 1));
+        assert_eq!(active_orders(&manager, taker2), (vec![], 1));
+        assert_eq!(pair_prices(&manager, TOKEN_ID, 0), None);
+        assert_eq!(price_orders(&manager, TOKEN_ID, 0, (1, 3)), None);
+        assert_eq!(pair_prices(&manager, 0, TOKEN_ID), None);
+        let maker_order = order(&manager, maker_order_id);
+        assert_eq!(maker_order.state, OrderState::Inactive as i32);
+        assert_eq!(maker_order.sell_token_quantity_remain, 0);
+        assert_eq!(order(&manager, taker_order_id).state, OrderState::Inactive as i32);
+
+        // Nothing left to cancel.
+        assert!(cancel(&mut manager, &header, maker, maker_order_id).is_err());
+    }
+
+    #[test]
+    fn test_market_match_multiple_prices() {
+        let (mut manager, header) = new_market_manager();
+        let maker1 = new_account(&mut manager, 1);
+        let maker2 = new_account(&mut manager, 2);
+        let taker = new_account(&mut manager, 3);
+
+        // Placed in random price order, sorted by price in the book.
+        let order_id_at_4 = sell(&mut manager, &header, maker1, (b"1000001", 100), (b"_", 400)).unwrap();
+        let order_id_at_2 = sell(&mut manager, &header, maker1, (b"1000001", 100), (b"_", 200)).unwrap();
+        let order_id_at_3 = sell(&mut manager, &header, maker2, (b"1000001", 100), (b"_", 300)).unwrap();
+        assert_eq!(pair_prices(&manager, TOKEN_ID, 0), Some(vec![(1, 2), (1, 3), (1, 4)]));
+        assert_eq!(active_orders(&manager, maker1), (vec![order_id_at_4, order_id_at_2], 2));
+
+        // Buy 200 tokens at most 3 TRX each, takes the orders at 2 and 3, the remain goes into the book.
+        let taker_order_id = sell(&mut manager, &header, taker, (b"_", 600), (b"1000001", 200)).unwrap();
+        assert_eq!(
+            balances(&manager, maker1),
+            (INITIAL_BALANCE + 200, INITIAL_BALANCE - 200)
+        );
+        assert_eq!(
+            balances(&manager, maker2),
+            (INITIAL_BALANCE + 300, INITIAL_BALANCE - 100)
+        );
+        assert_eq!(
+            balances(&manager, taker),
+            (INITIAL_BALANCE - 600, INITIAL_BALANCE + 200)
+        );
+        assert_eq!(active_orders(&manager, maker1), (vec![order_id_at_4], 2));
+        assert_eq!(active_orders(&manager, maker2), (vec![], 1));
+        assert_eq!(active_orders(&manager, taker), (vec![taker_order_id], 1));
+
+        assert_eq!(pair_prices(&manager, TOKEN_ID, 0), Some(vec![(1, 4)]));
+        assert_eq!(price_orders(&manager, TOKEN_ID, 0, (1, 2)), None);
+        assert_eq!(price_orders(&manager, TOKEN_ID, 0, (1, 3)), None);
+        assert_eq!(price_orders(&manager, TOKEN_ID, 0, (1, 4)), Some(vec![order_id_at_4]));
+        assert_eq!(pair_prices(&manager, 0, TOKEN_ID), Some(vec![(3, 1)]));
+        assert_eq!(price_orders(&manager, 0, TOKEN_ID, (3, 1)), Some(vec![taker_order_id]));
+
+        assert_eq!(order(&manager, order_id_at_2).state, OrderState::Inactive as i32);
+        assert_eq!(order(&manager, order_id_at_3).state, OrderState::Inactive as i32);
+        let taker_order = order(&manager, taker_order_id);
+        assert_eq!(taker_order.state, OrderState::Active as i32);
+        assert_eq!(taker_order.sell_token_quantity_remain, 100);
+
+        // Cancel returns the unsold TRX.
+        cancel(&mut manager, &header, taker, taker_order_id).unwrap();
+        assert_eq!(
+            balances(&manager, taker),
+            (INITIAL_BALANCE - 500, INITIAL_BALANCE + 200)
+        );
+        assert_eq!(active_orders(&manager, taker), (vec![], 1));
+        assert_eq!(pair_prices(&manager, 0, TOKEN_ID), None);
+        assert_eq!(price_orders(&manager, 0, TOKEN_ID, (3, 1)), None);
+    }
+
+    #[test]
+    fn test_market_max_match_num() {
+        let (mut manager, header) = new_market_manager();
+        let maker = new_account(&mut manager, 1);
+        let taker = new_account(&mut manager, 2);
+
+        let maker_order_ids: Vec<_> = (0..MAX_MATCH_NUM + 1)
+            .map(|_| sell(&mut manager, &header, maker, (b"1000001", 1), (b"_", 1)).unwrap())
+            .collect();
+        assert_eq!(
+            price_orders(&manager, TOKEN_ID, 0, (1, 1)),
+            Some(maker_order_ids.clone())
+        );
+
+        // One match too many, the whole transaction fails.
+        let num = MAX_MATCH_NUM as i64 + 1;
+        let err = sell(&mut manager, &header, taker, (b"_", num), (b"1000001", num)).unwrap_err();
+        assert!(err.to_string().contains("too many matches"));
+        assert_eq!(balances(&manager, taker), (INITIAL_BALANCE, INITIAL_BALANCE));
+        assert_eq!(manager.state_db.get(&keys::MarketAccount(taker)).unwrap(), None);
+        assert_eq!(
+            price_orders(&manager, TOKEN_ID, 0, (1, 1)),
+            Some(maker_order_ids.clone())
+        );
+
+        let num = MAX_MATCH_NUM as i64;
+        sell(&mut manager, &header, taker, (b"_", num), (b"1000001", num)).unwrap();
+        assert_eq!(
+            balances(&manager, taker),
+            (INITIAL_BALANCE - num, INITIAL_BALANCE + num)
+        );
+        assert_eq!(
+            balances(&manager, maker),
+            (INITIAL_BALANCE + num, INITIAL_BALANCE - num - 1)
+        );
+        assert_eq!(
+            active_orders(&manager, maker),
+            (maker_order_ids[MAX_MATCH_NUM..].to_vec(), num + 1)
+        );
+        assert_eq!(active_orders(&manager, taker), (vec![], 1));
+        assert_eq!(pair_prices(&manager, TOKEN_ID, 0), Some(vec![(1, 1)]));
+        assert_eq!(
+            price_orders(&manager, TOKEN_ID, 0, (1, 1)),
+            Some(maker_order_ids[MAX_MATCH_NUM..].to_vec())
+        );
+    }
+
+    #[test]
+    fn test_market_price() {
+        assert_eq!(normalize_price(300, 1200), (1, 4));
+        assert_eq!(normalize_price(7, 5), (7, 5));
+
+        // Selling A for 2 TRX each is better for a TRX seller than 3 TRX each.
+        let mut prices = vec![(1, 3), (1, 2), (2, 5)];
+        prices.sort_by(|&a, &b| compare_price(a, b));
+        assert_eq!(prices, vec![(1, 2), (2, 5), (1, 3)]);
+
+        // Taker sells 10 TRX for 4 A, at most 2.5 TRX each.
+        assert!(is_price_matched((10, 4), (1, 2)));
+        assert!(is_price_matched((10, 4), (2, 5)));
+        assert!(!is_price_matched((10, 4), (1, 3)));
+
+        assert_eq!(multiply_and_divide(i64::MAX, 3, 4), (i64::MAX as i128 * 3 / 4) as i64);
+    }
+}
@@ -10,15 +10,16 @@ use proto::state::Account;
 use state::keys;
 
 use super::Manager;
-use super::TransactionContext;
+use super::{ExecutionError, TransactionContext};
 
 mod account;
 pub mod asset;
 mod exchange;
+mod market;
 mod proposal;
 mod resource;
 #[cfg(feature = "nile")]
-mod shielded;
+pub mod shielded;
 pub mod smart_contract;
 mod transfer;
 mod witness;
@@ -49,48 +50,59 @@ pub trait BuiltinContractExecutorExt: BuiltinContractExt {
         recover_addrs: Vec<Address>,
         manager: &Manager,
         ctx: &mut TransactionContext,
-    ) -> Result<(), String> {
-        let owner_address = Address::try_from(self.owner_address()).map_err(|_| "invalid owner_address")?;
-        let maybe_acct = manager
-            .state()
-            .get(&keys::Account(owner_address))
-            .map_err(|_| "db query error")?;
-        if maybe_acct.is_none() {
-            return Err("owner account not exists".into());
-        }
-        let acct = maybe_acct.unwrap();
-        let operation_mask = Some(self.type_code() as i32);
-        let has_multi = recover_addrs.len() > 1;
-
-        let allow_multisig = manager.state().must_get(&keys::ChainParameter::AllowMultisig) != 0;
-        validate_multisig(
-            owner_address,
-            acct,
-            permission_id,
-            recover_addrs,
-            operation_mask,
-            allow_multisig,
-        )?;
-        if has_multi {
-            ctx.multisig_fee = manager.state().must_get(&keys::ChainParameter::MultisigFee);
-        }
-        Ok(())
+    ) -> Result<(), ExecutionError> {
+        validate_owner_signature(self, permission_id, recover_addrs, manager, ctx)
     }
 
-    fn validate(&self, _manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, _manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         Ok(())
     }
 
-    // TODO: for now, use String as Error type
-    fn execute(&self, _manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        _manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         unimplemented!("TODO: support builtin contract type {:?}", self.type_code())
     }
 
     /// Extra fee paid for specific type of builtin contract. Like asset issue, account permission update.
     #[inline]
-    fn fee(&self, _manager: &Manager) -> i64 {
-        0
+    fn fee(&self, _manager: &Manager) -> Result<i64, ExecutionError> {
+        Ok(0)
+    }
+}
+
+/// Validate signatures against permissions of the owner account, and the multisig fee.
+pub fn validate_owner_signature<C: BuiltinContractExt>(
+    cntr: &C,
+    permission_id: i32,
+    recover_addrs: Vec<Address>,
+    manager: &Manager,
+    ctx: &mut TransactionContext,
+) -> Result<(), ExecutionError> {
+    let owner_address = Address::try_from(cntr.owner_address()).map_err(|_| "invalid owner_address")?;
+    let acct = manager
+        .state()
+        .get(&keys::Account(owner_address))?
+        .ok_or(ExecutionError::AccountNotFound(owner_address))?;
+    let operation_mask = Some(cntr.type_code() as i32);
+    let has_multi = recover_addrs.len() > 1;
+
+    let allow_multisig = manager.state().must_get(&keys::ChainParameter::AllowMultisig)? != 0;
+    validate_multisig(
+        owner_address,
+        acct,
+        permission_id,
+        recover_addrs,
+        operation_mask,
+        allow_multisig,
+    )
+    .map_err(|reason| ExecutionError::PermissionDenied { reason })?;
+    if has_multi {
+        ctx.multisig_fee = manager.state().must_get(&keys::ChainParameter::MultisigFee)?;
     }
+    Ok(())
 }
 
 /// Validate a multisig.
@@ -238,11 +250,14 @@ impl_contract_ext_for!(ExchangeCreateContract);
 impl_contract_ext_for!(ExchangeInjectContract);
 impl_contract_ext_for!(ExchangeWithdrawContract);
 impl_contract_ext_for!(ExchangeTransactionContract);
+impl_contract_ext_for!(MarketSellAssetContract);
+impl_contract_ext_for!(MarketCancelOrderContract);
 
 #[cfg(feature = "nile")]
 impl BuiltinContractExt for ::proto::contract::ShieldedTransferContract {
+    /// Empty when transferring from shielded notes.
     fn owner_address(&self) -> &[u8] {
-        &[]
+        &self.transparent_from_address
     }
     fn type_code(&self) -> ContractType {
         ContractType::ShieldedTransferContract
@@ -9,11 +9,11 @@ use state::keys;
 
 use super::super::super::governance::proposal::ProposalUtil;
 use crate::Manager;
-use super::super::TransactionContext;
+use super::super::{ExecutionError, TransactionContext};
 use super::BuiltinContractExecutorExt;
 
 impl BuiltinContractExecutorExt for contract_pb::ProposalCreateContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
 
         // NOTE: witness implies account
@@ -36,14 +36,18 @@ impl BuiltinContractExecutorExt for contract_pb::ProposalCreateContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).unwrap();
 
-        let proposal_id = manager.state_db.must_get(&keys::DynamicProperty::LatestProposalId) + 1;
-        let now = manager.latest_block_timestamp();
+        let proposal_id = manager.state_db.must_get(&keys::DynamicProperty::LatestProposalId)? + 1;
+        let now = manager.latest_block_timestamp()?;
         let expiration_time = {
-            let maintenance_interval = manager.state_db.must_get(&keys::ChainParameter::MaintenanceInterval);
-            let current_maintenance_ts = manager.state_db.must_get(&keys::DynamicProperty::NextMaintenanceTime);
+            let maintenance_interval = manager.state_db.must_get(&keys::ChainParameter::MaintenanceInterval)?;
+            let current_maintenance_ts = manager.state_db.must_get(&keys::DynamicProperty::NextMaintenanceTime)?;
             let offset_now = now + manager.config.chain.proposal_expiration_duration;
             let round = (offset_now - current_maintenance_ts) / maintenance_interval;
             current_maintenance_ts + (round + 1) * maintenance_interval
@@ -72,7 +76,7 @@ impl BuiltinContractExecutorExt for contract_pb::ProposalCreateContract {
 }
 
 impl BuiltinContractExecutorExt for contract_pb::ProposalApproveContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
 
         // NOTE: witness implies account, so no need to check account
@@ -89,7 +93,7 @@ impl BuiltinContractExecutorExt for contract_pb::ProposalApproveContract {
             .get(&keys::Proposal(self.proposal_id))
             .map_err(|_| "db query error")?;
         if let Some(proposal) = maybe_proposal {
-            if manager.latest_block_timestamp() >= proposal.expiration_time {
+            if manager.latest_block_timestamp()? >= proposal.expiration_time {
                 return Err("proposal has expired".into());
             }
             if proposal.state == ProposalState::Cancelled as i32 {
@@ -105,10 +109,14 @@ impl BuiltinContractExecutorExt for contract_pb::ProposalApproveContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).unwrap();
 
-        let mut proposal = manager.state_db.must_get(&keys::Proposal(self.proposal_id));
+        let mut proposal = manager.state_db.must_get(&keys::Proposal(self.proposal_id))?;
 
         if self.is_approval {
             proposal.approver_addresses.push(owner_address.as_bytes().to_vec());
@@ -129,7 +137,7 @@ impl BuiltinContractExecutorExt for contract_pb::ProposalApproveContract {
 }
 
 impl BuiltinContractExecutorExt for contract_pb::ProposalDeleteContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
 
         // NOTE: Proposal creator implies a witness. No need to check others.
@@ -143,21 +151,27 @@ impl BuiltinContractExecutorExt for contract_pb::ProposalDeleteContract {
             return Err(format!(
                 "proposal #{} is not proposed by {}",
                 proposal.proposal_id, owner_address
-            ));
+            )
+            .into());
         }
         // NOTE: Pending implies not-expired, not-cancelled
         if proposal.state != ProposalState::Pending as i32 {
             return Err(format!(
                 "proposal #{} is not in pending state(expired or cancelled)",
                 proposal.proposal_id
-            ));
+            )
+            .into());
         }
 
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
-        let mut proposal = manager.state_db.must_get(&keys::Proposal(self.proposal_id));
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
+        let mut proposal = manager.state_db.must_get(&keys::Proposal(self.proposal_id))?;
         proposal.state = ProposalState::Cancelled as _;
         manager
             .state_db
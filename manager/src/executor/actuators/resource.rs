@@ -11,18 +11,18 @@ use state::keys;
 
 use super::super::super::governance::reward::RewardController;
 use crate::Manager;
-use super::super::TransactionContext;
+use super::super::{ExecutionError, TransactionContext};
 use super::BuiltinContractExecutorExt;
 
 impl BuiltinContractExecutorExt for contract_pb::FreezeBalanceContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
         let owner_acct = state_db
             .get(&keys::Account(owner_address))
             .map_err(|_| "error while querying db")?
-            .ok_or_else(|| "owner account is not on chain")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_address))?;
 
         if self.frozen_balance < 1_000_000 {
             return Err("frozen balance must be greater than 1_TRX".into());
@@ -31,7 +31,8 @@ impl BuiltinContractExecutorExt for contract_pb::FreezeBalanceContract {
             return Err(format!(
                 "insufficient frozen balance, balance={}, required={}",
                 owner_acct.balance, self.frozen_balance
-            ));
+            )
+            .into());
         }
 
         // TODO: handle block.checkFrozenTime config
@@ -42,7 +43,8 @@ impl BuiltinContractExecutorExt for contract_pb::FreezeBalanceContract {
                 "frozen duration must be in range [{}, {}]",
                 constants::MIN_NUM_OF_FROZEN_DAYS_FOR_RESOURCE,
                 constants::MAX_NUM_OF_FROZEN_DAYS_FOR_RESOURCE
-            ));
+            )
+            .into());
         }
 
         if ResourceCode::from_i32(self.resource).is_none() {
@@ -50,7 +52,10 @@ impl BuiltinContractExecutorExt for contract_pb::FreezeBalanceContract {
         }
 
         if !self.receiver_address.is_empty() &&
-            manager.state_db.must_get(&keys::ChainParameter::AllowDelegateResource) == 1
+            manager
+                .state_db
+                .must_get(&keys::ChainParameter::AllowDelegateResource)? ==
+                1
         {
             if self.receiver_address == self.owner_address {
                 return Err("the owner and receiver address cannot be the same".into());
@@ -60,11 +65,11 @@ impl BuiltinContractExecutorExt for contract_pb::FreezeBalanceContract {
             let recv_acct = state_db
                 .get(&keys::Account(receiver_address))
                 .map_err(|_| "error while querying db")?
-                .ok_or_else(|| "receiver account is not on chain")?;
+                .ok_or(ExecutionError::AccountNotFound(receiver_address))?;
 
             if manager
                 .state_db
-                .must_get(&keys::ChainParameter::AllowTvmConstantinopleUpgrade) !=
+                .must_get(&keys::ChainParameter::AllowTvmConstantinopleUpgrade)? !=
                 0 &&
                 recv_acct.r#type == AccountType::Contract as i32
             {
@@ -77,12 +82,16 @@ impl BuiltinContractExecutorExt for contract_pb::FreezeBalanceContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         const DAY_IN_MS: i64 = 86_400_000;
 
         let owner_addr = Address::try_from(&self.owner_address).unwrap();
 
-        let now = manager.latest_block_timestamp();
+        let now = manager.latest_block_timestamp()?;
         let duration = self.frozen_duration * DAY_IN_MS;
         let expire_time = now + duration;
 
@@ -90,7 +99,11 @@ impl BuiltinContractExecutorExt for contract_pb::FreezeBalanceContract {
         // Avoid using receiver_address before AllowDelegateResource.
         //
         // See-also: https://github.com/opentron/opentron/issues/44
-        let maybe_recv_addr = if manager.state_db.must_get(&keys::ChainParameter::AllowDelegateResource) != 0 {
+        let maybe_recv_addr = if manager
+            .state_db
+            .must_get(&keys::ChainParameter::AllowDelegateResource)? !=
+            0
+        {
             Address::try_from(&self.receiver_address).ok()
         } else {
             None
@@ -117,21 +130,24 @@ impl BuiltinContractExecutorExt for contract_pb::FreezeBalanceContract {
 
 // Unfreeze and get frozen amount back. Will also remove all votes.
 impl BuiltinContractExecutorExt for contract_pb::UnfreezeBalanceContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
         let owner_addr = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
         let owner_acct = state_db
             .get(&keys::Account(owner_addr))
             .map_err(|_| "error while querying db")?
-            .ok_or_else(|| "owner account is not on chain")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_addr))?;
 
         let res_type = ResourceCode::from_i32(self.resource).ok_or("invalid resource type")?;
 
-        let now = manager.latest_block_timestamp();
+        let now = manager.latest_block_timestamp()?;
 
         if !self.receiver_address.is_empty() &&
-            manager.state_db.must_get(&keys::ChainParameter::AllowDelegateResource) != 0
+            manager
+                .state_db
+                .must_get(&keys::ChainParameter::AllowDelegateResource)? !=
+                0
         {
             if self.owner_address == self.receiver_address {
                 return Err("the owner and receiver address cannot be the same".into());
@@ -140,7 +156,7 @@ impl BuiltinContractExecutorExt for contract_pb::UnfreezeBalanceContract {
             let recv_acct = state_db
                 .get(&keys::Account(recv_addr))
                 .map_err(|_| "error while querying db")?
-                .ok_or_else(|| "receiver account is not on chain")?;
+                .ok_or(ExecutionError::AccountNotFound(recv_addr))?;
 
             let del = manager
                 .state_db
@@ -150,11 +166,11 @@ impl BuiltinContractExecutorExt for contract_pb::UnfreezeBalanceContract {
 
             let allow_tvm_constantinople = manager
                 .state_db
-                .must_get(&keys::ChainParameter::AllowTvmConstantinopleUpgrade) !=
+                .must_get(&keys::ChainParameter::AllowTvmConstantinopleUpgrade)? !=
                 0;
             let allow_tvm_solidity059 = manager
                 .state_db
-                .must_get(&keys::ChainParameter::AllowTvmSolidity059Upgrade) !=
+                .must_get(&keys::ChainParameter::AllowTvmSolidity059Upgrade)? !=
                 0;
 
             // TODO: refactor logic
@@ -198,7 +214,7 @@ impl BuiltinContractExecutorExt for contract_pb::UnfreezeBalanceContract {
             }
         } else {
             // NOTE: there will be only 1 freeze!
-            let del = state_db.must_get(&keys::ResourceDelegation(owner_addr, owner_addr));
+            let del = state_db.must_get(&keys::ResourceDelegation(owner_addr, owner_addr))?;
             match res_type {
                 ResourceCode::Bandwidth => {
                     // NOTE: FrozenCount is not checked
@@ -223,38 +239,45 @@ impl BuiltinContractExecutorExt for contract_pb::UnfreezeBalanceContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_addr = Address::try_from(&self.owner_address).unwrap();
 
         // withdrawReward
         RewardController::new(manager).withdraw_reward(owner_addr)?;
 
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr))?;
         let res_type = ResourceCode::from_i32(self.resource).unwrap();
 
         let mut unfrozen_amount = 0;
         if !self.receiver_address.is_empty() &&
-            manager.state_db.must_get(&keys::ChainParameter::AllowDelegateResource) == 1
+            manager
+                .state_db
+                .must_get(&keys::ChainParameter::AllowDelegateResource)? ==
+                1
         {
             // handle delegated resource
             let recv_addr = Address::try_from(&self.receiver_address).unwrap();
             let mut del = manager
                 .state_db
-                .must_get(&keys::ResourceDelegation(owner_addr, recv_addr));
+                .must_get(&keys::ResourceDelegation(owner_addr, recv_addr))?;
 
             unfrozen_amount = del.amount_for_resource(res_type);
             del.reset_resource(res_type);
             owner_acct.delegated_out_amount -= unfrozen_amount;
 
-            let mut recv_acct = manager.state_db.must_get(&keys::Account(recv_addr));
+            let mut recv_acct = manager.state_db.must_get(&keys::Account(recv_addr))?;
 
             let allow_tvm_constantinople = manager
                 .state_db
-                .must_get(&keys::ChainParameter::AllowTvmConstantinopleUpgrade) !=
+                .must_get(&keys::ChainParameter::AllowTvmConstantinopleUpgrade)? !=
                 0;
             let allow_tvm_solidity059 = manager
                 .state_db
-                .must_get(&keys::ChainParameter::AllowTvmSolidity059Upgrade) !=
+                .must_get(&keys::ChainParameter::AllowTvmSolidity059Upgrade)? !=
                 0;
 
             if !allow_tvm_constantinople || recv_acct.r#type != AccountType::Contract as i32 {
@@ -299,7 +322,7 @@ impl BuiltinContractExecutorExt for contract_pb::UnfreezeBalanceContract {
             // handle frozen resource of oneself
             let mut del = manager
                 .state_db
-                .must_get(&keys::ResourceDelegation(owner_addr, owner_addr));
+                .must_get(&keys::ResourceDelegation(owner_addr, owner_addr))?;
             match res_type {
                 ResourceCode::Bandwidth => {
                     // ctx.withdrawal_amount = del.amount_for_bandwidth;
@@ -336,7 +359,7 @@ impl BuiltinContractExecutorExt for contract_pb::UnfreezeBalanceContract {
             ResourceCode::Bandwidth => keys::DynamicProperty::TotalBandwidthWeight,
             ResourceCode::Energy => keys::DynamicProperty::TotalEnergyWeight,
         };
-        let weight = manager.state_db.must_get(&weight_key);
+        let weight = manager.state_db.must_get(&weight_key)?;
         manager
             .state_db
             .put_key(weight_key, weight - unfrozen_amount / 1_000_000)
@@ -350,7 +373,7 @@ impl BuiltinContractExecutorExt for contract_pb::UnfreezeBalanceContract {
         if let Some(votes) = maybe_votes {
             for vote in &votes.votes {
                 let wit_addr = Address::try_from(&vote.vote_address).unwrap();
-                let mut wit = manager.state_db.must_get(&keys::Witness(wit_addr));
+                let mut wit = manager.state_db.must_get(&keys::Witness(wit_addr))?;
                 wit.vote_count -= vote.vote_count;
                 manager
                     .state_db
@@ -450,7 +473,7 @@ fn delegate_resource(
         .put_key(key, delegated)
         .map_err(|_| "db insert error")?;
 
-    let old_total_weight = manager.state_db.must_get(&weight_key);
+    let old_total_weight = manager.state_db.must_get(&weight_key)?;
     manager
         .state_db
         .put_key(weight_key, old_total_weight + amount / 1_000_000)
@@ -460,7 +483,7 @@ fn delegate_resource(
     add_to_delegation_index(manager, from, to)?;
 
     // handle to_account resource
-    let mut to_acct = manager.state_db.must_get(&keys::Account(to));
+    let mut to_acct = manager.state_db.must_get(&keys::Account(to))?;
     match resouce_code {
         ResourceCode::Bandwidth => {
             to_acct.delegated_frozen_amount_for_bandwidth += amount;
@@ -475,7 +498,7 @@ fn delegate_resource(
         .map_err(|_| "db insert error")?;
 
     // handle from_account balance
-    let mut from_acct = manager.state_db.must_get(&keys::Account(from));
+    let mut from_acct = manager.state_db.must_get(&keys::Account(from))?;
     from_acct.delegated_out_amount += amount;
     from_acct.adjust_balance(-amount).unwrap();
     manager
@@ -523,7 +546,7 @@ fn freeze_resource(
         .put_key(key, delegated)
         .map_err(|_| "db insert error")?;
 
-    let old_total_weight = manager.state_db.must_get(&weight_key);
+    let old_total_weight = manager.state_db.must_get(&weight_key)?;
     manager
         .state_db
         .put_key(weight_key, old_total_weight + amount / 1_000_000)
@@ -533,7 +556,7 @@ fn freeze_resource(
     add_to_delegation_index(manager, from, from)?;
 
     // handle account resource
-    let mut from_acct = manager.state_db.must_get(&keys::Account(from));
+    let mut from_acct = manager.state_db.must_get(&keys::Account(from))?;
 
     match resouce_code {
         ResourceCode::Bandwidth => {
@@ -1,68 +1,488 @@
-//! Shielded transfer, only available in nile testnet.
-//! This is a dummy implementation.
+//! Shielded TRC10 transfer, only available in nile testnet.
+//!
+//! Notes are tracked by the note commitment tree, roots of the tree at block ends are valid anchors of spends.
+//! Spent notes are tracked by nullifiers.
 
+use std::collections::HashSet;
 use std::convert::TryFrom;
 
 use ::keys::Address;
-use log::warn;
-use proto::chain::transaction::Result as TransactionResult;
+use chain::{IndexedBlock, IndexedTransaction};
+use primitive_types::H256;
+use prost::Message;
+use proto::chain::{transaction::Contract, transaction::Result as TransactionResult, ContractType};
 use proto::contract as contract_pb;
 use proto::state::Account;
 use state::keys;
+use ztron::shielded::{NoteCommitmentTree, NOTE_CIPHERTEXT_SIZE, OUT_CIPHERTEXT_SIZE};
 
+use super::super::{ExecutionError, TransactionContext};
+use super::{validate_owner_signature, BuiltinContractExecutorExt, BuiltinContractExt};
 use crate::Manager;
-use super::super::TransactionContext;
-use super::BuiltinContractExecutorExt;
 
 /// The TRZ token id.
 const SHIELDED_TOKEN_ID: i64 = 1000016;
 // TODO: This following 2 should be chain parameters. Never mind, this is a dead feature.
 const SHIELDED_TRANSACTION_FEE: i64 = 10_000_000;
 const SHIELDED_TRANSACTION_CREATE_ACCOUNT_FEE: i64 = 10_000_000;
+const MAX_NUM_OF_RECEIVE_DESCRIPTIONS: usize = 2;
 
 impl BuiltinContractExecutorExt for contract_pb::ShieldedTransferContract {
-    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate_signature(
+        &self,
+        permission_id: i32,
+        recover_addrs: Vec<Address>,
+        manager: &Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<(), ExecutionError> {
+        // NOTE: Only transparent sender signs the transaction.
+        if self.transparent_from_address.is_empty() {
+            return Ok(());
+        }
+        validate_owner_signature(self, permission_id, recover_addrs, manager, ctx)
+    }
+
+    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
+        let state_db = &manager.state_db;
+
+        if state_db
+            .get(&keys::ChainParameter::AllowShieldedTransaction)?
+            .unwrap_or(0) ==
+            0
+        {
+            return Err("shielded transaction is not allowed".into());
+        }
+
+        let fee = self.fee(manager)?;
+
+        // checkSender
+        if !self.transparent_from_address.is_empty() && !self.spend_description.is_empty() {
+            return Err("more than one sender".into());
+        }
+        if self.transparent_from_address.is_empty() && self.spend_description.is_empty() {
+            return Err("no sender".into());
+        }
         if !self.transparent_from_address.is_empty() {
-            let fee = self.fee(manager);
-            ctx.contract_fee = fee;
+            let from_addr = Address::try_from(&self.transparent_from_address).map_err(|_| "invalid from address")?;
+            if self.from_amount <= 0 {
+                return Err("from_amount must be greater than 0".into());
+            }
+            let from_acct = state_db
+                .get(&keys::Account(from_addr))?
+                .ok_or(ExecutionError::AccountNotFound(from_addr))?;
+            if from_acct.token_balance.get(&SHIELDED_TOKEN_ID).copied().unwrap_or(0) < self.from_amount {
+                return Err(ExecutionError::InsufficientBalance);
+            }
+        } else if self.from_amount != 0 {
+            return Err("from_amount must be 0 without transparent sender".into());
+        }
+
+        // checkReceiver
+        if self.transparent_to_address.is_empty() && self.receive_description.is_empty() {
+            return Err("no receiver".into());
+        }
+        if self.receive_description.len() > MAX_NUM_OF_RECEIVE_DESCRIPTIONS {
+            return Err("too many receive descriptions".into());
+        }
+        if !self.transparent_to_address.is_empty() {
+            Address::try_from(&self.transparent_to_address).map_err(|_| "invalid to address")?;
+            if self.transparent_to_address == self.transparent_from_address {
+                return Err("cannot transfer to yourself".into());
+            }
+            if self.to_amount <= 0 {
+                return Err("to_amount must be greater than 0".into());
+            }
+        } else if self.to_amount != 0 {
+            return Err("to_amount must be 0 without transparent receiver".into());
+        }
+
+        let mut nullifiers = HashSet::new();
+        for spend in &self.spend_description {
+            if spend.nullifier.len() != 32 || spend.anchor.len() != 32 {
+                return Err("invalid spend description".into());
+            }
+            let nullifier = H256::from_slice(&spend.nullifier);
+            if !nullifiers.insert(nullifier) {
+                return Err("duplicated nullifier in transaction".into());
+            }
+            if state_db.get(&keys::ShieldedNullifier(nullifier))?.is_some() {
+                return Err("note has been spent".into());
+            }
+            if state_db
+                .get(&keys::ShieldedAnchor(H256::from_slice(&spend.anchor)))?
+                .is_none()
+            {
+                return Err("anchor is invalid".into());
+            }
         }
+
+        let mut note_commitments = HashSet::new();
+        for receive in &self.receive_description {
+            if !note_commitments.insert(&receive.note_commitment) {
+                return Err("duplicated note commitment in transaction".into());
+            }
+            if receive.c_enc.len() != NOTE_CIPHERTEXT_SIZE || receive.c_out.len() != OUT_CIPHERTEXT_SIZE {
+                return Err("invalid ciphertext size of receive description".into());
+            }
+        }
+
+        let value_balance = self.value_balance(fee)?;
+        let pool_value = state_db.must_get(&keys::DynamicProperty::TotalShieldedPoolValue)?;
+        if pool_value.checked_sub(value_balance).map(|v| v < 0).unwrap_or(true) {
+            return Err("insufficient shielded pool value".into());
+        }
+
+        ctx.contract_fee = fee;
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
+        let fee = ctx.contract_fee;
+
         if !self.transparent_from_address.is_empty() {
             let from_addr = Address::try_from(&self.transparent_from_address).unwrap();
-            let mut from_acct = manager.state_db.must_get(&keys::Account(from_addr));
-
+            let mut from_acct = manager.state_db.must_get(&keys::Account(from_addr))?;
             from_acct
                 .adjust_token_balance(SHIELDED_TOKEN_ID, -self.from_amount)
-                .unwrap();
+                .map_err(|_| "insufficient shielded token balance")?;
+            manager.state_db.put_key(keys::Account(from_addr), from_acct)?;
+        }
+
+        for spend in &self.spend_description {
+            manager.state_db.put_key(
+                keys::ShieldedNullifier(H256::from_slice(&spend.nullifier)),
+                ctx.transaction_hash,
+            )?;
+        }
+
+        if !self.receive_description.is_empty() {
+            let mut tree = get_note_commitment_tree(manager)?;
+            for receive in &self.receive_description {
+                tree.append(&receive.note_commitment).map_err(|e| e.to_string())?;
+            }
             manager
-                .add_token_to_blackhole(SHIELDED_TOKEN_ID, ctx.contract_fee)
-                .unwrap();
-            ctx.contract_fee = 0; // in TRZ, not TRX
+                .state_db
+                .put_key(keys::ShieldedNoteCommitmentTree, tree.to_bytes())?;
+        }
 
-            manager.state_db.put_key(keys::Account(from_addr), from_acct).unwrap();
-        } else if !self.transparent_to_address.is_empty() {
+        if !self.transparent_to_address.is_empty() {
             let to_addr = Address::try_from(&self.transparent_to_address).unwrap();
-            let maybe_to_acct = manager.state_db.get(&keys::Account(to_addr)).unwrap();
-            let mut to_acct = maybe_to_acct.unwrap_or_else(|| Account::new(manager.latest_block_timestamp()));
-            to_acct.adjust_token_balance(SHIELDED_TOKEN_ID, self.to_amount).unwrap();
-
-            manager.state_db.put_key(keys::Account(to_addr), to_acct).unwrap();
-        } else {
-            warn!("fake handling z to z shielded transfer");
+            let maybe_to_acct = manager.state_db.get(&keys::Account(to_addr))?;
+            let mut to_acct = match maybe_to_acct {
+                Some(acct) => acct,
+                None => Account::new(manager.latest_block_timestamp()?),
+            };
+            to_acct
+                .adjust_token_balance(SHIELDED_TOKEN_ID, self.to_amount)
+                .map_err(|_| "math overflow")?;
+            manager.state_db.put_key(keys::Account(to_addr), to_acct)?;
         }
+
+        let pool_value = manager
+            .state_db
+            .must_get(&keys::DynamicProperty::TotalShieldedPoolValue)?;
+        manager.state_db.put_key(
+            keys::DynamicProperty::TotalShieldedPoolValue,
+            pool_value - self.value_balance(fee)?,
+        )?;
+
+        manager
+            .add_token_to_blackhole(SHIELDED_TOKEN_ID, fee)
+            .map_err(|e| e.to_string())?;
+        ctx.contract_fee = 0; // in TRZ, not TRX
+
         Ok(TransactionResult::success())
     }
 
-    fn fee(&self, manager: &Manager) -> i64 {
+    fn fee(&self, manager: &Manager) -> Result<i64, ExecutionError> {
         if !self.transparent_to_address.is_empty() {
-            let to_addr = Address::try_from(&self.transparent_to_address).unwrap();
-            if manager.state_db.get(&keys::Account(to_addr)).unwrap().is_none() {
-                return SHIELDED_TRANSACTION_CREATE_ACCOUNT_FEE;
+            let to_addr = Address::try_from(&self.transparent_to_address).map_err(|_| "invalid to address")?;
+            if manager.state_db.get(&keys::Account(to_addr))?.is_none() {
+                return Ok(SHIELDED_TRANSACTION_CREATE_ACCOUNT_FEE);
             }
         }
-        SHIELDED_TRANSACTION_FEE
+        Ok(SHIELDED_TRANSACTION_FEE)
+    }
+}
+
+trait ShieldedTransferExt {
+    /// Value leaving the shielded pool.
+    fn value_balance(&self, fee: i64) -> Result<i64, String>;
+
+    /// Signed message of spend authority signatures and the binding signature. Signatures are cleared, and the
+    /// token id is prepended.
+    fn sighash(&self, txn: &IndexedTransaction) -> [u8; 32];
+}
+
+impl ShieldedTransferExt for contract_pb::ShieldedTransferContract {
+    fn value_balance(&self, fee: i64) -> Result<i64, String> {
+        self.to_amount
+            .checked_add(fee)
+            .and_then(|v| v.checked_sub(self.from_amount))
+            .ok_or_else(|| "math overflow".into())
+    }
+
+    fn sighash(&self, txn: &IndexedTransaction) -> [u8; 32] {
+        let mut cntr = self.clone();
+        cntr.binding_signature.clear();
+        for spend in cntr.spend_description.iter_mut() {
+            spend.spend_authority_signature.clear();
+        }
+
+        let mut raw = txn.raw.raw_data.clone().unwrap();
+        raw.contract = Some(Contract {
+            r#type: ContractType::ShieldedTransferContract as i32,
+            parameter: cntr.to_any(),
+            ..Default::default()
+        });
+        let mut buf = Vec::with_capacity(raw.encoded_len());
+        raw.encode(&mut buf).unwrap();
+
+        let token_id_hash = crypto::sha256(SHIELDED_TOKEN_ID.to_string().as_bytes());
+        crypto::sha256(&[token_id_hash.as_bytes(), &buf].concat()).to_fixed_bytes()
+    }
+}
+
+/// Verify zk-SNARK proofs, spend authority signatures and the binding signature. Must be called after `validate`.
+pub fn validate_proofs(
+    cntr: &contract_pb::ShieldedTransferContract,
+    txn: &IndexedTransaction,
+    ctx: &TransactionContext,
+) -> Result<(), String> {
+    let spends = cntr
+        .spend_description
+        .iter()
+        .map(|spend| ztron::shielded::SpendDescription {
+            value_commitment: &spend.value_commitment,
+            anchor: &spend.anchor,
+            nullifier: &spend.nullifier,
+            rk: &spend.rk,
+            zkproof: &spend.zkproof,
+            spend_authority_signature: &spend.spend_authority_signature,
+        })
+        .collect::<Vec<_>>();
+    let receives = cntr
+        .receive_description
+        .iter()
+        .map(|receive| ztron::shielded::ReceiveDescription {
+            value_commitment: &receive.value_commitment,
+            note_commitment: &receive.note_commitment,
+            epk: &receive.epk,
+            zkproof: &receive.zkproof,
+        })
+        .collect::<Vec<_>>();
+
+    ztron::shielded::verify_transfer(
+        &spends,
+        &receives,
+        &cntr.binding_signature,
+        cntr.value_balance(ctx.contract_fee)?,
+        &cntr.sighash(txn),
+    )
+    .map_err(|e| format!("invalid shielded transfer proof: {}", e))
+}
+
+/// Current note commitment tree of shielded TRC10 transfers.
+fn get_note_commitment_tree(manager: &Manager) -> Result<NoteCommitmentTree, String> {
+    match manager.state_db.get(&keys::ShieldedNoteCommitmentTree)? {
+        Some(raw) => NoteCommitmentTree::from_bytes(&raw).map_err(|e| e.to_string()),
+        None => Ok(NoteCommitmentTree::default()),
+    }
+}
+
+/// Save root of the note commitment tree at the end of a block, as a valid anchor of following blocks.
+///
+/// The tree only changes in blocks with shielded transfers.
+pub fn save_shielded_anchor(manager: &mut Manager, block: &IndexedBlock) -> Result<(), String> {
+    let has_shielded_transfer = block.transactions.iter().any(|txn| {
+        txn.raw
+            .raw_data
+            .as_ref()
+            .and_then(|raw| raw.contract.as_ref())
+            .map(|cntr| cntr.r#type == ContractType::ShieldedTransferContract as i32)
+            .unwrap_or(false)
+    });
+    if has_shielded_transfer {
+        let root = H256::from(get_note_commitment_tree(manager)?.root());
+        if manager.state_db.get(&keys::ShieldedAnchor(root))?.is_none() {
+            manager.state_db.put_key(keys::ShieldedAnchor(root), block.number())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chain::IndexedBlockHeader;
+    use config::{Config, GenesisConfig};
+    use proto::chain::transaction::Raw as TransactionRaw;
+    use proto::chain::Transaction;
+    use state::db::StateDB;
+
+    use super::*;
+
+    // A note commitment from nile testnet.
+    const NOTE_COMMITMENT: &str = "05655316a07e6ec8c9769af54ef98b30667bfb6302b32987d552227dae86a087";
+
+    fn new_shielded_manager() -> (Manager, IndexedBlockHeader) {
+        let config = Config::load_from_str(include_str!("../../../../etc/conf.toml")).unwrap();
+        let genesis_config = GenesisConfig::load_from_str(include_str!("../../../../etc/genesis.json")).unwrap();
+        let mut manager = Manager::with_state_db(StateDB::new_in_memory(), &config, &genesis_config);
+        let header = genesis_config.to_indexed_block().unwrap().header;
+
+        manager
+            .state_db
+            .put_key(keys::ChainParameter::AllowShieldedTransaction, 1)
+            .unwrap();
+        manager
+            .state_db
+            .put_key(keys::DynamicProperty::TotalShieldedPoolValue, 0)
+            .unwrap();
+        (manager, header)
+    }
+
+    fn shielded_token_balance(manager: &Manager, addr: Address) -> i64 {
+        let acct = manager.state_db.must_get(&keys::Account(addr)).unwrap();
+        acct.token_balance.get(&SHIELDED_TOKEN_ID).copied().unwrap_or(0)
+    }
+
+    fn pool_value(manager: &Manager) -> i64 {
+        manager
+            .state_db
+            .must_get(&keys::DynamicProperty::TotalShieldedPoolValue)
+            .unwrap()
+    }
+
+    /// Validate and execute the contract, proofs are not verified.
+    fn transfer(
+        manager: &mut Manager,
+        header: &IndexedBlockHeader,
+        cntr: &contract_pb::ShieldedTransferContract,
+    ) -> Result<(), ExecutionError> {
+        let mut manager = manager.savepoint();
+        let mut ctx = TransactionContext::dummy(header);
+        cntr.validate(&manager, &mut ctx)?;
+        cntr.execute(&mut manager, &mut ctx)?;
+        manager.commit();
+        Ok(())
+    }
+
+    /// A block of the shielded transfer, at the given number.
+    fn shielded_block(
+        header: &IndexedBlockHeader,
+        number: i64,
+        cntr: &contract_pb::ShieldedTransferContract,
+    ) -> IndexedBlock {
+        let mut header = header.clone();
+        header.raw.raw_data.as_mut().unwrap().number = number;
+        let txn = Transaction {
+            raw_data: Some(TransactionRaw {
+                contract: Some(Contract {
+                    r#type: ContractType::ShieldedTransferContract as i32,
+                    parameter: cntr.to_any(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        IndexedBlock::new(header, vec![IndexedTransaction::from_raw(txn).unwrap()])
+    }
+
+    fn spend(anchor: H256, nullifier: H256) -> contract_pb::SpendDescription {
+        contract_pb::SpendDescription {
+            anchor: anchor.as_bytes().to_vec(),
+            nullifier: nullifier.as_bytes().to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_shielded_transfer() {
+        let (mut manager, header) = new_shielded_manager();
+        let from_addr = Address::from_tvm_bytes(&[1; 20]);
+        let to_addr = Address::from_tvm_bytes(&[2; 20]);
+        let mut from_acct = Account::new(0);
+        from_acct.token_balance.insert(SHIELDED_TOKEN_ID, 100_000_000);
+        manager.state_db.put_key(keys::Account(from_addr), from_acct).unwrap();
+
+        // Transparent to shielded, 20 TRZ into the pool, 10 TRZ fee.
+        let shield = contract_pb::ShieldedTransferContract {
+            transparent_from_address: from_addr.as_bytes().to_vec(),
+            from_amount: 30_000_000,
+            receive_description: vec![contract_pb::ReceiveDescription {
+                note_commitment: hex::decode(NOTE_COMMITMENT).unwrap(),
+                c_enc: vec![0; NOTE_CIPHERTEXT_SIZE],
+                c_out: vec![0; OUT_CIPHERTEXT_SIZE],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        transfer(&mut manager, &header, &shield).unwrap();
+        assert_eq!(shielded_token_balance(&manager, from_addr), 70_000_000);
+        assert_eq!(pool_value(&manager), 20_000_000);
+        let tree = get_note_commitment_tree(&manager).unwrap();
+        assert_eq!(tree.size(), 1);
+        let anchor = H256::from(tree.root());
+
+        // The new root is an anchor only after the block ends.
+        let nullifier = H256::repeat_byte(0x11);
+        let unshield = contract_pb::ShieldedTransferContract {
+            spend_description: vec![spend(anchor, nullifier)],
+            transparent_to_address: to_addr.as_bytes().to_vec(),
+            to_amount: 5_000_000,
+            ..Default::default()
+        };
+        let err = transfer(&mut manager, &header, &unshield).unwrap_err();
+        assert_eq!(err.to_string(), "anchor is invalid");
+
+        // Blocks without shielded transfers do not save anchors.
+        let empty_block = IndexedBlock::new(header.clone(), vec![]);
+        save_shielded_anchor(&mut manager, &empty_block).unwrap();
+        assert_eq!(manager.state_db.get(&keys::ShieldedAnchor(anchor)).unwrap(), None);
+        save_shielded_anchor(&mut manager, &shielded_block(&header, 1, &shield)).unwrap();
+        assert_eq!(manager.state_db.get(&keys::ShieldedAnchor(anchor)).unwrap(), Some(1));
+        // The first block of an anchor is kept.
+        save_shielded_anchor(&mut manager, &shielded_block(&header, 2, &unshield)).unwrap();
+        assert_eq!(manager.state_db.get(&keys::ShieldedAnchor(anchor)).unwrap(), Some(1));
+
+        // Shielded to transparent, 5 TRZ out of the pool, 10 TRZ fee for creating the receiver.
+        transfer(&mut manager, &header, &unshield).unwrap();
+        assert_eq!(shielded_token_balance(&manager, to_addr), 5_000_000);
+        assert_eq!(pool_value(&manager), 5_000_000);
+        assert!(manager
+            .state_db
+            .get(&keys::ShieldedNullifier(nullifier))
+            .unwrap()
+            .is_some());
+
+        // Double spend of the same note.
+        let err = transfer(&mut manager, &header, &unshield).unwrap_err();
+        assert_eq!(err.to_string(), "note has been spent");
+
+        let mut double_spend = unshield.clone();
+        let nullifier = H256::repeat_byte(0x22);
+        double_spend.spend_description = vec![spend(anchor, nullifier), spend(anchor, nullifier)];
+        let err = transfer(&mut manager, &header, &double_spend).unwrap_err();
+        assert_eq!(err.to_string(), "duplicated nullifier in transaction");
+
+        let mut unknown_anchor = unshield.clone();
+        unknown_anchor.spend_description = vec![spend(H256::repeat_byte(0x33), nullifier)];
+        let err = transfer(&mut manager, &header, &unknown_anchor).unwrap_err();
+        assert_eq!(err.to_string(), "anchor is invalid");
+
+        // 1 TRZ out and 10 TRZ fee, while 5 TRZ left in the pool.
+        let mut underflow = unshield.clone();
+        underflow.spend_description = vec![spend(anchor, nullifier)];
+        underflow.to_amount = 1_000_000;
+        let err = transfer(&mut manager, &header, &underflow).unwrap_err();
+        assert_eq!(err.to_string(), "insufficient shielded pool value");
+        assert_eq!(pool_value(&manager), 5_000_000);
+        assert_eq!(shielded_token_balance(&manager, to_addr), 5_000_000);
+        assert_eq!(manager.state_db.get(&keys::ShieldedNullifier(nullifier)).unwrap(), None);
     }
 }
@@ -18,20 +18,19 @@ use super::super::super::resource::{EnergyProcessor, EnergyUtil};
 use super::super::super::version_fork::ForkController;
 use super::super::super::vm::StateBackend;
 use crate::Manager;
-use super::super::TransactionContext;
+use super::super::{ExecutionError, TransactionContext};
 use super::BuiltinContractExecutorExt;
 
 const MAX_CONTRACT_NAME_LENGTH: usize = 32;
-const MAX_FEE_LIMIT: i64 = 1_000_000_000;
 const MIN_TOKEN_ID: i64 = 1_000_000;
 const SAVE_CODE_ENERGY_PER_BYTE: usize = 200;
 
 // Create a smart contract and deploy it on chain.
 impl BuiltinContractExecutorExt for contract_pb::CreateSmartContract {
-    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
-        if state_db.must_get(&keys::ChainParameter::AllowTvm) == 0 {
+        if state_db.must_get(&keys::ChainParameter::AllowTvm)? == 0 {
             return Err("TVM is disabled".into());
         }
 
@@ -67,7 +66,7 @@ impl BuiltinContractExecutorExt for contract_pb::CreateSmartContract {
 
         let allow_trc10_transfer = manager
             .state_db
-            .must_get(&keys::ChainParameter::AllowTvmTransferTrc10Upgrade) !=
+            .must_get(&keys::ChainParameter::AllowTvmTransferTrc10Upgrade)? !=
             0;
 
         if allow_trc10_transfer {
@@ -76,16 +75,14 @@ impl BuiltinContractExecutorExt for contract_pb::CreateSmartContract {
         }
 
         log::debug!("fee_limit => {}", ctx.fee_limit);
-        if ctx.fee_limit < 0 || ctx.fee_limit > MAX_FEE_LIMIT {
-            return Err("invalid fee_limit".into());
-        }
+        validate_fee_limit(manager, ctx.fee_limit)?;
 
         let maybe_owner_acct = manager
             .state_db
             .get(&keys::Account(owner_address))
             .map_err(|_| "db query error")?;
         if maybe_owner_acct.is_none() {
-            return Err("owner_account not found".into());
+            return Err(ExecutionError::AccountNotFound(owner_address));
         }
         let acct = maybe_owner_acct.unwrap();
 
@@ -106,9 +103,9 @@ impl BuiltinContractExecutorExt for contract_pb::CreateSmartContract {
                 return Err("origin_energy_limit must be greater than 0".into());
             }
 
-            get_account_energy_limit_with_fixed_ratio(manager, &acct, ctx.fee_limit, call_value)
+            get_account_energy_limit_with_fixed_ratio(manager, &acct, ctx.fee_limit, call_value)?
         } else {
-            get_account_energy_limit_with_float_ratio(manager, &acct, ctx.fee_limit, call_value)
+            get_account_energy_limit_with_float_ratio(manager, &acct, ctx.fee_limit, call_value)?
         };
 
         log::debug!("energy_limit => {}", energy_limit);
@@ -117,7 +114,7 @@ impl BuiltinContractExecutorExt for contract_pb::CreateSmartContract {
         // checkTokenValueAndId
         if allow_trc10_transfer {
             // NOTE: also checks allowMultiSig
-            if manager.state_db.must_get(&keys::ChainParameter::AllowMultisig) != 0 {
+            if manager.state_db.must_get(&keys::ChainParameter::AllowMultisig)? != 0 {
                 if call_token_id != 0 && call_token_id <= MIN_TOKEN_ID {
                     return Err("invalid token id range".into());
                 }
@@ -130,12 +127,17 @@ impl BuiltinContractExecutorExt for contract_pb::CreateSmartContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let new_cntr = self.new_contract.as_ref().unwrap();
         let owner_address = Address::try_from(&self.owner_address).unwrap();
         let cntr_address = generate_created_contract_address(&ctx.transaction_hash, &owner_address);
+        ctx.created_contract_address = cntr_address.as_bytes().to_vec();
 
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address))?;
 
         // Routine to handle smart contract creation:
         // . create contract account
@@ -146,19 +148,16 @@ impl BuiltinContractExecutorExt for contract_pb::CreateSmartContract {
         // . save code if after AllowTvmConstantinopleUpgrade
 
         // If contract creation is failed, all creation will be discarded.
-        manager.new_layer();
+        let mut manager = manager.savepoint();
 
-        let mut cntr_acct = Account::new_contract_account(manager.latest_block_timestamp());
+        let mut cntr_acct = Account::new_contract_account(manager.latest_block_timestamp()?);
         let mut cntr = self.new_contract.as_ref().unwrap().clone();
         cntr.contract_address = cntr_address.as_bytes().to_vec();
 
         let call_value = new_cntr.call_value;
         if call_value > 0 {
             if owner_acct.adjust_balance(-call_value).is_err() {
-                return Err(format!(
-                    "insufficient balance, balance={} required={}",
-                    owner_acct.balance, call_value
-                )); // validate error
+                return Err(ExecutionError::InsufficientBalance); // validate error
             }
             cntr_acct.adjust_balance(call_value).unwrap();
         }
@@ -167,7 +166,7 @@ impl BuiltinContractExecutorExt for contract_pb::CreateSmartContract {
                 .adjust_token_balance(self.call_token_id, -self.call_token_value)
                 .is_err()
             {
-                return Err("insufficient token balance".into()); // validate error
+                return Err(ExecutionError::InsufficientBalance); // validate error
             }
             cntr_acct
                 .adjust_token_balance(self.call_token_id, self.call_token_value)
@@ -186,7 +185,7 @@ impl BuiltinContractExecutorExt for contract_pb::CreateSmartContract {
 
         let allow_tvm_constantinople = manager
             .state_db
-            .must_get(&keys::ChainParameter::AllowTvmConstantinopleUpgrade) !=
+            .must_get(&keys::ChainParameter::AllowTvmConstantinopleUpgrade)? !=
             0;
         if !allow_tvm_constantinople {
             let code = legacy_get_runtime_code(&new_cntr.bytecode);
@@ -200,11 +199,11 @@ impl BuiltinContractExecutorExt for contract_pb::CreateSmartContract {
         // execution
         let energy_limit = ctx.energy_limit as usize;
 
-        let upgrade = get_current_tvm_upgrade(manager);
+        let upgrade = get_current_tvm_upgrade(&manager)?;
         let precompile = upgrade.precompile();
         let config = upgrade.to_tvm_config();
 
-        let mut backend = StateBackend::new(owner_address, manager, ctx);
+        let mut backend = StateBackend::new(owner_address, &mut manager, ctx);
         let mut executor = tvm::StackExecutor::new_with_precompile(&backend, energy_limit, &config, precompile);
 
         let vm_ctx = tvm::Context {
@@ -248,11 +247,12 @@ impl BuiltinContractExecutorExt for contract_pb::CreateSmartContract {
                     .put_key(keys::ContractCode(cntr_address), ret_val.clone())
                     .unwrap();
             }
+            manager.commit();
         } else {
             drop(backend);
             drop(applies);
             drop(logs);
-            manager.rollback_layers(1);
+            manager.rollback();
         }
         ctx.result = ret_val;
 
@@ -271,7 +271,7 @@ impl BuiltinContractExecutorExt for contract_pb::CreateSmartContract {
             used_energy,
         );
         // consume energy
-        EnergyProcessor::new(manager).consume(
+        EnergyProcessor::new(&mut manager).consume(
             owner_address,
             owner_address,
             energy_usage,
@@ -292,10 +292,10 @@ impl BuiltinContractExecutorExt for contract_pb::CreateSmartContract {
 
 // Calling smart contract method.
 impl BuiltinContractExecutorExt for contract_pb::TriggerSmartContract {
-    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
-        if state_db.must_get(&keys::ChainParameter::AllowTvm) == 0 {
+        if state_db.must_get(&keys::ChainParameter::AllowTvm)? == 0 {
             return Err("TVM is disabled".into());
         }
 
@@ -317,7 +317,7 @@ impl BuiltinContractExecutorExt for contract_pb::TriggerSmartContract {
         let mut call_token_id = 0_i64;
         let allow_trc10_transfer = manager
             .state_db
-            .must_get(&keys::ChainParameter::AllowTvmTransferTrc10Upgrade) !=
+            .must_get(&keys::ChainParameter::AllowTvmTransferTrc10Upgrade)? !=
             0;
         if allow_trc10_transfer {
             call_token_value = self.call_token_value;
@@ -336,7 +336,7 @@ impl BuiltinContractExecutorExt for contract_pb::TriggerSmartContract {
         // checkTokenValueAndId
         if allow_trc10_transfer {
             // NOTE: also checks allowMultiSig
-            if manager.state_db.must_get(&keys::ChainParameter::AllowMultisig) != 0 {
+            if manager.state_db.must_get(&keys::ChainParameter::AllowMultisig)? != 0 {
                 if call_token_id != 0 && call_token_id <= MIN_TOKEN_ID {
                     return Err("invalid token id range".into());
                 }
@@ -351,22 +351,20 @@ impl BuiltinContractExecutorExt for contract_pb::TriggerSmartContract {
             .get(&keys::ContractCode(cntr_address))
             .map_err(|_| "db query error")?;
         if code.is_some() && !code.as_ref().unwrap().is_empty() {
-            if ctx.fee_limit < 0 || ctx.fee_limit > MAX_FEE_LIMIT {
-                return Err("invalid fee_limit".into());
-            }
+            validate_fee_limit(manager, ctx.fee_limit)?;
 
             // TODO: check constant call
             let caller_acct = manager
                 .state_db
                 .get(&keys::Account(owner_address))
                 .map_err(|_| "db query error")?
-                .ok_or_else(|| "owner account is not on chain")?;
-            let origin_acct = manager.state_db.must_get(&keys::Account(origin_address));
+                .ok_or(ExecutionError::AccountNotFound(owner_address))?;
+            let origin_acct = manager.state_db.must_get(&keys::Account(origin_address))?;
 
             let energy_limit = if owner_address == origin_address {
-                get_account_energy_limit(manager, &caller_acct, ctx.fee_limit, call_value)
+                get_account_energy_limit(manager, &caller_acct, ctx.fee_limit, call_value)?
             } else {
-                get_total_energy_limit(manager, &caller_acct, &origin_acct, &cntr, ctx.fee_limit, call_value)
+                get_total_energy_limit(manager, &caller_acct, &origin_acct, &cntr, ctx.fee_limit, call_value)?
             };
             ctx.energy_limit = energy_limit;
         } else {
@@ -375,14 +373,18 @@ impl BuiltinContractExecutorExt for contract_pb::TriggerSmartContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).unwrap();
         let cntr_address = Address::try_from(&self.contract_address).unwrap();
 
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address));
-        let mut cntr_acct = manager.state_db.must_get(&keys::Account(cntr_address));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address))?;
+        let mut cntr_acct = manager.state_db.must_get(&keys::Account(cntr_address))?;
 
-        let cntr = manager.state_db.must_get(&keys::Contract(cntr_address));
+        let cntr = manager.state_db.must_get(&keys::Contract(cntr_address))?;
         let origin_address = Address::try_from(&cntr.origin_address).unwrap();
 
         let energy_limit = ctx.energy_limit as usize;
@@ -408,17 +410,14 @@ impl BuiltinContractExecutorExt for contract_pb::TriggerSmartContract {
             return Ok(ret);
         }
 
-        manager.new_layer();
+        let mut manager = manager.savepoint();
 
         let mut has_transfer = false;
         // transfer TRX
         if self.call_value > 0 {
             has_transfer = true;
             if owner_acct.adjust_balance(-self.call_value).is_err() {
-                return Err(format!(
-                    "insufficient balance, balance={} required={}",
-                    owner_acct.balance, self.call_value
-                )); // validate error
+                return Err(ExecutionError::InsufficientBalance); // validate error
             }
             cntr_acct.adjust_balance(self.call_value).unwrap();
         }
@@ -430,7 +429,7 @@ impl BuiltinContractExecutorExt for contract_pb::TriggerSmartContract {
         if self.call_token_value > 0 &&
             manager
                 .state_db
-                .must_get(&keys::ChainParameter::AllowTvmTransferTrc10Upgrade) !=
+                .must_get(&keys::ChainParameter::AllowTvmTransferTrc10Upgrade)? !=
                 0
         {
             has_transfer = true;
@@ -440,7 +439,7 @@ impl BuiltinContractExecutorExt for contract_pb::TriggerSmartContract {
                 .adjust_token_balance(call_token_id, -call_token_value)
                 .is_err()
             {
-                return Err("insufficient token balance".into()); // validate error
+                return Err(ExecutionError::InsufficientBalance); // validate error
             }
             cntr_acct.adjust_token_balance(call_token_id, call_token_value).unwrap();
         }
@@ -466,11 +465,11 @@ impl BuiltinContractExecutorExt for contract_pb::TriggerSmartContract {
         let data = Rc::new(self.data.to_vec());
         debug!("calling data = {:?}", hex::encode(&self.data));
 
-        let upgrade = get_current_tvm_upgrade(manager);
+        let upgrade = get_current_tvm_upgrade(&manager)?;
         let precompile = upgrade.precompile();
         let config = upgrade.to_tvm_config();
 
-        let mut backend = StateBackend::new(owner_address, manager, ctx);
+        let mut backend = StateBackend::new(owner_address, &mut manager, ctx);
         let mut executor = tvm::StackExecutor::new_with_precompile(&backend, energy_limit, &config, precompile);
 
         let vm_ctx = tvm::Context {
@@ -491,11 +490,12 @@ impl BuiltinContractExecutorExt for contract_pb::TriggerSmartContract {
 
         if exit_reason.is_succeed() {
             backend.apply(applies, logs, false);
+            manager.commit();
         } else {
             drop(backend);
             drop(applies);
             drop(logs);
-            manager.rollback_layers(1);
+            manager.rollback();
         }
 
         if !ret_val.is_empty() {
@@ -510,7 +510,7 @@ impl BuiltinContractExecutorExt for contract_pb::TriggerSmartContract {
         };
         ctx.energy = energy_usage;
         // consume energy
-        EnergyProcessor::new(manager).consume(
+        EnergyProcessor::new(&mut manager).consume(
             owner_address,
             origin_address,
             energy_usage,
@@ -531,10 +531,10 @@ impl BuiltinContractExecutorExt for contract_pb::TriggerSmartContract {
 
 // Update a contract's `consume_user_energy_percent` setting.
 impl BuiltinContractExecutorExt for contract_pb::UpdateSettingContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
-        if state_db.must_get(&keys::ChainParameter::AllowTvm) == 0 {
+        if state_db.must_get(&keys::ChainParameter::AllowTvm)? == 0 {
             return Err("TVM is disabled".into());
         }
 
@@ -562,9 +562,13 @@ impl BuiltinContractExecutorExt for contract_pb::UpdateSettingContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let cntr_address = Address::try_from(&self.contract_address).unwrap();
-        let mut cntr = manager.state_db.must_get(&keys::Contract(cntr_address));
+        let mut cntr = manager.state_db.must_get(&keys::Contract(cntr_address))?;
 
         cntr.consume_user_energy_percent = self.consume_user_energy_percent;
         manager
@@ -578,10 +582,10 @@ impl BuiltinContractExecutorExt for contract_pb::UpdateSettingContract {
 
 // Update a contract's `origin_energy_limit` setting.
 impl BuiltinContractExecutorExt for contract_pb::UpdateEnergyLimitContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
-        if state_db.must_get(&keys::ChainParameter::AllowTvm) == 0 {
+        if state_db.must_get(&keys::ChainParameter::AllowTvm)? == 0 {
             return Err("TVM is disabled".into());
         }
 
@@ -607,9 +611,13 @@ impl BuiltinContractExecutorExt for contract_pb::UpdateEnergyLimitContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let cntr_address = Address::try_from(&self.contract_address).unwrap();
-        let mut cntr = manager.state_db.must_get(&keys::Contract(cntr_address));
+        let mut cntr = manager.state_db.must_get(&keys::Contract(cntr_address))?;
 
         cntr.origin_energy_limit = self.origin_energy_limit;
         manager
@@ -625,10 +633,10 @@ impl BuiltinContractExecutorExt for contract_pb::UpdateEnergyLimitContract {
 //
 // NOTE: This is a design flaw, to deceive oneself.
 impl BuiltinContractExecutorExt for contract_pb::ClearAbiContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
-        if state_db.must_get(&keys::ChainParameter::AllowTvm) == 0 {
+        if state_db.must_get(&keys::ChainParameter::AllowTvm)? == 0 {
             return Err("TVM is disabled".into());
         }
 
@@ -652,9 +660,13 @@ impl BuiltinContractExecutorExt for contract_pb::ClearAbiContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let cntr_address = Address::try_from(&self.contract_address).unwrap();
-        let mut cntr = manager.state_db.must_get(&keys::Contract(cntr_address));
+        let mut cntr = manager.state_db.must_get(&keys::Contract(cntr_address))?;
 
         cntr.abi.as_mut().map(|abi| abi.entries = vec![]);
         manager
@@ -666,18 +678,18 @@ impl BuiltinContractExecutorExt for contract_pb::ClearAbiContract {
     }
 }
 
-// Dry run a TriggerSmartContract. Use 1 db layer and rollback.
+// Dry run a TriggerSmartContract. Use a savepoint and rollback.
 pub fn execute_smart_contract(
     manager: &mut Manager,
     trigger: &contract_pb::TriggerSmartContract,
     ctx: &mut TransactionContext,
-) -> Result<TransactionResult, String> {
+) -> Result<TransactionResult, ExecutionError> {
     let owner_address = Address::try_from(&trigger.owner_address).map_err(|_| "invalid owner address")?;
     let cntr_address = Address::try_from(&trigger.contract_address).map_err(|_| "invalid contract address")?;
 
     let energy_limit = ctx.energy_limit as usize;
 
-    manager.new_layer();
+    let mut manager = manager.savepoint();
 
     // transfer
     if trigger.call_value > 0 || trigger.call_token_value > 0 {
@@ -685,7 +697,7 @@ pub fn execute_smart_contract(
             .state_db
             .get(&keys::Account(owner_address))
             .unwrap()
-            .ok_or_else(|| "owner account not found")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_address))?;
         let mut cntr_acct = manager
             .state_db
             .get(&keys::Account(cntr_address))
@@ -693,10 +705,7 @@ pub fn execute_smart_contract(
             .ok_or_else(|| "contract not found")?;
         if trigger.call_value > 0 {
             if owner_acct.adjust_balance(-trigger.call_value).is_err() {
-                return Err(format!(
-                    "insufficient balance, balance={} required={}",
-                    owner_acct.balance, trigger.call_value
-                ));
+                return Err(ExecutionError::InsufficientBalance);
             }
             cntr_acct.adjust_balance(trigger.call_value).unwrap();
         }
@@ -705,7 +714,7 @@ pub fn execute_smart_contract(
                 .adjust_token_balance(trigger.call_token_id, -trigger.call_token_value)
                 .is_err()
             {
-                return Err("insufficient token balance".into());
+                return Err(ExecutionError::InsufficientBalance);
             }
             cntr_acct
                 .adjust_token_balance(trigger.call_token_id, trigger.call_token_value)
@@ -730,11 +739,11 @@ pub fn execute_smart_contract(
     let code = Rc::new(code);
     let data = Rc::new(trigger.data.to_vec());
 
-    let upgrade = get_current_tvm_upgrade(manager);
+    let upgrade = get_current_tvm_upgrade(&manager)?;
     let precompile = upgrade.precompile();
     let config = upgrade.to_tvm_config();
 
-    let mut backend = StateBackend::new(owner_address, manager, ctx);
+    let mut backend = StateBackend::new(owner_address, &mut manager, ctx);
     let mut executor = tvm::StackExecutor::new_with_precompile(&backend, energy_limit, &config, precompile);
 
     let vm_ctx = tvm::Context {
@@ -755,7 +764,7 @@ pub fn execute_smart_contract(
     backend.apply(applies, logs, false);
     drop(backend);
 
-    manager.rollback_layers(1);
+    manager.rollback();
 
     if !ret_val.is_empty() {
         debug!("return value: {:?}", hex::encode(&ret_val));
@@ -814,12 +823,18 @@ fn generate_created_contract_address(txn_hash: &H256, owner_address: &Address) -
     Address::from_tvm_bytes(&hasher.finalize()[12..])
 }
 
+/// Fee limit is capped by `MaxFeeLimit`, which defaults to 1000 TRX and is adjustable by proposal since 4.1.2.
+fn validate_fee_limit(manager: &Manager, fee_limit: i64) -> Result<(), String> {
+    let max_fee_limit = manager.state_db.must_get(&keys::ChainParameter::MaxFeeLimit)?;
+    if fee_limit < 0 || fee_limit > max_fee_limit {
+        return Err("invalid fee_limit".into());
+    }
+    Ok(())
+}
+
 #[inline]
-fn get_account_energy_limit(manager: &Manager, acct: &Account, fee_limit: i64, call_value: i64) -> i64 {
-    if ForkController::new(manager)
-        .pass_version(BlockVersion::ENERGY_LIMIT())
-        .unwrap()
-    {
+fn get_account_energy_limit(manager: &Manager, acct: &Account, fee_limit: i64, call_value: i64) -> Result<i64, String> {
+    if ForkController::new(manager).pass_version(BlockVersion::ENERGY_LIMIT())? {
         get_account_energy_limit_with_fixed_ratio(manager, &acct, fee_limit, call_value)
     } else {
         get_account_energy_limit_with_float_ratio(manager, &acct, fee_limit, call_value)
@@ -832,17 +847,17 @@ fn get_account_energy_limit_with_fixed_ratio(
     acct: &Account,
     fee_limit: i64,
     call_value: i64,
-) -> i64 {
-    let energy_price = manager.state_db.must_get(&keys::ChainParameter::EnergyFee);
+) -> Result<i64, String> {
+    let energy_price = manager.state_db.must_get(&keys::ChainParameter::EnergyFee)?;
 
-    let left_energy = EnergyUtil::new(manager).get_left_frozen_energy(acct);
+    let left_energy = EnergyUtil::new(manager).get_left_frozen_energy(acct)?;
     let energy_from_balance = (acct.balance - call_value).max(0) / energy_price;
 
     let available_energy = left_energy + energy_from_balance;
 
     let energy_from_fee_limit = fee_limit / energy_price;
 
-    available_energy.min(energy_from_fee_limit)
+    Ok(available_energy.min(energy_from_fee_limit))
 }
 
 // getEnergyFee(long callerEnergyUsage, long callerEnergyFrozen, long callerEnergyTotal)
@@ -861,18 +876,18 @@ fn get_account_energy_limit_with_float_ratio(
     acct: &Account,
     fee_limit: i64,
     call_value: i64,
-) -> i64 {
-    let energy_price = manager.state_db.must_get(&keys::ChainParameter::EnergyFee);
+) -> Result<i64, String> {
+    let energy_price = manager.state_db.must_get(&keys::ChainParameter::EnergyFee)?;
 
     // getAccountLeftEnergyFromFreeze
-    let left_energy_from_freeze = EnergyUtil::new(manager).get_left_frozen_energy(acct);
+    let left_energy_from_freeze = EnergyUtil::new(manager).get_left_frozen_energy(acct)?;
     let energy_from_left_balance = (acct.balance - call_value.max(0)).max(0) / energy_price;
     let amount_for_energy = acct.amount_for_energy();
 
     let energy_from_fee_limit = if amount_for_energy == 0 {
         fee_limit / energy_price
     } else {
-        let energy_limit = EnergyUtil::new(manager).calculate_global_energy_limit(acct);
+        let energy_limit = EnergyUtil::new(manager).calculate_global_energy_limit(acct)?;
         // getEnergyFee(totalBalanceForEnergyFreeze, leftEnergyFromFreeze, totalEnergyFromFreeze)
         // getEnergyFee(callerEnergyUsage, callerEnergyFrozen, callerEnergyTotal) ->
         //   callerEnergyFrozen * callerEnergyUsage / callerEnergyTotal
@@ -884,10 +899,10 @@ fn get_account_energy_limit_with_float_ratio(
             left_energy_from_freeze + (fee_limit - left_balance_for_freeze) / energy_price
         }
     };
-    i64::min(
+    Ok(i64::min(
         left_energy_from_freeze + energy_from_left_balance,
         energy_from_fee_limit,
-    )
+    ))
 }
 
 // getTotalEnergyLimit
@@ -899,13 +914,10 @@ fn get_total_energy_limit(
     cntr: &SmartContract,
     fee_limit: i64,
     call_value: i64,
-) -> i64 {
+) -> Result<i64, String> {
     // TODO: Can origin be null? (use getAccountEnergyLimitWithFixRatio)
     // if block.number > BlockNumForEneryLimit
-    if ForkController::new(manager)
-        .pass_version(BlockVersion::ENERGY_LIMIT())
-        .unwrap()
-    {
+    if ForkController::new(manager).pass_version(BlockVersion::ENERGY_LIMIT())? {
         get_total_energy_limit_with_fixed_ratio(manager, caller, origin, cntr, fee_limit, call_value)
     } else {
         get_total_energy_limit_with_float_ratio(manager, caller, origin, cntr, fee_limit, call_value)
@@ -920,12 +932,12 @@ fn get_total_energy_limit_with_fixed_ratio(
     cntr: &SmartContract,
     fee_limit: i64,
     call_value: i64,
-) -> i64 {
-    let caller_energy_limit = get_account_energy_limit_with_fixed_ratio(manager, caller, fee_limit, call_value);
+) -> Result<i64, String> {
+    let caller_energy_limit = get_account_energy_limit_with_fixed_ratio(manager, caller, fee_limit, call_value)?;
     let consume_user_energy_percent = cntr.consume_user_energy_percent;
     assert!(cntr.origin_energy_limit >= 0);
 
-    let origin_energy_left = EnergyUtil::new(manager).get_left_frozen_energy(origin);
+    let origin_energy_left = EnergyUtil::new(manager).get_left_frozen_energy(origin)?;
     let origin_energy_limit = if consume_user_energy_percent > 0 {
         assert!(consume_user_energy_percent <= 100);
         i64::min(
@@ -936,7 +948,7 @@ fn get_total_energy_limit_with_fixed_ratio(
         i64::min(origin_energy_left, cntr.origin_energy_limit)
     };
 
-    caller_energy_limit + origin_energy_limit
+    Ok(caller_energy_limit + origin_energy_limit)
 }
 
 /// getTotalEnergyLimitWithFloatRatio, before ENERGY_LIMIT fork.
@@ -947,46 +959,49 @@ fn get_total_energy_limit_with_float_ratio(
     cntr: &SmartContract,
     fee_limit: i64,
     call_value: i64,
-) -> i64 {
-    let caller_energy_limit = get_account_energy_limit_with_float_ratio(manager, caller, fee_limit, call_value);
+) -> Result<i64, String> {
+    let caller_energy_limit = get_account_energy_limit_with_float_ratio(manager, caller, fee_limit, call_value)?;
     let user_energy_percent = cntr.consume_user_energy_percent;
     let origin_energy_percent = 100 - user_energy_percent;
 
     // creatorEnergyFromFreeze
-    let origin_energy_limit = EnergyUtil::new(manager).get_left_frozen_energy(origin);
+    let origin_energy_limit = EnergyUtil::new(manager).get_left_frozen_energy(origin)?;
 
     // orgin/caller > origin_percent/user_percent
     if origin_energy_limit * user_energy_percent > caller_energy_limit * origin_energy_percent {
-        caller_energy_limit * 100 / user_energy_percent
+        Ok(caller_energy_limit * 100 / user_energy_percent)
     } else {
-        caller_energy_limit + origin_energy_limit
+        Ok(caller_energy_limit + origin_energy_limit)
     }
 }
 
 // TODO: Optimize and cache values.
-fn get_current_tvm_upgrade(manager: &Manager) -> TvmUpgrade {
-    TvmUpgrade {
+fn get_current_tvm_upgrade(manager: &Manager) -> Result<TvmUpgrade, String> {
+    Ok(TvmUpgrade {
         asset_transfer: manager
             .state_db
-            .must_get(&keys::ChainParameter::AllowTvmTransferTrc10Upgrade) !=
+            .must_get(&keys::ChainParameter::AllowTvmTransferTrc10Upgrade)? !=
             0,
         constantinople: manager
             .state_db
-            .must_get(&keys::ChainParameter::AllowTvmConstantinopleUpgrade) !=
+            .must_get(&keys::ChainParameter::AllowTvmConstantinopleUpgrade)? !=
             0,
         solidity059: manager
             .state_db
-            .must_get(&keys::ChainParameter::AllowTvmSolidity059Upgrade) !=
+            .must_get(&keys::ChainParameter::AllowTvmSolidity059Upgrade)? !=
             0,
         shielded: manager
             .state_db
-            .must_get(&keys::ChainParameter::AllowTvmShieldedUpgrade) !=
+            .must_get(&keys::ChainParameter::AllowTvmShieldedUpgrade)? !=
             0,
         stake: false,
-        istanbul: false,
+        istanbul: manager
+            .state_db
+            .must_get(&keys::ChainParameter::AllowTvmIstanbulUpgrade)? !=
+            0,
         asset_issue: false,
-        multisig: manager.state_db.must_get(&keys::ChainParameter::AllowMultisig) != 0,
-    }
+        multisig: manager.state_db.must_get(&keys::ChainParameter::AllowMultisig)? != 0,
+    })
 }
 
 /// Helper for `tvm::ExitReason`.
@@ -1034,8 +1049,71 @@ impl ExitReasonExt for ExitReason {
 
 #[cfg(test)]
 mod tests {
+    use config::{Config, GenesisConfig};
+    use state::db::StateDB;
+
     use super::*;
 
+    // Runtime: `mstore(0, chainid())`, returns the chain id.
+    const CHAIN_ID_CODE: &str = "4660005260206000f3";
+
+    /// Trigger `CHAIN_ID_CODE` through the actuator, returns the contract status, the return value and
+    /// the genesis block hash.
+    fn trigger_chain_id(allow_tvm_istanbul: bool) -> (i32, Vec<u8>, H256) {
+        let config = Config::load_from_str(include_str!("../../../../etc/conf.toml")).unwrap();
+        let genesis_config = GenesisConfig::load_from_str(include_str!("../../../../etc/genesis.json")).unwrap();
+        let mut manager = Manager::with_state_db(StateDB::new_in_memory(), &config, &genesis_config);
+        let header = genesis_config.to_indexed_block().unwrap().header;
+
+        manager
+            .state_db
+            .put_key(keys::ChainParameter::AllowTvmIstanbulUpgrade, allow_tvm_istanbul as i64)
+            .unwrap();
+
+        let owner_address: Address = "TN21Wx2yoNYiZ7znuQonmZMJnH5Vdfxu78".parse().unwrap();
+        let cntr_address: Address = "TCCcBZEdTHmS1NfFtCYfwpjBKeTv515n71".parse().unwrap();
+        let mut owner_acct = Account::new(0);
+        owner_acct.balance = 10_000_000_000;
+        manager
+            .state_db
+            .put_key(keys::Account(owner_address), owner_acct)
+            .unwrap();
+        manager
+            .state_db
+            .put_key(keys::Account(cntr_address), Account::new_contract_account(0))
+            .unwrap();
+        let mut cntr = SmartContract::new_inner();
+        cntr.origin_address = owner_address.as_bytes().to_vec();
+        cntr.contract_address = cntr_address.as_bytes().to_vec();
+        manager.state_db.put_key(keys::Contract(cntr_address), cntr).unwrap();
+        manager
+            .state_db
+            .put_key(keys::ContractCode(cntr_address), hex::decode(CHAIN_ID_CODE).unwrap())
+            .unwrap();
+
+        let trigger = contract_pb::TriggerSmartContract {
+            owner_address: owner_address.as_bytes().to_vec(),
+            contract_address: cntr_address.as_bytes().to_vec(),
+            ..Default::default()
+        };
+        let mut ctx = TransactionContext::dummy(&header);
+        ctx.energy_limit = 1_000_000;
+        let ret = trigger.execute(&mut manager, &mut ctx).unwrap();
+        (ret.contract_status, ctx.result, manager.genesis_block_hash)
+    }
+
+    #[test]
+    fn chain_id_since_tvm_istanbul_upgrade() {
+        let (status, ret_val, genesis_block_hash) = trigger_chain_id(true);
+        assert_eq!(status, ContractStatus::Success as i32);
+        assert_eq!(ret_val, genesis_block_hash.as_bytes());
+
+        // CHAINID is an invalid opcode before the upgrade.
+        let (status, ret_val, _) = trigger_chain_id(false);
+        assert_ne!(status, ContractStatus::Success as i32);
+        assert!(ret_val.is_empty());
+    }
+
     #[test]
     fn contract_address_of_create_smart_contract() {
         // This is a transaction from Nile testnet.
@@ -9,19 +9,19 @@ use proto::state::Account;
 use state::keys;
 
 use crate::Manager;
-use super::super::TransactionContext;
+use super::super::{ExecutionError, TransactionContext};
 use super::BuiltinContractExecutorExt;
 
 const TRANSFER_FEE: i64 = 0;
 
 impl BuiltinContractExecutorExt for contract_pb::TransferContract {
-    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
         let to_address = Address::try_from(&self.to_address).map_err(|_| "invalid to_address")?;
 
-        let mut fee = self.fee(manager);
+        let mut fee = self.fee(manager)?;
 
         if owner_address == to_address {
             return Err("cannot transfer to oneself".into());
@@ -34,14 +34,11 @@ impl BuiltinContractExecutorExt for contract_pb::TransferContract {
         let owner_acct = state_db
             .get(&keys::Account(owner_address))
             .map_err(|_| "error while querying db")?
-            .ok_or_else(|| "owner account is not on chain")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_address))?;
 
         if let Some(spend) = self.amount.checked_add(fee) {
             if owner_acct.balance < spend {
-                return Err(format!(
-                    "insufficient balance, balance={}, required={}",
-                    owner_acct.balance, spend
-                ));
+                return Err(ExecutionError::InsufficientBalance);
             }
         } else {
             return Err("math overflow".into());
@@ -56,11 +53,11 @@ impl BuiltinContractExecutorExt for contract_pb::TransferContract {
                 ctx.new_account_created = true;
                 // NOTE: CreateNewAccountFeeInSystemContract is 0,
                 // account creation fee is handled by BandwidthProcessor.
-                fee += state_db.must_get(&keys::ChainParameter::CreateNewAccountFeeInSystemContract);
+                fee += state_db.must_get(&keys::ChainParameter::CreateNewAccountFeeInSystemContract)?;
             }
             Some(to_acct)
                 if to_acct.r#type == AccountType::Contract as i32 &&
-                    state_db.must_get(&keys::ChainParameter::ForbidTransferToContract) == 1 =>
+                    state_db.must_get(&keys::ChainParameter::ForbidTransferToContract)? == 1 =>
             {
                 return Err("cannot transfer to a smart contract address".into());
             }
@@ -77,19 +74,26 @@ impl BuiltinContractExecutorExt for contract_pb::TransferContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).unwrap();
         let to_address = Address::try_from(&self.to_address).unwrap();
 
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address))?;
 
         let fee = ctx.contract_fee;
 
-        let mut to_acct = manager
+        let maybe_to_acct = manager
             .state_db
             .get(&keys::Account(to_address))
-            .map_err(|e| format!("state-db error: {:?}", e))?
-            .unwrap_or_else(|| Account::new(manager.latest_block_timestamp()));
+            .map_err(|e| format!("state-db error: {:?}", e))?;
+        let mut to_acct = match maybe_to_acct {
+            Some(acct) => acct,
+            None => Account::new(manager.latest_block_timestamp()?),
+        };
 
         if fee != 0 {
             owner_acct.adjust_balance(-fee).unwrap();
@@ -111,7 +115,7 @@ impl BuiltinContractExecutorExt for contract_pb::TransferContract {
         Ok(TransactionResult::success())
     }
 
-    fn fee(&self, _: &Manager) -> i64 {
-        TRANSFER_FEE
+    fn fee(&self, _: &Manager) -> Result<i64, ExecutionError> {
+        Ok(TRANSFER_FEE)
     }
 }
@@ -12,16 +12,16 @@ use state::keys;
 
 use super::super::super::governance::reward::{RewardController, RewardUtil};
 use crate::Manager;
-use super::super::TransactionContext;
+use super::super::{ExecutionError, TransactionContext};
 use super::BuiltinContractExecutorExt;
 
 impl BuiltinContractExecutorExt for contract_pb::WitnessCreateContract {
-    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
 
-        let fee = self.fee(manager);
+        let fee = self.fee(manager)?;
 
         // validUrl
         if self.url.is_empty() || self.url.len() > 256 {
@@ -31,17 +31,17 @@ impl BuiltinContractExecutorExt for contract_pb::WitnessCreateContract {
         let owner_acct = state_db
             .get(&keys::Account(owner_address))
             .map_err(|_| "error while querying db")?
-            .ok_or_else(|| "owner account is not on chain")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_address))?;
 
         let maybe_witness = state_db
             .get(&keys::Witness(owner_address))
             .map_err(|_| "error while querying db")?;
         if maybe_witness.is_some() {
-            return Err(format!("witness {} already exists", owner_address));
+            return Err(format!("witness {} already exists", owner_address).into());
         }
 
         if owner_acct.balance < fee {
-            return Err("insufficient balance to create witness".into());
+            return Err(ExecutionError::InsufficientBalance);
         }
 
         ctx.contract_fee = fee;
@@ -49,10 +49,14 @@ impl BuiltinContractExecutorExt for contract_pb::WitnessCreateContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_address = Address::try_from(&self.owner_address).unwrap();
 
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_address))?;
         // createWitness
 
         let witness = Witness {
@@ -82,8 +86,8 @@ impl BuiltinContractExecutorExt for contract_pb::WitnessCreateContract {
         Ok(TransactionResult::success())
     }
 
-    fn fee(&self, manager: &Manager) -> i64 {
-        manager.state_db.must_get(&keys::ChainParameter::WitnessCreateFee)
+    fn fee(&self, manager: &Manager) -> Result<i64, ExecutionError> {
+        Ok(manager.state_db.must_get(&keys::ChainParameter::WitnessCreateFee)?)
     }
 }
 
@@ -93,7 +97,7 @@ impl BuiltinContractExecutorExt for contract_pb::WitnessCreateContract {
 // The new votes will be directely counted and save to Witness store.
 // The current effective vote count is saved in WitnessSchedule.
 impl BuiltinContractExecutorExt for contract_pb::VoteWitnessContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
@@ -130,7 +134,7 @@ impl BuiltinContractExecutorExt for contract_pb::VoteWitnessContract {
         let owner_acct = state_db
             .get(&keys::Account(owner_address))
             .map_err(|_| "error while querying db")?
-            .ok_or_else(|| "owner account is not on chain")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_address))?;
 
         // 1_TRX for 1_TP
         let tp = owner_acct.tron_power();
@@ -138,13 +142,18 @@ impl BuiltinContractExecutorExt for contract_pb::VoteWitnessContract {
             return Err(format!(
                 "total number of votes is greater than account's tron power, {} > {}",
                 total_vote_count, tp
-            ));
+            )
+            .into());
         }
 
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_addr = Address::try_from(&self.owner_address).unwrap();
 
         // delegationService.withdrawReward(ownerAddress);
@@ -174,7 +183,7 @@ impl BuiltinContractExecutorExt for contract_pb::VoteWitnessContract {
         // Save votes.
         for (addr, count_diff) in votes_diff {
             if count_diff != 0 {
-                let mut wit = manager.state_db.must_get(&keys::Witness(addr));
+                let mut wit = manager.state_db.must_get(&keys::Witness(addr))?;
                 wit.vote_count += count_diff;
 
                 manager
@@ -184,7 +193,7 @@ impl BuiltinContractExecutorExt for contract_pb::VoteWitnessContract {
             }
         }
 
-        let epoch = manager.state_db.must_get(&keys::DynamicProperty::CurrentEpoch);
+        let epoch = manager.state_db.must_get(&keys::DynamicProperty::CurrentEpoch)?;
         manager
             .state_db
             .put_key(
@@ -206,7 +215,7 @@ impl BuiltinContractExecutorExt for contract_pb::VoteWitnessContract {
 
 // Withdraw block producing reward, standby witness reward, and voting reward.
 impl BuiltinContractExecutorExt for contract_pb::WithdrawBalanceContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         const DAY_IN_MS: i64 = 86_400_000;
 
         let state_db = &manager.state_db;
@@ -216,7 +225,7 @@ impl BuiltinContractExecutorExt for contract_pb::WithdrawBalanceContract {
         let acct = state_db
             .get(&keys::Account(owner_address))
             .map_err(|_| "db query error")?
-            .ok_or_else(|| "account not exists")?;
+            .ok_or(ExecutionError::AccountNotFound(owner_address))?;
 
         let is_gr = manager
             .genesis_config
@@ -229,7 +238,7 @@ impl BuiltinContractExecutorExt for contract_pb::WithdrawBalanceContract {
         }
 
         let latest_withdraw_ts = acct.latest_withdraw_timestamp;
-        let now = manager.latest_block_timestamp();
+        let now = manager.latest_block_timestamp()?;
         let witness_allowance_frozen_time = constants::NUM_OF_FROZEN_DAYS_FOR_WITNESS_ALLOWANCE * DAY_IN_MS;
 
         if now - latest_withdraw_ts < witness_allowance_frozen_time {
@@ -247,19 +256,23 @@ impl BuiltinContractExecutorExt for contract_pb::WithdrawBalanceContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_addr = Address::try_from(&self.owner_address).unwrap();
 
         // delegationService.withdrawReward(ownerAddress);
         RewardController::new(manager).withdraw_reward(owner_addr)?;
 
         // NOTE: Account must be fetched after RewardController.
-        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr));
+        let mut owner_acct = manager.state_db.must_get(&keys::Account(owner_addr))?;
 
         ctx.withdrawal_amount = owner_acct.allowance;
         log::debug!("calibrated allowance = {}", owner_acct.allowance);
 
-        let now = manager.latest_block_timestamp();
+        let now = manager.latest_block_timestamp()?;
 
         owner_acct.adjust_balance(owner_acct.allowance).unwrap();
         owner_acct.allowance = 0;
@@ -275,10 +288,10 @@ impl BuiltinContractExecutorExt for contract_pb::WithdrawBalanceContract {
 
 // Update brokerage rate in percent of a witness account.
 impl BuiltinContractExecutorExt for contract_pb::UpdateBrokerageContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
-        let allow_change_delegation = state_db.must_get(&keys::ChainParameter::AllowChangeDelegation) != 0;
+        let allow_change_delegation = state_db.must_get(&keys::ChainParameter::AllowChangeDelegation)? != 0;
         if !allow_change_delegation {
             return Err("AllowChangeDelegation if OFF, brokerage rate is not supported".into());
         }
@@ -294,15 +307,19 @@ impl BuiltinContractExecutorExt for contract_pb::UpdateBrokerageContract {
             .get(&keys::Witness(owner_address))
             .map_err(|_| "error while querying db")?;
         if maybe_witness.is_none() {
-            return Err(format!("account {} is not a witness", owner_address));
+            return Err(format!("account {} is not a witness", owner_address).into());
         }
 
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_addr = Address::try_from(&self.owner_address).unwrap();
-        let mut wit = manager.state_db.must_get(&keys::Witness(owner_addr));
+        let mut wit = manager.state_db.must_get(&keys::Witness(owner_addr))?;
 
         if self.brokerage != wit.brokerage {
             wit.brokerage = self.brokerage;
@@ -317,7 +334,7 @@ impl BuiltinContractExecutorExt for contract_pb::UpdateBrokerageContract {
 }
 
 impl BuiltinContractExecutorExt for contract_pb::WitnessUpdateContract {
-    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), String> {
+    fn validate(&self, manager: &Manager, _ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
         let state_db = &manager.state_db;
 
         let owner_address = Address::try_from(&self.owner_address).map_err(|_| "invalid owner_address")?;
@@ -327,7 +344,7 @@ impl BuiltinContractExecutorExt for contract_pb::WitnessUpdateContract {
             .get(&keys::Witness(owner_address))
             .map_err(|_| "error while querying db")?;
         if maybe_witness.is_none() {
-            return Err(format!("account {} is not a witness", owner_address));
+            return Err(format!("account {} is not a witness", owner_address).into());
         }
 
         // validUrl
@@ -338,9 +355,13 @@ impl BuiltinContractExecutorExt for contract_pb::WitnessUpdateContract {
         Ok(())
     }
 
-    fn execute(&self, manager: &mut Manager, _ctx: &mut TransactionContext) -> Result<TransactionResult, String> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        _ctx: &mut TransactionContext,
+    ) -> Result<TransactionResult, ExecutionError> {
         let owner_addr = Address::try_from(&self.owner_address).unwrap();
-        let mut wit = manager.state_db.must_get(&keys::Witness(owner_addr));
+        let mut wit = manager.state_db.must_get(&keys::Witness(owner_addr))?;
 
         wit.url = unsafe { String::from_utf8_unchecked(self.update_url.clone()) };
 
@@ -0,0 +1,69 @@
+//! Transaction execution errors.
+use std::fmt;
+
+use ::keys::Address;
+use state::StateError;
+
+/// Transaction execution errors, returned by actuators.
+#[derive(Debug)]
+pub enum ExecutionError {
+    /// A required account does not exist.
+    AccountNotFound(Address),
+    /// Insufficient TRX or token balance.
+    InsufficientBalance,
+    /// Signatures do not satisfy the permission of the owner account.
+    PermissionDenied { reason: String },
+    /// Insufficient bandwidth, and insufficient balance to burn for it.
+    BandwidthExhausted,
+    /// The contract is invalid against current state.
+    ContractValidate { reason: String },
+    /// A validated contract fails to execute, i.e. arithmetic overflow or state-db write error.
+    ContractExecute { reason: String },
+    /// Underlying state-db error.
+    State(StateError),
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExecutionError::AccountNotFound(ref addr) => write!(f, "account {} not exists", addr),
+            ExecutionError::InsufficientBalance => write!(f, "insufficient balance"),
+            ExecutionError::PermissionDenied { ref reason } => write!(f, "permission denied: {}", reason),
+            ExecutionError::BandwidthExhausted => write!(f, "insufficient bandwidth"),
+            ExecutionError::ContractValidate { ref reason } => write!(f, "{}", reason),
+            ExecutionError::ContractExecute { ref reason } => write!(f, "{}", reason),
+            ExecutionError::State(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionError {}
+
+impl ExecutionError {
+    /// Untyped errors of `execute` are execution failures, the contract is already validated.
+    pub(crate) fn into_execute_error(self) -> Self {
+        match self {
+            ExecutionError::ContractValidate { reason } => ExecutionError::ContractExecute { reason },
+            e => e,
+        }
+    }
+}
+
+impl From<StateError> for ExecutionError {
+    fn from(e: StateError) -> Self {
+        ExecutionError::State(e)
+    }
+}
+
+/// Untyped errors of actuators are validation errors, see `into_execute_error` for the ones of `execute`.
+impl From<String> for ExecutionError {
+    fn from(reason: String) -> Self {
+        ExecutionError::ContractValidate { reason }
+    }
+}
+
+impl From<&str> for ExecutionError {
+    fn from(reason: &str) -> Self {
+        ExecutionError::ContractValidate { reason: reason.into() }
+    }
+}
@@ -0,0 +1,170 @@
+//! Checking transaction results against the ones embedded in blocks.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+
+use config::{ResultMismatchMode, VerificationConfig};
+use log::{debug, error};
+use primitive_types::H256;
+use proto::chain::transaction::Result as TransactionResult;
+
+use super::TransactionContext;
+
+/// A transaction result mismatched with the one embedded in block.
+struct Mismatch {
+    block_number: i64,
+    transaction_hash: H256,
+    expected: TransactionResult,
+    got: TransactionResult,
+}
+
+/// Records divergence of computed transaction results, for tracking correctness regressions.
+///
+/// Mismatches found while executing are pending, they are reported only when the block is applied to the
+/// main chain. Dry runs, packing of produced blocks and replays never report.
+pub struct MismatchReporter {
+    mode: ResultMismatchMode,
+    report: Option<File>,
+    num_mismatches: usize,
+    pending: Vec<Mismatch>,
+}
+
+impl MismatchReporter {
+    pub fn new(config: &VerificationConfig) -> io::Result<Self> {
+        let report = if config.report_file.is_empty() {
+            None
+        } else {
+            Some(OpenOptions::new().create(true).append(true).open(&config.report_file)?)
+        };
+        Ok(MismatchReporter {
+            mode: config.result_mismatch,
+            report,
+            num_mismatches: 0,
+            pending: vec![],
+        })
+    }
+
+    /// Number of mismatches reported since started.
+    pub fn num_mismatches(&self) -> usize {
+        self.num_mismatches
+    }
+
+    /// Compare the computed result with the one in block. Returns whether they match.
+    ///
+    /// A mismatch is kept as pending, until `report_pending` or `discard_pending`.
+    pub fn check(
+        &mut self,
+        ctx: &TransactionContext,
+        exec_result: &TransactionResult,
+        maybe_result: &Option<&TransactionResult>,
+    ) -> bool {
+        let result = match maybe_result {
+            Some(result) => result,
+            None => {
+                debug!("no result field in chain pb");
+                return true;
+            }
+        };
+        if *result == exec_result {
+            return true;
+        }
+
+        debug!(
+            "execution result mismatch of {:?} at block #{}",
+            ctx.transaction_hash,
+            ctx.block_header.number()
+        );
+        self.pending.push(Mismatch {
+            block_number: ctx.block_header.number(),
+            transaction_hash: ctx.transaction_hash,
+            expected: (*result).clone(),
+            got: exec_result.clone(),
+        });
+        false
+    }
+
+    /// Drop pending mismatches, i.e. of a dry run or a failed block.
+    pub fn discard_pending(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Report pending mismatches of an applied block.
+    ///
+    /// Fails only in halt mode.
+    pub fn report_pending(&mut self) -> Result<(), String> {
+        for Mismatch {
+            block_number,
+            transaction_hash,
+            expected,
+            got,
+        } in self.pending.drain(..)
+        {
+            self.num_mismatches += 1;
+            error!(
+                "execution result mismatch of {:?} at block #{}, expected: \n{:?}\ngot: \n{:?}",
+                transaction_hash, block_number, expected, got
+            );
+            if self.mode != ResultMismatchMode::Log {
+                if let Some(report) = self.report.as_mut() {
+                    // block, txid, expected, got
+                    writeln!(
+                        report,
+                        "{}\t{:?}\t{:?}\t{:?}",
+                        block_number, transaction_hash, expected, got
+                    )
+                    .map_err(|e| format!("can not write mismatch report: {}", e))?;
+                }
+            }
+            if self.mode == ResultMismatchMode::Halt {
+                // Remaining pending mismatches are dropped along with the drain.
+                return Err(format!("execution result mismatch of {:?}", transaction_hash));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain::IndexedBlockHeader;
+    use proto::chain::transaction::result::ContractStatus;
+    use std::fs;
+
+    #[test]
+    fn test_mismatch_report() {
+        let report_file = tempfile::NamedTempFile::new().unwrap();
+        let config = VerificationConfig {
+            result_mismatch: ResultMismatchMode::Halt,
+            report_file: report_file.path().to_str().unwrap().to_owned(),
+        };
+        let mut reporter = MismatchReporter::new(&config).unwrap();
+
+        let header = IndexedBlockHeader::dummy(100, 0);
+        let ctx = TransactionContext::dummy(&header);
+        let expected = TransactionResult::default();
+        let got = TransactionResult {
+            contract_status: ContractStatus::Revert as i32,
+            ..Default::default()
+        };
+
+        assert!(reporter.check(&ctx, &expected, &None));
+        assert!(reporter.check(&ctx, &expected, &Some(&expected)));
+
+        // Mismatches of dry runs are never reported.
+        assert!(!reporter.check(&ctx, &got, &Some(&expected)));
+        reporter.discard_pending();
+        assert!(reporter.report_pending().is_ok());
+        assert_eq!(reporter.num_mismatches(), 0);
+        assert!(fs::read_to_string(report_file.path()).unwrap().is_empty());
+
+        assert!(!reporter.check(&ctx, &got, &Some(&expected)));
+        assert_eq!(reporter.num_mismatches(), 0);
+        assert!(reporter.report_pending().is_err());
+        assert_eq!(reporter.num_mismatches(), 1);
+
+        let report = fs::read_to_string(report_file.path()).unwrap();
+        assert_eq!(report.lines().count(), 1);
+        assert!(report.starts_with("100\t"));
+    }
+}
@@ -0,0 +1,643 @@
+//! Actuator registry of builtin contracts, keyed by contract type.
+//!
+//! Routine to handle transactions of builtin contracts:
+//!
+//! - decode google.Any
+//! - multisig verifiy
+//! - validate (except bandwidth)
+//! - handle bandwidth
+//! - handle mutisig fee
+//! - execute logic
+//!
+//! Which is diffent from java-tron:
+//!
+//! - bandwidth
+//! - multisig
+//! - runtime.validate
+//! - runtime.execute
+//!
+//! Bandwidth consumption must come before transaction execution,
+//! since some type of transaction cause bandwidth usage changes(freeze/unfreeze).
+//! The order is declared per contract type, see `Step`.
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::str;
+use std::sync::Arc;
+
+use ::keys::{b58encode_check, Address};
+use chain::{IndexedBlockHeader, IndexedTransaction};
+use log::{debug, warn};
+use prost::Message;
+use prost_types::Any;
+use proto::chain::{transaction::result::ContractStatus, ContractType};
+use proto::common::ResourceCode;
+use proto::contract as contract_pb;
+use proto::state::TransactionReceipt;
+use state::keys;
+
+use super::actuators::{BuiltinContractExecutorExt, BuiltinContractExt};
+use super::{ExecutionError, TransactionContext};
+use crate::resource::BandwidthProcessor;
+use crate::Manager;
+
+/// A step of handling a builtin contract transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Multisig verification and multisig fee.
+    ValidateSignature,
+    /// Validate, and the extra check of the actuator if any.
+    Validate,
+    /// Bandwidth and account creation fee.
+    ConsumeBandwidth,
+    /// Execute logic. Must appear exactly once.
+    Execute,
+}
+
+/// Most builtin contracts.
+pub const DEFAULT_STEPS: &[Step] = &[
+    Step::ValidateSignature,
+    Step::Validate,
+    Step::ConsumeBandwidth,
+    Step::Execute,
+];
+
+/// Contracts whose bandwidth depends on the execution, i.e. account creation or asset issue.
+pub const BANDWIDTH_AFTER_EXECUTION: &[Step] = &[
+    Step::ValidateSignature,
+    Step::Validate,
+    Step::Execute,
+    Step::ConsumeBandwidth,
+];
+
+/// TVM: Should handle BW first, then remaining can be used for E.
+pub const BANDWIDTH_BEFORE_VALIDATION: &[Step] = &[
+    Step::ValidateSignature,
+    Step::ConsumeBandwidth,
+    Step::Validate,
+    Step::Execute,
+];
+
+/// Actuator of a contract type.
+pub trait ContractActuator: Send + Sync {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        txn: &IndexedTransaction,
+        recover_addrs: Vec<Address>,
+        block_header: &IndexedBlockHeader,
+    ) -> Result<TransactionReceipt, ExecutionError>;
+}
+
+/// Actuator of a builtin contract type, running the steps of `BuiltinContractExecutorExt` in the declared order.
+pub struct BuiltinActuator<C> {
+    steps: &'static [Step],
+    decode: fn(&Any, &IndexedTransaction) -> Result<C, String>,
+    log: fn(&C),
+    check: Option<fn(&C, &IndexedTransaction, &TransactionContext) -> Result<(), String>>,
+    strict: bool,
+    _marker: PhantomData<fn() -> C>,
+}
+
+impl<C: BuiltinContractExecutorExt> BuiltinActuator<C> {
+    pub fn new(steps: &'static [Step]) -> Self {
+        assert_eq!(
+            steps.iter().filter(|&&step| step == Step::Execute).count(),
+            1,
+            "execute step must appear exactly once"
+        );
+        BuiltinActuator {
+            steps,
+            decode: decode_any::<C>,
+            log: log_owner::<C>,
+            check: None,
+            strict: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Custom decoder of the contract parameter.
+    pub fn with_decoder(mut self, decode: fn(&Any, &IndexedTransaction) -> Result<C, String>) -> Self {
+        self.decode = decode;
+        self
+    }
+
+    /// Custom debug output of the contract.
+    pub fn with_logger(mut self, log: fn(&C)) -> Self {
+        self.log = log;
+        self
+    }
+
+    /// Extra check against the whole transaction, run right after `validate`.
+    pub fn with_check(mut self, check: fn(&C, &IndexedTransaction, &TransactionContext) -> Result<(), String>) -> Self {
+        self.check = Some(check);
+        self
+    }
+
+    /// Fail the transaction when the result mismatches the one embedded in block.
+    ///
+    /// NOTE: vm must be strictly checked.
+    pub fn with_strict_result_check(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+}
+
+impl<C: BuiltinContractExecutorExt> ContractActuator for BuiltinActuator<C> {
+    fn execute(
+        &self,
+        manager: &mut Manager,
+        txn: &IndexedTransaction,
+        recover_addrs: Vec<Address>,
+        block_header: &IndexedBlockHeader,
+    ) -> Result<TransactionReceipt, ExecutionError> {
+        let raw_cntr = txn.raw.raw_data.as_ref().unwrap().contract.as_ref().unwrap();
+        let cntr = (self.decode)(raw_cntr.parameter.as_ref().ok_or("missing contract parameter")?, txn)?;
+        (self.log)(&cntr);
+
+        let maybe_result = txn.raw.result.get(0);
+        let mut ctx = TransactionContext::new(&block_header, &txn);
+        if self.strict {
+            ctx.contract_status = maybe_result
+                .and_then(|ret| ContractStatus::from_i32(ret.contract_status))
+                .unwrap_or_default();
+        }
+
+        let mut recover_addrs = Some(recover_addrs);
+        let mut exec_result = None;
+        for step in self.steps {
+            match step {
+                Step::ValidateSignature => {
+                    let recover_addrs = recover_addrs.take().unwrap_or_default();
+                    cntr.validate_signature(raw_cntr.permission_id, recover_addrs, manager, &mut ctx)?;
+                }
+                Step::Validate => {
+                    cntr.validate(manager, &mut ctx)?;
+                    if let Some(check) = self.check {
+                        check(&cntr, txn, &ctx)?;
+                    }
+                }
+                Step::ConsumeBandwidth => BandwidthProcessor::new(manager, txn, &cntr)?.consume(&mut ctx)?,
+                Step::Execute => {
+                    exec_result = Some(
+                        cntr.execute(manager, &mut ctx)
+                            .map_err(ExecutionError::into_execute_error)?,
+                    )
+                }
+            }
+        }
+        let exec_result = exec_result.unwrap();
+
+        if !manager.mismatch_reporter.check(&ctx, &exec_result, &maybe_result) && self.strict {
+            debug!("result => {:?}", exec_result);
+            return Err("result check not passed!".into());
+        }
+
+        debug!("context => {:?}", ctx);
+        Ok(ctx.into())
+    }
+}
+
+fn decode_any<C: BuiltinContractExt>(any: &Any, _txn: &IndexedTransaction) -> Result<C, String> {
+    C::from_any(any).ok_or_else(|| format!("invalid {:?}", C::default().type_code()))
+}
+
+fn log_owner<C: BuiltinContractExt>(cntr: &C) {
+    debug!("=> {:?} by {}", cntr.type_code(), b58encode_check(cntr.owner_address()));
+}
+
+// See-also: https://github.com/opentron/opentron/issues/34
+// Sea-also: https://github.com/opentron/opentron/issues/38
+fn decode_create_smart_contract(
+    any: &Any,
+    txn: &IndexedTransaction,
+) -> Result<contract_pb::CreateSmartContract, String> {
+    let raw_cntr = &any.value[..];
+    let maybe_cntr = contract_pb::CreateSmartContract::decode(raw_cntr);
+
+    match maybe_cntr {
+        Ok(cntr) => Ok(cntr),
+        Err(e) => {
+            warn!("pb error: {:?}", e);
+            warn!("try fix protobuf bug at {:?}", txn.hash);
+            let mut raw = raw_cntr.to_vec();
+            match &*format!("{:?}", txn.hash) {
+                "0xd7506ce73f42c802fedb367cd803975d328ef331767711313a965d7cb935fc3e" |
+                "0xc8b66021c09ec0e18bea68750630fa7dd066cd1d5e3162074e96baa652c3b884" => {
+                    // rm trailing `220123`
+                    let _ = raw.split_off(raw.len() - 3);
+                    Ok(contract_pb::CreateSmartContract::decode(&raw[..]).expect("pb decode error"))
+                }
+                "0xa58995a7160be51ec2388f749c8abe1468c0cac795a8e879f912837882e0d490" |
+                "0x73d96abda1756f724871dfba418aa1e8c1c7526070e4d69fb247171f753d1158" |
+                "0x46ff9d24e110296dadb7ad70b8ab817050999fdad147170a2d360997051db9e6" |
+                "0x0c4d57f340a94593dce4a87aa4d1d277c19edb3869d3427aa51a688f756b9af6" |
+                "0xa6b98c471b496d9f00ea2b7b0fc0173e84be0b26dd9cd7dab4907f822fbcf57a" |
+                "0x31ae94f0d236c7bda7c1776296497f5c073d0845e7214b9c3c46a55c44f6775e" => {
+                    // rm trailing `22022727`
+                    let _ = raw.split_off(raw.len() - 4);
+                    Ok(contract_pb::CreateSmartContract::decode(&raw[..]).expect("pb decode error"))
+                }
+                _ => {
+                    warn!("HEX: {}", hex::encode(raw));
+                    Err("cannot handle protobuf bug".into())
+                }
+            }
+        }
+    }
+}
+
+/// Actuators of contract types.
+pub struct ActuatorRegistry {
+    actuators: HashMap<ContractType, Arc<dyn ContractActuator>>,
+}
+
+impl ActuatorRegistry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        ActuatorRegistry {
+            actuators: HashMap::new(),
+        }
+    }
+
+    /// Register the actuator of a contract type. Replaces the previous one if any.
+    pub fn register<A: ContractActuator + 'static>(&mut self, cntr_type: ContractType, actuator: A) {
+        self.actuators.insert(cntr_type, Arc::new(actuator));
+    }
+
+    /// Register a builtin actuator, under the type code of the contract.
+    pub fn register_builtin<C: BuiltinContractExecutorExt + 'static>(&mut self, actuator: BuiltinActuator<C>) {
+        self.register(C::default().type_code(), actuator);
+    }
+
+    pub fn get(&self, cntr_type: ContractType) -> Option<Arc<dyn ContractActuator>> {
+        self.actuators.get(&cntr_type).cloned()
+    }
+}
+
+impl Default for ActuatorRegistry {
+    /// All builtin contracts of the chain.
+    fn default() -> Self {
+        use self::BuiltinActuator as Actuator;
+
+        let mut registry = ActuatorRegistry::new();
+
+        registry.register_builtin(
+            Actuator::<contract_pb::TransferContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> transfer from {} to {} with amount {}",
+                    b58encode_check(&cntr.owner_address),
+                    b58encode_check(&cntr.to_address),
+                    cntr.amount
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::ProposalCreateContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Proposal by {} {:?}",
+                    b58encode_check(&cntr.owner_address),
+                    cntr.parameters
+                        .iter()
+                        .map(|(&k, v)| (
+                            keys::ChainParameter::from_i32(k as i32).expect(&format!("unknown proposal {}={}", k, v)),
+                            v
+                        ))
+                        .collect::<HashMap<_, _>>()
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::ProposalApproveContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Approve Proposal #{} by {} {}",
+                    cntr.proposal_id,
+                    b58encode_check(cntr.owner_address()),
+                    cntr.is_approval
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::ProposalDeleteContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Delete Proposal #{} by {}",
+                    cntr.proposal_id,
+                    b58encode_check(cntr.owner_address()),
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::WitnessCreateContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> New Witness {} url={:?}",
+                    b58encode_check(cntr.owner_address()),
+                    str::from_utf8(&cntr.url)
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::WitnessUpdateContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Witness Update {} new_url={:?}",
+                    b58encode_check(cntr.owner_address()),
+                    String::from_utf8(cntr.update_url.clone()),
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::UpdateBrokerageContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Update Witness Brokerage {}: new_brokerage_rate={}",
+                    b58encode_check(cntr.owner_address()),
+                    cntr.brokerage,
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::FreezeBalanceContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Freeze Resource {} amount={} resource={:?}",
+                    b58encode_check(cntr.owner_address()),
+                    cntr.frozen_balance,
+                    ResourceCode::from_i32(cntr.resource).unwrap()
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::UnfreezeBalanceContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                if cntr.receiver_address.is_empty() {
+                    debug!(
+                        "=> Unfreeze {:?} {}",
+                        ResourceCode::from_i32(cntr.resource).unwrap(),
+                        b58encode_check(cntr.owner_address()),
+                    );
+                } else {
+                    debug!(
+                        "=> Unfreeze {:?} {} receiver={}",
+                        ResourceCode::from_i32(cntr.resource).unwrap(),
+                        b58encode_check(cntr.owner_address()),
+                        b58encode_check(&cntr.receiver_address)
+                    );
+                }
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::VoteWitnessContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Vote Witness by {} votes: {:?}",
+                    b58encode_check(cntr.owner_address()),
+                    cntr.votes
+                        .iter()
+                        .fold(HashMap::<String, i64>::new(), |mut votes, vote| {
+                            *votes.entry(b58encode_check(&vote.vote_address)).or_default() += vote.vote_count;
+                            votes
+                        })
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::AssetIssueContract>::new(BANDWIDTH_AFTER_EXECUTION).with_logger(|cntr| {
+                debug!(
+                    "=> Issue Asset by {}: {:?}",
+                    b58encode_check(&cntr.owner_address()),
+                    cntr.name
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::UpdateAssetContract>::new(BANDWIDTH_AFTER_EXECUTION)
+                .with_logger(|cntr| debug!("=> Asset Update {}: {:?}", b58encode_check(&cntr.owner_address()), cntr)),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::UnfreezeAssetContract>::new(BANDWIDTH_AFTER_EXECUTION).with_logger(|cntr| {
+                debug!(
+                    "=> Asset Unfreeze {}: {:?}",
+                    b58encode_check(&cntr.owner_address()),
+                    cntr
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::TransferAssetContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Transfer Asset from {} to {}: amount={} asset_name={:?}",
+                    b58encode_check(&cntr.owner_address()),
+                    b58encode_check(&cntr.to_address),
+                    cntr.amount,
+                    cntr.asset_name
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::ParticipateAssetIssueContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Participate Asset Issue {}, to {}: token_id={} amount={}",
+                    b58encode_check(&cntr.owner_address()),
+                    b58encode_check(&cntr.to_address),
+                    cntr.asset_name,
+                    cntr.amount
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::AccountUpdateContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Account Set Name {}: name={:?}",
+                    b58encode_check(&cntr.owner_address()),
+                    cntr.account_name
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::SetAccountIdContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Account Set ID {}: name={:?}",
+                    b58encode_check(&cntr.owner_address()),
+                    cntr.account_id
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::AccountCreateContract>::new(BANDWIDTH_AFTER_EXECUTION).with_logger(|cntr| {
+                debug!(
+                    "=> Create Account By {}: {:?}, type={:?}",
+                    b58encode_check(&cntr.owner_address()),
+                    b58encode_check(&cntr.account_address),
+                    cntr.r#type,
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::AccountPermissionUpdateContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Account Permission Update {}",
+                    b58encode_check(&cntr.owner_address()),
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::WithdrawBalanceContract>::new(DEFAULT_STEPS)
+                .with_logger(|cntr| debug!("=> Withdraw Reward {}", b58encode_check(&cntr.owner_address()))),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::UpdateSettingContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Update Contract setting {}, contract={}",
+                    b58encode_check(&cntr.owner_address()),
+                    b58encode_check(&cntr.contract_address)
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::UpdateEnergyLimitContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Update Contract origin_energy_limit {}, contract={}",
+                    b58encode_check(&cntr.owner_address()),
+                    b58encode_check(&cntr.contract_address)
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::ClearAbiContract>::new(DEFAULT_STEPS).with_logger(|cntr| {
+                debug!(
+                    "=> Clear Contract ABI {}, contract={}",
+                    b58encode_check(&cntr.owner_address()),
+                    b58encode_check(&cntr.contract_address)
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::CreateSmartContract>::new(BANDWIDTH_BEFORE_VALIDATION)
+                .with_decoder(decode_create_smart_contract)
+                .with_logger(|cntr| {
+                    debug!(
+                        "=> Create Smart Contract by {}: name={:?} code_size={}",
+                        b58encode_check(&cntr.owner_address()),
+                        cntr.new_contract.as_ref().unwrap().name,
+                        cntr.new_contract.as_ref().unwrap().bytecode.len(),
+                    )
+                })
+                .with_strict_result_check(),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::TriggerSmartContract>::new(BANDWIDTH_BEFORE_VALIDATION)
+                .with_logger(|cntr| {
+                    debug!(
+                        "=> Calling Smart Contract by {}: contract={}",
+                        b58encode_check(&cntr.owner_address()),
+                        b58encode_check(&cntr.contract_address),
+                    )
+                })
+                .with_strict_result_check(),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::ExchangeCreateContract>::new(BANDWIDTH_AFTER_EXECUTION).with_logger(|cntr| {
+                debug!(
+                    "=> ExchangeCreate by {}: {}:{} <=> {}:{}",
+                    b58encode_check(&cntr.owner_address()),
+                    cntr.first_token_id,
+                    cntr.first_token_balance,
+                    cntr.second_token_id,
+                    cntr.second_token_balance
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::ExchangeWithdrawContract>::new(BANDWIDTH_AFTER_EXECUTION).with_logger(|cntr| {
+                debug!(
+                    "=> ExchangeWithdraw by {}: exchange#{} {}:{}",
+                    b58encode_check(&cntr.owner_address()),
+                    cntr.exchange_id,
+                    cntr.token_id,
+                    cntr.quant,
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::ExchangeInjectContract>::new(BANDWIDTH_BEFORE_VALIDATION).with_logger(|cntr| {
+                debug!(
+                    "=> ExchangeInject by {}: exchange#{} {}:{}",
+                    b58encode_check(&cntr.owner_address()),
+                    cntr.exchange_id,
+                    cntr.token_id,
+                    cntr.quant,
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::ExchangeTransactionContract>::new(BANDWIDTH_BEFORE_VALIDATION).with_logger(
+                |cntr| {
+                    debug!(
+                        "=> ExchangeTransaction by {}: exchange#{} {}:{} expected={}",
+                        b58encode_check(&cntr.owner_address()),
+                        cntr.exchange_id,
+                        cntr.token_id,
+                        cntr.quant,
+                        cntr.expected
+                    )
+                },
+            ),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::MarketSellAssetContract>::new(BANDWIDTH_BEFORE_VALIDATION).with_logger(|cntr| {
+                debug!(
+                    "=> MarketSellAsset by {}: {}:{} => {}:{}",
+                    b58encode_check(&cntr.owner_address()),
+                    String::from_utf8_lossy(&cntr.sell_token_id),
+                    cntr.sell_token_quantity,
+                    String::from_utf8_lossy(&cntr.buy_token_id),
+                    cntr.buy_token_quantity
+                )
+            }),
+        );
+        registry.register_builtin(
+            Actuator::<contract_pb::MarketCancelOrderContract>::new(BANDWIDTH_BEFORE_VALIDATION).with_logger(|cntr| {
+                debug!(
+                    "=> MarketCancelOrder by {}: order {}",
+                    b58encode_check(&cntr.owner_address()),
+                    hex::encode(&cntr.order_id)
+                )
+            }),
+        );
+        // NOTE: Shielded transaction won't consume bandwidth. Fee is paid in TRZ.
+        #[cfg(feature = "nile")]
+        registry.register_builtin(
+            Actuator::<contract_pb::ShieldedTransferContract>::new(&[
+                Step::ValidateSignature,
+                Step::Validate,
+                Step::Execute,
+            ])
+            .with_logger(|cntr| {
+                debug!(
+                    "=> Shielded Transfer: {} spends, {} receives, from={} to={}",
+                    cntr.spend_description.len(),
+                    cntr.receive_description.len(),
+                    cntr.from_amount,
+                    cntr.to_amount
+                )
+            })
+            .with_check(super::actuators::shielded::validate_proofs),
+        );
+
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_actuators() {
+        let registry = ActuatorRegistry::default();
+        assert!(registry.get(ContractType::TransferContract).is_some());
+        assert!(registry.get(ContractType::TriggerSmartContract).is_some());
+        assert!(registry.get(ContractType::ObsoleteCustomContract).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_steps_without_execution() {
+        let _ = BuiltinActuator::<contract_pb::TransferContract>::new(&[Step::ValidateSignature, Step::Validate]);
+    }
+}
@@ -0,0 +1,172 @@
+//! Fork database of unsolidified blocks, aka. `KhaosDatabase` in java-tron.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chain::IndexedBlock;
+use primitive_types::H256;
+
+/// Blocks above the solid block, from all known forks.
+///
+/// The longest chain(highest block number) wins.
+#[derive(Default)]
+pub struct ForkDB {
+    blocks: HashMap<H256, IndexedBlock>,
+    by_number: BTreeMap<i64, Vec<H256>>,
+}
+
+impl ForkDB {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn contains(&self, hash: &H256) -> bool {
+        self.blocks.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &H256) -> Option<&IndexedBlock> {
+        self.blocks.get(hash)
+    }
+
+    pub fn insert(&mut self, block: IndexedBlock) {
+        let hash = *block.hash();
+        if !self.blocks.contains_key(&hash) {
+            self.by_number.entry(block.number()).or_default().push(hash);
+            self.blocks.insert(hash, block);
+        }
+    }
+
+    /// Remove a block and all its descendants.
+    pub fn remove(&mut self, hash: &H256) {
+        let number = match self.blocks.remove(hash) {
+            Some(block) => block.number(),
+            None => return,
+        };
+        self.remove_from_index(number, hash);
+
+        let children = self
+            .by_number
+            .get(&(number + 1))
+            .map(|hashes| {
+                hashes
+                    .iter()
+                    .filter(|child| self.blocks[child].parent_hash() == hash.as_bytes())
+                    .copied()
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        for child in &children {
+            self.remove(child);
+        }
+    }
+
+    /// Drop all blocks at or below the solid block number.
+    pub fn prune(&mut self, solid_block_number: i64) {
+        let kept = self.by_number.split_off(&(solid_block_number + 1));
+        for hash in self.by_number.values().flatten() {
+            self.blocks.remove(hash);
+        }
+        self.by_number = kept;
+    }
+
+    /// Walk back from two heads to their common ancestor.
+    ///
+    /// Returns blocks of both branches, from head down to the child of the common ancestor. The ancestor itself
+    /// needs not to be in the fork db. `None` if any of the branches can not be traced back.
+    pub fn branches(&self, head_a: &H256, head_b: &H256) -> Option<(Vec<&IndexedBlock>, Vec<&IndexedBlock>)> {
+        let mut branch_a = vec![];
+        let mut branch_b = vec![];
+
+        let (mut a, mut num_a) = (*head_a, self.blocks.get(head_a)?.number());
+        let (mut b, mut num_b) = (*head_b, self.blocks.get(head_b)?.number());
+        while a != b {
+            if num_a >= num_b {
+                let block = self.blocks.get(&a)?;
+                branch_a.push(block);
+                a = H256::from_slice(block.parent_hash());
+                num_a -= 1;
+            } else {
+                let block = self.blocks.get(&b)?;
+                branch_b.push(block);
+                b = H256::from_slice(block.parent_hash());
+                num_b -= 1;
+            }
+        }
+        Some((branch_a, branch_b))
+    }
+
+    fn remove_from_index(&mut self, number: i64, hash: &H256) {
+        if let Some(hashes) = self.by_number.get_mut(&number) {
+            hashes.retain(|h| h != hash);
+            if hashes.is_empty() {
+                self.by_number.remove(&number);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain::IndexedBlockHeader;
+    use proto::chain::{block_header::Raw as BlockHeaderRaw, BlockHeader};
+
+    fn block(hash: u64, number: i64, parent: u64) -> IndexedBlock {
+        let header = BlockHeader {
+            raw_data: Some(BlockHeaderRaw {
+                number,
+                parent_hash: H256::from_low_u64_be(parent).as_bytes().to_vec(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        IndexedBlock::new(IndexedBlockHeader::new(H256::from_low_u64_be(hash), header), vec![])
+    }
+
+    #[test]
+    fn test_fork_branches() {
+        // 100 <- 1 <- 2 <- 3
+        //         \- 12 <- 13 <- 14
+        let mut fork_db = ForkDB::new();
+        for blk in vec![
+            block(1, 1, 100),
+            block(2, 2, 1),
+            block(3, 3, 2),
+            block(12, 2, 1),
+            block(13, 3, 12),
+            block(14, 4, 13),
+        ] {
+            fork_db.insert(blk);
+        }
+
+        let hash = H256::from_low_u64_be;
+        let numbers = |branch: Vec<&IndexedBlock>| branch.iter().map(|blk| blk.number()).collect::<Vec<_>>();
+
+        let (new_branch, old_branch) = fork_db.branches(&hash(14), &hash(3)).unwrap();
+        assert_eq!(numbers(new_branch), vec![4, 3, 2]);
+        assert_eq!(numbers(old_branch), vec![3, 2]);
+
+        // common ancestor outside of fork db
+        let (branch_a, branch_b) = fork_db.branches(&hash(3), &hash(1)).unwrap();
+        assert_eq!(numbers(branch_a), vec![3, 2]);
+        assert!(branch_b.is_empty());
+
+        fork_db.insert(block(21, 2, 20));
+        assert!(fork_db.branches(&hash(21), &hash(3)).is_none());
+
+        fork_db.remove(&hash(12));
+        assert!(!fork_db.contains(&hash(13)));
+        assert!(!fork_db.contains(&hash(14)));
+
+        fork_db.prune(2);
+        assert_eq!(fork_db.len(), 1);
+        assert!(fork_db.contains(&hash(3)));
+    }
+}
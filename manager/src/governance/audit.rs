@@ -0,0 +1,232 @@
+//! Reward audit, reconstructed from the per-epoch `VoterReward` state.
+//!
+//! Rewards are accumulated per epoch and per witness, then split between the witness(brokerage) and its voters.
+//! Comparing reports against known-good values pins a reward bug to the first mismatched epoch.
+
+use std::collections::BTreeMap;
+
+use ::keys::Address;
+use proto::state::WitnessVoterReward;
+use serde::{Deserialize, Serialize};
+use state::keys;
+
+use super::reward::RewardUtil;
+use crate::Manager;
+
+/// Reward split of a witness in one epoch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WitnessEpochReward {
+    pub address: String,
+    pub vote_count: i64,
+    /// Brokerage rate, 0-100.
+    pub brokerage: i32,
+    /// Paid to the witness's allowance. Not recorded for epochs before the audit was introduced.
+    pub brokerage_amount: i64,
+    /// Shared by voters, by vote count.
+    pub voters_reward: i64,
+}
+
+/// Accrued reward of the audited voter from a voted witness, in one epoch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoterEpochReward {
+    pub witness: String,
+    pub reward: i64,
+}
+
+/// Reward report of one epoch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochRewardReport {
+    pub epoch: i64,
+    pub witnesses: Vec<WitnessEpochReward>,
+    #[serde(default)]
+    pub voter_rewards: Vec<VoterEpochReward>,
+}
+
+/// A value differs from the expected one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewardMismatch {
+    pub epoch: i64,
+    /// Witness address, or `voter:{witness}` for the voter's reward.
+    pub subject: String,
+    pub field: &'static str,
+    pub expected: Option<i64>,
+    pub actual: Option<i64>,
+}
+
+pub struct RewardAudit<'m> {
+    manager: &'m Manager,
+}
+
+impl RewardAudit<'_> {
+    pub fn new<'a>(manager: &'a Manager) -> RewardAudit<'a> {
+        RewardAudit { manager }
+    }
+
+    /// Report of one epoch. Voter's reward is only reconstructed for epochs since its last vote or withdrawal,
+    /// when its votes are known.
+    pub fn epoch_report(&self, epoch: i64, voter: Option<&Address>) -> Result<EpochRewardReport, String> {
+        let mut witnesses = vec![];
+        self.manager.state_db.for_each_by_prefix(
+            &epoch.to_be_bytes(),
+            |key: &keys::VoterReward, reward: &WitnessVoterReward| {
+                witnesses.push(WitnessEpochReward {
+                    address: key.1.to_string(),
+                    vote_count: reward.vote_count,
+                    brokerage: reward.brokerage,
+                    brokerage_amount: reward.brokerage_amount,
+                    voters_reward: reward.reward_amount,
+                });
+            },
+        )?;
+
+        let mut voter_rewards = vec![];
+        if let Some(addr) = voter {
+            if let Some(votes) = self.manager.state_db.get(&keys::Votes(*addr))? {
+                if votes.epoch <= epoch {
+                    voter_rewards = RewardUtil::new(self.manager)
+                        .compute_reward_by_witness(epoch, &votes)?
+                        .into_iter()
+                        .map(|(wit_addr, reward)| VoterEpochReward {
+                            witness: wit_addr.to_string(),
+                            reward,
+                        })
+                        .collect();
+                }
+            }
+        }
+
+        Ok(EpochRewardReport {
+            epoch,
+            witnesses,
+            voter_rewards,
+        })
+    }
+}
+
+/// Compare reports against expected ones, ordered by epoch. Epochs missing in the expected reports are skipped.
+pub fn compare_reports(actual: &[EpochRewardReport], expected: &[EpochRewardReport]) -> Vec<RewardMismatch> {
+    let actual = actual
+        .iter()
+        .map(|report| (report.epoch, report))
+        .collect::<BTreeMap<_, _>>();
+    let mut expected = expected.iter().collect::<Vec<_>>();
+    expected.sort_by_key(|report| report.epoch);
+
+    let mut mismatches = vec![];
+    for expected in expected {
+        let epoch = expected.epoch;
+        let actual = actual.get(&epoch);
+
+        let actual_witnesses = actual
+            .map(|report| {
+                report
+                    .witnesses
+                    .iter()
+                    .map(|wit| (&wit.address, wit))
+                    .collect::<BTreeMap<_, _>>()
+            })
+            .unwrap_or_default();
+        for wit in &expected.witnesses {
+            let actual = actual_witnesses.get(&wit.address);
+            let fields = [
+                ("voteCount", wit.vote_count, actual.map(|w| w.vote_count)),
+                ("brokerage", wit.brokerage as i64, actual.map(|w| w.brokerage as i64)),
+                (
+                    "brokerageAmount",
+                    wit.brokerage_amount,
+                    actual.map(|w| w.brokerage_amount),
+                ),
+                ("votersReward", wit.voters_reward, actual.map(|w| w.voters_reward)),
+            ];
+            for &(field, expected, actual) in &fields {
+                if Some(expected) != actual {
+                    mismatches.push(RewardMismatch {
+                        epoch,
+                        subject: wit.address.clone(),
+                        field,
+                        expected: Some(expected),
+                        actual,
+                    });
+                }
+            }
+        }
+
+        let actual_voter_rewards = actual
+            .map(|report| {
+                report
+                    .voter_rewards
+                    .iter()
+                    .map(|reward| (&reward.witness, reward.reward))
+                    .collect::<BTreeMap<_, _>>()
+            })
+            .unwrap_or_default();
+        for reward in &expected.voter_rewards {
+            let actual = actual_voter_rewards.get(&reward.witness).copied();
+            if Some(reward.reward) != actual {
+                mismatches.push(RewardMismatch {
+                    epoch,
+                    subject: format!("voter:{}", reward.witness),
+                    field: "reward",
+                    expected: Some(reward.reward),
+                    actual,
+                });
+            }
+        }
+    }
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn witness_reward(address: &str, voters_reward: i64) -> WitnessEpochReward {
+        WitnessEpochReward {
+            address: address.into(),
+            vote_count: 100,
+            brokerage: 20,
+            brokerage_amount: voters_reward / 4,
+            voters_reward,
+        }
+    }
+
+    #[test]
+    fn test_compare_reports() {
+        let actual = vec![
+            EpochRewardReport {
+                epoch: 1,
+                witnesses: vec![witness_reward("A", 800), witness_reward("B", 400)],
+                voter_rewards: vec![],
+            },
+            EpochRewardReport {
+                epoch: 2,
+                witnesses: vec![witness_reward("A", 808)],
+                voter_rewards: vec![VoterEpochReward {
+                    witness: "A".into(),
+                    reward: 80,
+                }],
+            },
+        ];
+        let mut expected = actual.clone();
+        assert!(compare_reports(&actual, &expected).is_empty());
+
+        expected[1].witnesses[0].voters_reward = 800;
+        expected[1].voter_rewards[0].reward = 79;
+        expected.push(EpochRewardReport {
+            epoch: 3,
+            witnesses: vec![witness_reward("A", 800)],
+            voter_rewards: vec![],
+        });
+
+        let mismatches = compare_reports(&actual, &expected);
+        assert_eq!(mismatches.len(), 6);
+        assert_eq!(mismatches[0].epoch, 2);
+        assert_eq!(mismatches[0].field, "votersReward");
+        assert_eq!(mismatches[0].actual, Some(808));
+        assert_eq!(mismatches[1].subject, "voter:A");
+        assert!(mismatches[2..].iter().all(|m| m.epoch == 3 && m.actual.is_none()));
+    }
+}
@@ -7,6 +7,7 @@ use log::{debug, info};
 use proto::state::{Witness, WitnessVoterReward};
 use state::keys;
 
+use super::reward::RewardController;
 use crate::Manager;
 
 /// Massive things done during maintenance.
@@ -23,44 +24,47 @@ impl MaintenanceManager<'_> {
         let next_maintenance_time = self
             .manager
             .state_db
-            .must_get(&keys::DynamicProperty::NextMaintenanceTime);
+            .must_get(&keys::DynamicProperty::NextMaintenanceTime)?;
 
         let is_maintenance = next_maintenance_time <= block.timestamp();
 
         if is_maintenance {
+            // Fees accrued in the ending epoch, paid by its witness schedule.
+            if self
+                .manager
+                .state_db
+                .must_get(&keys::ChainParameter::AllowTransactionFeePool)? !=
+                0
+            {
+                RewardController::new(self.manager).pay_transaction_fee_pool()?;
+            }
             if block.number() != 1 {
                 self.do_maintenance()?;
             } else {
                 // init schedule on first non-genesis block.
-                self.update_witness_schedule();
+                self.update_witness_schedule()?;
             }
             // updateNextMaintenanceTime
             self.increase_next_maintenance_time(next_maintenance_time, block.timestamp())?;
 
             // update epoch and witness reward info
-            let epoch = self
-                .manager
-                .state_db
-                .incr_key(keys::DynamicProperty::CurrentEpoch)
-                .unwrap();
+            let epoch = self.manager.state_db.incr_key(keys::DynamicProperty::CurrentEpoch)?;
             // Only update VoterReward when AllowChangeDelegation is enabled.
             if self
                 .manager
                 .state_db
-                .must_get(&keys::ChainParameter::AllowChangeDelegation) !=
+                .must_get(&keys::ChainParameter::AllowChangeDelegation)? !=
                 0
             {
-                for (wit_addr, vote_count, _) in self.manager.state_db.must_get(&keys::WitnessSchedule) {
-                    self.manager
-                        .state_db
-                        .put_key(
-                            keys::VoterReward(epoch, wit_addr),
-                            WitnessVoterReward {
-                                vote_count,
-                                reward_amount: 0,
-                            },
-                        )
-                        .unwrap();
+                for (wit_addr, vote_count, brokerage) in self.manager.state_db.must_get(&keys::WitnessSchedule)? {
+                    self.manager.state_db.put_key(
+                        keys::VoterReward(epoch, wit_addr),
+                        WitnessVoterReward {
+                            vote_count,
+                            brokerage: brokerage as _,
+                            ..Default::default()
+                        },
+                    )?;
                 }
             }
 
@@ -73,8 +77,7 @@ impl MaintenanceManager<'_> {
         }
         self.manager
             .state_db
-            .put_key(keys::DynamicProperty::IsMaintenance, is_maintenance as _)
-            .unwrap();
+            .put_key(keys::DynamicProperty::IsMaintenance, is_maintenance as _)?;
         Ok(())
     }
 
@@ -82,7 +85,7 @@ impl MaintenanceManager<'_> {
         // 0: default (unremoved)
         // 1: remove now
         // -1: removed
-        if self.manager.state_db.must_get(&keys::ChainParameter::RemovePowerOfGr) == 1 {
+        if self.manager.state_db.must_get(&keys::ChainParameter::RemovePowerOfGr)? == 1 {
             self.remove_power_of_gr()?;
             info!("power of GR gets removed");
         }
@@ -105,15 +108,14 @@ impl MaintenanceManager<'_> {
                 .put_key(keys::DynamicProperty::HasNewVotesInCurrentEpoch, 0)
                 .map_err(|_| "db insert error")?;
 
-            let old_active_witnesses = self.manager.get_active_witnesses();
+            let old_active_witnesses = self.manager.get_active_witnesses()?;
 
             // FIXME: handle votes, unvotes
             let mut witnesses: HashMap<Address, Witness> = HashMap::new();
             for (&wit_addr, &vote_count) in votes.iter() {
-                let mut wit = witnesses
-                    .entry(wit_addr)
-                    .or_insert_with(|| self.manager.state_db.must_get(&keys::Witness(wit_addr)));
+                let mut wit = self.manager.state_db.must_get(&keys::Witness(wit_addr))?;
                 wit.vote_count = vote_count;
+                witnesses.insert(wit_addr, wit);
             }
 
             for (wit_addr, wit) in witnesses.into_iter() {
@@ -124,9 +126,9 @@ impl MaintenanceManager<'_> {
                     .map_err(|_| "db insert error")?;
             }
 
-            self.update_witness_schedule();
+            self.update_witness_schedule()?;
 
-            let new_active_witnesses = self.manager.get_active_witnesses();
+            let new_active_witnesses = self.manager.get_active_witnesses()?;
 
             if old_active_witnesses != new_active_witnesses {
                 for (idx, (old_wit_addr, new_wit_addr)) in
@@ -143,10 +145,10 @@ impl MaintenanceManager<'_> {
             if self
                 .manager
                 .state_db
-                .must_get(&keys::ChainParameter::AllowChangeDelegation) ==
+                .must_get(&keys::ChainParameter::AllowChangeDelegation)? ==
                 0
             {
-                self.legacy_reward_standby_witnesses()
+                self.legacy_reward_standby_witnesses()?;
             }
         }
 
@@ -163,7 +165,7 @@ impl MaintenanceManager<'_> {
             let votes = &mut votes;
             self.manager.state_db.for_each(move |_key: &keys::Witness, wit| {
                 votes.insert(*Address::from_bytes(&wit.address), wit.vote_count);
-            });
+            })?;
         }
         Ok(votes)
     }
@@ -177,14 +179,13 @@ impl MaintenanceManager<'_> {
         let maintenance_interval = self
             .manager
             .state_db
-            .must_get(&keys::ChainParameter::MaintenanceInterval);
+            .must_get(&keys::ChainParameter::MaintenanceInterval)?;
 
         let round = (block_ts - current_next_maintenance_time) / maintenance_interval;
         let next_maintenance_time = current_next_maintenance_time + (round + 1) * maintenance_interval;
         self.manager
             .state_db
-            .put_key(keys::DynamicProperty::NextMaintenanceTime, next_maintenance_time)
-            .unwrap();
+            .put_key(keys::DynamicProperty::NextMaintenanceTime, next_maintenance_time)?;
         Ok(())
     }
 
@@ -193,12 +194,12 @@ impl MaintenanceManager<'_> {
     /// NOTE: Witness re-scheduling only occurs when new votes found.
     /// So when removing power of GR, witness schedule's vote_count should be updated as well.
     fn remove_power_of_gr(&mut self) -> Result<(), String> {
-        let mut wit_sched = self.manager.state_db.must_get(&keys::WitnessSchedule);
+        let mut wit_sched = self.manager.state_db.must_get(&keys::WitnessSchedule)?;
         debug!("before => {:?}", wit_sched);
         for gr_wit in &self.manager.genesis_config.witnesses {
             let addr = gr_wit.address.parse::<Address>().expect("address format error");
 
-            let mut witness = self.manager.state_db.must_get(&keys::Witness(addr));
+            let mut witness = self.manager.state_db.must_get(&keys::Witness(addr))?;
             witness.vote_count -= gr_wit.votes;
             self.manager
                 .state_db
@@ -222,13 +223,13 @@ impl MaintenanceManager<'_> {
     }
 
     // DposService.updateWitness
-    fn update_witness_schedule(&mut self) {
+    fn update_witness_schedule(&mut self) -> Result<(), String> {
         let mut wit_sched: Vec<(Address, i64, u8)> = Vec::new();
         {
             let wit_sched = &mut wit_sched;
             self.manager.state_db.for_each(move |key: &keys::Witness, value| {
                 wit_sched.push((key.0, value.vote_count, value.brokerage as u8));
-            });
+            })?;
         }
 
         // NOTE: This is different from java-tron. In OpenTron, raw address is used as final fallback sorting key.
@@ -244,7 +245,8 @@ impl MaintenanceManager<'_> {
             let _ = wit_sched.split_off(constants::MAX_NUM_OF_STANDBY_WITNESSES);
         }
 
-        self.manager.state_db.put_key(keys::WitnessSchedule, wit_sched).unwrap();
+        self.manager.state_db.put_key(keys::WitnessSchedule, wit_sched)?;
+        Ok(())
     }
 
     /// `IncentiveManager.reward`, only when `AllowChangeDelegation = false`.
@@ -252,30 +254,36 @@ impl MaintenanceManager<'_> {
     /// Not used by testnet, but is used on mainnet.
     ///
     /// This is done after vote couting.
-    fn legacy_reward_standby_witnesses(&mut self) {
-        let addrs = self.manager.get_standby_witnesses();
-        let vote_counts: Vec<_> = addrs
+    fn legacy_reward_standby_witnesses(&mut self) -> Result<(), String> {
+        let addrs = self.manager.get_standby_witnesses()?;
+        let vote_counts = addrs
             .iter()
-            .map(|&addr| self.manager.state_db.must_get(&keys::Witness(addr)).vote_count)
-            .collect();
+            .map(|&addr| {
+                self.manager
+                    .state_db
+                    .must_get(&keys::Witness(addr))
+                    .map(|wit| wit.vote_count)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         let total_vote_count: i64 = vote_counts.iter().sum();
         let total_pay = self
             .manager
             .state_db
-            .must_get(&keys::ChainParameter::StandbyWitnessAllowance);
+            .must_get(&keys::ChainParameter::StandbyWitnessAllowance)?;
         let pay_per_vote = total_pay as f64 / total_vote_count as f64;
 
         if total_pay != 0 {
             for (addr, vote_weight) in addrs.into_iter().zip(vote_counts.into_iter()) {
                 let pay = (vote_weight as f64 * pay_per_vote) as i64;
                 if pay != 0 {
-                    let mut acct = self.manager.state_db.must_get(&keys::Account(addr));
+                    let mut acct = self.manager.state_db.must_get(&keys::Account(addr))?;
                     acct.allowance += pay;
-                    self.manager.state_db.put_key(keys::Account(addr), acct).unwrap();
+                    self.manager.state_db.put_key(keys::Account(addr), acct)?;
                 }
             }
         }
+        Ok(())
     }
 }
 
@@ -1,5 +1,6 @@
 //! Chain governance related.
 
+pub mod audit;
 pub mod maintenance;
 pub mod proposal;
 pub mod reward;
@@ -21,7 +21,10 @@ impl ProposalController<'_> {
     }
 
     pub fn process_proposals(&mut self) -> Result<(), String> {
-        let latest_proposal_id = self.manager.state_db.must_get(&keys::DynamicProperty::LatestProposalId);
+        let latest_proposal_id = self
+            .manager
+            .state_db
+            .must_get(&keys::DynamicProperty::LatestProposalId)?;
         if latest_proposal_id == 0 {
             debug!("no proposal yet");
             return Ok(());
@@ -29,7 +32,7 @@ impl ProposalController<'_> {
 
         // NOTE: proposals are handled in reverse order
         for proposal_id in (1..=latest_proposal_id).rev() {
-            let proposal = self.manager.state_db.must_get(&keys::Proposal(proposal_id));
+            let proposal = self.manager.state_db.must_get(&keys::Proposal(proposal_id))?;
 
             if proposal.is_processed() {
                 debug!("proposal #{} is processed", proposal_id);
@@ -45,7 +48,7 @@ impl ProposalController<'_> {
             let current_maintenance_time = self
                 .manager
                 .state_db
-                .must_get(&keys::DynamicProperty::NextMaintenanceTime);
+                .must_get(&keys::DynamicProperty::NextMaintenanceTime)?;
             if proposal.expiration_time <= current_maintenance_time {
                 info!(
                     "proposal #{} expired, counting votes of active witnesses...",
@@ -61,7 +64,7 @@ impl ProposalController<'_> {
     }
 
     fn process_proposal(&mut self, mut proposal: Proposal) -> Result<(), String> {
-        let active_witnesses = self.manager.get_active_witnesses();
+        let active_witnesses = self.manager.get_active_witnesses()?;
         if active_witnesses.len() != constants::MAX_NUM_OF_ACTIVE_WITNESSES {
             info!("current number of active witnesses: {}", active_witnesses.len());
         }
@@ -131,7 +134,7 @@ impl ProposalUtil<'_> {
             CreateNewAccountFeeInSystemContract |
             CreateNewAccountBandwidthRate => self.accept_long_value(value),
             RemovePowerOfGr => {
-                if self.manager.state_db.must_get(&ChainParameter::RemovePowerOfGr) == -1 {
+                if self.manager.state_db.must_get(&ChainParameter::RemovePowerOfGr)? == -1 {
                     return Err("power of gr is already removed".into());
                 }
                 self.accept_true(value)
@@ -258,7 +261,7 @@ impl ProposalUtil<'_> {
     }
 
     fn require_proposal(&self, parameter: ChainParameter) -> Result<(), String> {
-        if self.manager.state_db.must_get(&parameter) == 0 {
+        if self.manager.state_db.must_get(&parameter)? == 0 {
             return Err(format!("{:?} is required before this proposal", parameter));
         }
         Ok(())
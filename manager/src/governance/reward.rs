@@ -24,23 +24,23 @@ impl RewardController<'_> {
     pub fn pay_reward(&mut self, block: &IndexedBlock) -> Result<(), String> {
         let curr_wit_addr = *Address::from_bytes(block.witness());
 
-        let wit_sched = self.manager.state_db.must_get(&keys::WitnessSchedule);
+        let wit_sched = self.manager.state_db.must_get(&keys::WitnessSchedule)?;
 
         let mut wit_accts = Vec::with_capacity(constants::MAX_NUM_OF_ACTIVE_WITNESSES);
         let mut total_votes = 0_i64;
         for &(wit_addr, vote_count, _) in &wit_sched {
-            wit_accts.push(self.manager.state_db.must_get(&keys::Account(wit_addr)));
+            wit_accts.push(self.manager.state_db.must_get(&keys::Account(wit_addr))?);
             total_votes += vote_count;
         }
 
         let block_reward = self
             .manager
             .state_db
-            .must_get(&keys::ChainParameter::WitnessPayPerBlock);
+            .must_get(&keys::ChainParameter::WitnessPayPerBlock)?;
         let standby_reward = self
             .manager
             .state_db
-            .must_get(&keys::ChainParameter::StandbyWitnessPayPerBlock);
+            .must_get(&keys::ChainParameter::StandbyWitnessPayPerBlock)?;
 
         // NOTE: When there're no votes at all, none will be paid to standby witnesses.
         let standby_pay_per_vote = if total_votes > 0 {
@@ -49,10 +49,11 @@ impl RewardController<'_> {
             0.0
         };
 
-        let epoch = self.manager.state_db.must_get(&keys::DynamicProperty::CurrentEpoch);
+        let epoch = self.manager.state_db.must_get(&keys::DynamicProperty::CurrentEpoch)?;
         // payReward
         for ((wit_addr, vote_count, brokerage), mut wit_acct) in wit_sched.into_iter().zip(wit_accts.into_iter()) {
             let mut voters_reward = 0;
+            let mut total_brokerage_amount = 0;
             // payStandbyWitness
             let brokerage_rate = brokerage as f64 / 100.0;
             let pay = (standby_pay_per_vote * vote_count as f64) as i64;
@@ -60,6 +61,7 @@ impl RewardController<'_> {
             if pay > 0 {
                 let brokerage_amount = (pay as f64 * brokerage_rate) as i64;
                 voters_reward += pay - brokerage_amount;
+                total_brokerage_amount += brokerage_amount;
                 if wit_acct.adjust_allowance(brokerage_amount).is_err() {
                     return Err("math overflow while adding brokerage amount".into());
                 }
@@ -69,13 +71,14 @@ impl RewardController<'_> {
             if wit_addr == curr_wit_addr {
                 let brokerage_amount = (block_reward as f64 * brokerage_rate) as i64;
                 voters_reward += block_reward - brokerage_amount;
+                total_brokerage_amount += brokerage_amount;
                 if wit_acct.adjust_allowance(brokerage_amount).is_err() {
                     return Err("math overflow while adding brokerage amount".into());
                 }
             }
             // save reward of voters
             // delegationStore.addReward(cycle, witnessAddress, value);
-            self.add_voter_reward(epoch, wit_addr, voters_reward);
+            self.add_voter_reward(epoch, wit_addr, voters_reward, total_brokerage_amount)?;
             self.manager
                 .state_db
                 .put_key(keys::Account(wit_addr), wit_acct)
@@ -85,12 +88,70 @@ impl RewardController<'_> {
         Ok(())
     }
 
-    fn add_voter_reward(&mut self, epoch: i64, wit_addr: Address, amount: i64) {
+    /// Pay the transaction fee pool to active witnesses by vote count, since `AllowTransactionFeePool`.
+    ///
+    /// Done at maintenance, before entering the next epoch. Pays are split by brokerage like block rewards.
+    /// Rounding remains are kept in the pool.
+    pub fn pay_transaction_fee_pool(&mut self) -> Result<(), String> {
+        let pool = self
+            .manager
+            .state_db
+            .must_get(&keys::DynamicProperty::TransactionFeePool)?;
+        let wit_sched = self.manager.state_db.must_get(&keys::WitnessSchedule)?;
+        let total_votes: i64 = wit_sched.iter().map(|&(_, vote_count, _)| vote_count).sum();
+        if pool <= 0 || total_votes <= 0 {
+            return Ok(());
+        }
+
+        let allow_change_delegation = self
+            .manager
+            .state_db
+            .must_get(&keys::ChainParameter::AllowChangeDelegation)? !=
+            0;
+        let epoch = self.manager.state_db.must_get(&keys::DynamicProperty::CurrentEpoch)?;
+
+        let mut total_paid = 0;
+        for (wit_addr, vote_count, brokerage) in wit_sched {
+            let pay = ((pool as i128) * (vote_count as i128) / (total_votes as i128)) as i64;
+            if pay <= 0 {
+                continue;
+            }
+            total_paid += pay;
+
+            let mut wit_acct = self.manager.state_db.must_get(&keys::Account(wit_addr))?;
+            if allow_change_delegation {
+                let brokerage_amount = (pay as f64 * (brokerage as f64 / 100.0)) as i64;
+                if wit_acct.adjust_allowance(brokerage_amount).is_err() {
+                    return Err("math overflow while adding brokerage amount".into());
+                }
+                self.add_voter_reward(epoch, wit_addr, pay - brokerage_amount, brokerage_amount)?;
+            } else if wit_acct.adjust_allowance(pay).is_err() {
+                return Err("math overflow while adding transaction fee reward".into());
+            }
+            self.manager.state_db.put_key(keys::Account(wit_addr), wit_acct)?;
+        }
+        debug!("pay transaction fee pool {}/{} at epoch {}", total_paid, pool, epoch);
+
+        self.manager
+            .state_db
+            .put_key(keys::DynamicProperty::TransactionFeePool, pool - total_paid)?;
+        Ok(())
+    }
+
+    fn add_voter_reward(
+        &mut self,
+        epoch: i64,
+        wit_addr: Address,
+        amount: i64,
+        brokerage_amount: i64,
+    ) -> Result<(), String> {
         assert!(amount >= 0, "voter reward must be greater than or equal to 0");
         let key = keys::VoterReward(epoch, wit_addr);
-        let mut reward = self.manager.state_db.must_get(&key);
+        let mut reward = self.manager.state_db.must_get(&key)?;
         reward.reward_amount += amount;
-        self.manager.state_db.put_key(key, reward).unwrap();
+        reward.brokerage_amount += brokerage_amount;
+        self.manager.state_db.put_key(key, reward)?;
+        Ok(())
     }
 
     // withdrawReward
@@ -99,7 +160,7 @@ impl RewardController<'_> {
         if self
             .manager
             .state_db
-            .must_get(&keys::ChainParameter::AllowChangeDelegation) ==
+            .must_get(&keys::ChainParameter::AllowChangeDelegation)? ==
             0
         {
             return Ok(());
@@ -111,12 +172,12 @@ impl RewardController<'_> {
             .get(&keys::Votes(addr))
             .map_err(|_| "db query error")?
         {
-            let curr_epoch = self.manager.state_db.must_get(&keys::DynamicProperty::CurrentEpoch);
+            let curr_epoch = self.manager.state_db.must_get(&keys::DynamicProperty::CurrentEpoch)?;
             if votes.epoch == curr_epoch {
                 return Ok(());
             }
 
-            let mut acct = self.manager.state_db.must_get(&keys::Account(addr));
+            let mut acct = self.manager.state_db.must_get(&keys::Account(addr))?;
 
             let begin_epoch = votes.epoch;
             let mut reward_amount = 0;
@@ -152,7 +213,7 @@ impl RewardUtil<'_> {
         let allow_change_delegation = self
             .manager
             .state_db
-            .must_get(&keys::ChainParameter::AllowChangeDelegation) !=
+            .must_get(&keys::ChainParameter::AllowChangeDelegation)? !=
             0;
         if !allow_change_delegation {
             return Ok(0);
@@ -164,7 +225,7 @@ impl RewardUtil<'_> {
             .get(&keys::Votes(addr))
             .map_err(|_| "db query error")?
         {
-            let curr_epoch = self.manager.state_db.must_get(&keys::DynamicProperty::CurrentEpoch);
+            let curr_epoch = self.manager.state_db.must_get(&keys::DynamicProperty::CurrentEpoch)?;
             if votes.epoch == curr_epoch {
                 return Ok(0);
             }
@@ -181,7 +242,16 @@ impl RewardUtil<'_> {
     }
 
     fn compute_reward(&self, epoch: i64, votes: &Votes) -> Result<i64, String> {
-        let mut reward_amount = 0_i64;
+        Ok(self
+            .compute_reward_by_witness(epoch, votes)?
+            .into_iter()
+            .map(|(_, reward_amount)| reward_amount)
+            .sum())
+    }
+
+    /// Reward of votes in one epoch, by voted witness.
+    pub fn compute_reward_by_witness(&self, epoch: i64, votes: &Votes) -> Result<Vec<(Address, i64)>, String> {
+        let mut rewards = Vec::with_capacity(votes.votes.len());
         for vote in &votes.votes {
             let wit_addr = *Address::from_bytes(&vote.vote_address);
             if let Some(total_reward) = self
@@ -194,9 +264,60 @@ impl RewardUtil<'_> {
                     continue;
                 }
                 let vote_rate = vote.vote_count as f64 / total_reward.vote_count as f64;
-                reward_amount += (vote_rate * total_reward.reward_amount as f64) as i64;
+                rewards.push((wit_addr, (vote_rate * total_reward.reward_amount as f64) as i64));
             }
         }
-        Ok(reward_amount)
+        Ok(rewards)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use config::{Config, GenesisConfig};
+    use state::db::StateDB;
+
+    use super::*;
+
+    #[test]
+    fn test_pay_transaction_fee_pool() {
+        let config = Config::load_from_str(include_str!("../../../etc/conf.toml")).unwrap();
+        let genesis_config = GenesisConfig::load_from_str(include_str!("../../../etc/genesis.json")).unwrap();
+        let mut manager = Manager::with_state_db(StateDB::new_in_memory(), &config, &genesis_config);
+
+        let wit_addrs: Vec<Address> = genesis_config.witnesses[..2]
+            .iter()
+            .map(|wit| wit.address.parse().unwrap())
+            .collect();
+        manager.state_db.new_layer();
+        manager
+            .state_db
+            .put_key(keys::ChainParameter::AllowChangeDelegation, 0)
+            .unwrap();
+        manager
+            .state_db
+            .put_key(
+                keys::WitnessSchedule,
+                vec![(wit_addrs[0], 3, 20), (wit_addrs[1], 1, 20)],
+            )
+            .unwrap();
+        manager
+            .state_db
+            .put_key(keys::DynamicProperty::TransactionFeePool, 101)
+            .unwrap();
+
+        RewardController::new(&mut manager).pay_transaction_fee_pool().unwrap();
+
+        let allowance =
+            |manager: &Manager, addr: Address| manager.state_db.must_get(&keys::Account(addr)).unwrap().allowance;
+        assert_eq!(allowance(&manager, wit_addrs[0]), 75);
+        assert_eq!(allowance(&manager, wit_addrs[1]), 25);
+        // Rounding remains are kept.
+        assert_eq!(
+            manager
+                .state_db
+                .must_get(&keys::DynamicProperty::TransactionFeePool)
+                .unwrap(),
+            1
+        );
     }
 }
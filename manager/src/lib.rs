@@ -1,26 +1,43 @@
 #![feature(asm)]
 
-use ::keys::{b58encode_check, Address};
+use ::keys::{b58encode_check, Address, KeyPair};
 use chain::{IndexedBlock, IndexedBlockHeader, IndexedTransaction};
 use chrono::Utc;
 use config::{Config, GenesisConfig};
 use log::{debug, info, trace, warn};
 use primitive_types::H256;
 use prost::Message;
-use proto::state::TransactionReceipt;
-use state::db::StateDB;
+use proto::channel::PbftMessage;
+use proto::state::{TransactionReceipt, WitnessPerformance};
+use state::db::{KeyValueDB, Savepoint, StateDB};
 use state::keys;
+use state::StateError;
+use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 
+use self::dry_run::{BlockDryRun, StateDiff};
+use self::event::{ChainEvent, ChainEventHandler};
+use self::executor::mismatch::MismatchReporter;
+use self::executor::registry::{ActuatorRegistry, ContractActuator};
 use self::executor::TransactionExecutor;
+use self::fork::ForkDB;
 use self::governance::maintenance::MaintenanceManager;
 use self::governance::proposal::ProposalController;
 use self::governance::reward::RewardController;
+use self::pbft::PbftState;
 use self::resource::EnergyProcessor;
+use self::verifier::{RecoveredOwners, RefBlockHashes, VerifiedBlock};
 
+pub mod dry_run;
+pub mod event;
 pub mod executor;
+pub mod fork;
 pub mod governance;
+pub mod pbft;
+pub mod producer;
 pub mod resource;
+pub mod txpool;
+pub mod verifier;
 pub mod version_fork;
 pub mod vm;
 
@@ -36,27 +53,52 @@ fn new_error(msg: &str) -> Error {
 
 /// State DB Manager.
 pub struct Manager {
-    state_db: StateDB,
+    // Any backend, RocksDB for the node, or in memory for tests.
+    state_db: Box<StateDB<dyn KeyValueDB>>,
     genesis_block_timestamp: i64,
+    // CHAINID of TVM.
+    genesis_block_hash: H256,
     blackhole: Address,
     my_witness: Vec<u8>,
 
     block_energy_usage: i64,
     // TaPoS check, size = 65536, 2MB.
-    ref_block_hashes: Vec<H256>,
+    ref_block_hashes: RefBlockHashes,
+    // Unsolidified blocks of all forks.
+    fork_db: ForkDB,
+    event_handler: Option<ChainEventHandler>,
+    mismatch_reporter: MismatchReporter,
+    // Actuators of builtin contracts, and custom ones of private chains.
+    actuators: ActuatorRegistry,
+    pbft: PbftState,
     config: Config,
     genesis_config: GenesisConfig,
     maintenance_started_at: i64,
+    // Block latency of witnesses, by `(witness, epoch)`. Depends on wall clock, so not part of the state.
+    block_latency: HashMap<(Address, i64), BlockLatency>,
+}
 
-    layers: usize,
+impl AsMut<StateDB<dyn KeyValueDB>> for Manager {
+    fn as_mut(&mut self) -> &mut StateDB<dyn KeyValueDB> {
+        &mut self.state_db
+    }
 }
 
 impl Manager {
     pub fn new(config: &Config, genesis_config: &GenesisConfig) -> Self {
-        let mut state_db = StateDB::new(&config.storage.state_data_dir);
+        let state_db = StateDB::new(&config.storage.state_data_dir);
+        Manager::with_state_db(state_db, config, genesis_config)
+    }
 
+    /// Create a manager on the given state-db, e.g. `StateDB::new_in_memory()`.
+    pub fn with_state_db<D: KeyValueDB + 'static>(
+        mut state_db: StateDB<D>,
+        config: &Config,
+        genesis_config: &GenesisConfig,
+    ) -> Self {
         state_db.init_genesis(&genesis_config, &config.chain).unwrap();
         let genesis_block_timestamp = genesis_config.timestamp;
+        let genesis_block_hash = *genesis_config.to_indexed_block().expect("invalid genesis block").hash();
 
         let blackhole = genesis_config
             .allocs
@@ -67,76 +109,174 @@ impl Manager {
 
         debug!("loaded the Blackhole address {}", blackhole);
 
+        let my_witness = if config.witness.private_key.is_empty() {
+            vec![]
+        } else if !config.witness.address.is_empty() {
+            let addr: Address = config.witness.address.parse().expect("invalid witness address");
+            info!("witness address {}", addr);
+            addr.as_bytes().to_vec()
+        } else {
+            let private = config.witness.private_key.parse().expect("invalid witness private key");
+            let keypair = KeyPair::from_private(private).expect("invalid witness private key");
+            info!("witness address {}", keypair.address());
+            keypair.address().as_bytes().to_vec()
+        };
+
+        // Signs PBFT messages when the witness is active.
+        let pbft_keypair = if config.witness.private_key.is_empty() {
+            None
+        } else {
+            let private = config.witness.private_key.parse().expect("invalid witness private key");
+            Some(KeyPair::from_private(private).expect("invalid witness private key"))
+        };
+
         Manager {
-            state_db,
+            state_db: Box::new(state_db),
             genesis_block_timestamp,
+            genesis_block_hash,
             blackhole,
-            my_witness: vec![],
+            my_witness,
             block_energy_usage: 0,
-            ref_block_hashes: Vec::with_capacity(65536),
+            ref_block_hashes: RefBlockHashes::default(),
+            fork_db: ForkDB::new(),
+            event_handler: None,
+            mismatch_reporter: MismatchReporter::new(&config.verification).expect("can not open mismatch report file"),
+            actuators: ActuatorRegistry::default(),
+            pbft: PbftState::new(pbft_keypair),
             config: config.clone(),
             genesis_config: genesis_config.clone(),
             maintenance_started_at: 0,
-            layers: 0,
+            block_latency: HashMap::new(),
         }
     }
 
-    pub fn state(&self) -> &StateDB {
+    pub fn state(&self) -> &StateDB<dyn KeyValueDB> {
         &self.state_db
     }
 
+    /// Block latency of a witness in an epoch, since started.
+    pub fn block_latency(&self, witness: &Address, epoch: i64) -> Option<&BlockLatency> {
+        self.block_latency.get(&(*witness, epoch))
+    }
+
+    fn record_block_latency(&mut self, witness: Address, epoch: i64, latency: i64) {
+        if latency < 0 || latency >= MAX_TRACKED_BLOCK_LATENCY {
+            return;
+        }
+        self.block_latency
+            .retain(|&(_, ep), _| ep + NUM_OF_LATENCY_TRACKED_EPOCHS > epoch);
+        let stat = self.block_latency.entry((witness, epoch)).or_default();
+        stat.num_samples += 1;
+        stat.total += latency;
+        stat.max = stat.max.max(latency);
+    }
+
+    /// Number of transaction results mismatched with the ones embedded in blocks, since started.
+    pub fn num_result_mismatches(&self) -> usize {
+        self.mismatch_reporter.num_mismatches()
+    }
+
     pub fn init_ref_blocks(&mut self, hashes: Vec<H256>) {
         debug!("update num of ref_hashes => {:?}", hashes.len());
-        self.ref_block_hashes = hashes;
+        self.ref_block_hashes = RefBlockHashes::new(hashes);
     }
 
-    fn update_ref_blocks(&mut self, new_hash: H256) {
-        if self.ref_block_hashes.len() < 65536 {
-            self.ref_block_hashes.push(new_hash);
-        } else {
-            let ref_block_bytes = {
-                let mut raw = [0u8; 2];
-                raw.copy_from_slice(&new_hash.as_bytes()[6..8]);
-                raw
-            };
-            let ref_slot_index = u16::from_be_bytes(ref_block_bytes) as usize;
-            self.ref_block_hashes[ref_slot_index] = new_hash;
+    /// Put the latest block into the fork db on startup, so that forks of it can be linked.
+    pub fn init_fork_db(&mut self, latest_block: IndexedBlock) -> Result<()> {
+        if *latest_block.hash() != self.latest_block_hash()? {
+            return Err(new_error("not the latest block of state-db"));
         }
+        self.fork_db.insert(latest_block);
+        Ok(())
     }
 
+    /// Recent block hashes, used to start a block verifying pipeline.
+    pub fn ref_block_hashes(&self) -> &RefBlockHashes {
+        &self.ref_block_hashes
+    }
+
+    /// Burn tokens, always credited to the Blackhole account.
     pub fn add_token_to_blackhole(&mut self, token_id: i64, fee: i64) -> Result<()> {
         let key = keys::Account(self.blackhole);
-        let mut blackhole_acct = self.state_db.must_get(&key);
+        let mut blackhole_acct = self.state_db.must_get(&key)?;
         blackhole_acct.adjust_token_balance(token_id, fee).unwrap();
-        self.state_db.put_key(key, blackhole_acct).unwrap();
+        self.state_db.put_key(key, blackhole_acct)?;
         Ok(())
     }
 
+    /// Burn TRX. Since `AllowBlackholeOptimization`, only a counter is increased instead of the Blackhole balance.
     pub fn add_to_blackhole(&mut self, fee: i64) -> Result<()> {
+        if self
+            .state_db
+            .must_get(&keys::ChainParameter::AllowBlackholeOptimization)? !=
+            0
+        {
+            let burned = self.state_db.must_get(&keys::DynamicProperty::BurnedTrx)?;
+            self.state_db.put_key(keys::DynamicProperty::BurnedTrx, burned + fee)?;
+            return Ok(());
+        }
         let key = keys::Account(self.blackhole);
-        let mut blackhole_acct = self.state_db.must_get(&key);
+        let mut blackhole_acct = self.state_db.must_get(&key)?;
         blackhole_acct.balance += fee;
-        self.state_db.put_key(key, blackhole_acct).unwrap();
+        self.state_db.put_key(key, blackhole_acct)?;
         Ok(())
     }
 
-    fn new_layer(&mut self) {
-        self.layers += 1;
-        self.state_db.new_layer();
+    /// Charge bandwidth and energy fees. Since `AllowTransactionFeePool`, they are accrued in the fee pool and paid
+    /// to witnesses at maintenance, otherwise burnt.
+    pub fn add_transaction_fee(&mut self, fee: i64) -> Result<()> {
+        if self.state_db.must_get(&keys::ChainParameter::AllowTransactionFeePool)? != 0 {
+            let pool = self.state_db.must_get(&keys::DynamicProperty::TransactionFeePool)?;
+            self.state_db
+                .put_key(keys::DynamicProperty::TransactionFeePool, pool + fee)?;
+            return Ok(());
+        }
+        self.add_to_blackhole(fee)
     }
 
-    fn commit_current_layers(&mut self) {
-        for _ in 0..self.layers {
-            self.state_db.solidify_layer();
+    /// Handle a PBFT message from the network. Returns whether the message is new, and should be relayed.
+    pub fn handle_pbft_message(&mut self, msg: &PbftMessage) -> Result<bool> {
+        if self.state_db.must_get(&keys::ChainParameter::AllowPbft)? == 0 {
+            return Ok(false);
         }
-        self.layers = 0;
+        match msg.raw_data.as_ref() {
+            Some(raw) if raw.view_n > self.solid_block_number()? => {}
+            _ => return Ok(false),
+        }
+        let signers = self.get_pbft_signers()?;
+        Ok(self.pbft.on_message(msg, &signers))
     }
 
-    fn rollback_layers(&mut self, n: usize) {
-        for _ in 0..n {
-            self.state_db.discard_last_layer().unwrap();
+    /// PBFT messages signed by this node, to be broadcasted.
+    pub fn take_pbft_messages(&mut self) -> Vec<PbftMessage> {
+        self.pbft.take_messages()
+    }
+
+    /// Set the receiver of chain events.
+    pub fn set_event_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(ChainEvent) + Send + Sync + 'static,
+    {
+        self.event_handler = Some(Box::new(handler));
+    }
+
+    /// Register the actuator of a contract type, replacing the builtin one if any. For private chains.
+    pub fn register_actuator<A>(&mut self, cntr_type: proto::chain::ContractType, actuator: A)
+    where
+        A: ContractActuator + 'static,
+    {
+        self.actuators.register(cntr_type, actuator);
+    }
+
+    fn emit_event(&self, event: ChainEvent) {
+        if let Some(handler) = self.event_handler.as_ref() {
+            handler(event);
         }
-        self.layers -= n;
+    }
+
+    /// Create a savepoint, all state changes made through it are discarded on drop, unless committed.
+    pub fn savepoint(&mut self) -> Savepoint<'_, Manager, dyn KeyValueDB> {
+        Savepoint::new(self)
     }
 
     // Entry of db manager.
@@ -146,15 +286,7 @@ impl Manager {
         }
 
         // . verify witness signature
-        if self.my_witness.is_empty() || block.witness() != &*self.my_witness {
-            let recovered = block.recover_witness()?;
-            if self.state_db.must_get(&keys::ChainParameter::AllowMultisig) == 1 {
-                // warn!("TODO: handle multisig witness");
-            }
-            if recovered.as_bytes() != block.witness() {
-                return Err(new_error("verifying block witness signature failed"));
-            }
-        }
+        self.verify_witness_signature(block, block.recover_witness())?;
 
         // . verify merkle root hash of transaction
         if !block.verify_merkle_root_hash() {
@@ -164,24 +296,58 @@ impl Manager {
             )));
         }
 
-        // TODO: check dup block? (StateManager.receiveBlock)
+        self.push_block_unchecked(block, None)
+    }
 
-        // NOTE: mainnet does not support shielded TRC10 transaction. No need to check shielded transaction count.
+    /// Push a block produced and signed by this node, skipping signature verification.
+    pub(crate) fn push_produced_block(&mut self, block: &IndexedBlock) -> Result<bool> {
+        if block.number() <= 0 {
+            panic!("only accepts block number > 1");
+        }
+        self.push_block_unchecked(block, None)
+    }
 
-        // . reject smaller block number
-        if block.number() <= self.latest_block_number() {
-            warn!(
-                "reject smaller block number latest={}, got={}",
-                self.latest_block_number(),
-                block.number()
-            );
-            return Ok(false);
+    /// Push a block from the verifying pipeline, signatures and merkle root hash are already checked.
+    pub fn push_verified_block(&mut self, verified: VerifiedBlock) -> Result<bool> {
+        let VerifiedBlock {
+            block,
+            witness,
+            merkle_root_verified,
+            tapos_verified,
+            transaction_owners,
+        } = verified;
+        if block.number() <= 0 {
+            panic!("only accepts block number > 1");
+        }
+
+        self.verify_witness_signature(&block, witness)?;
+        if !merkle_root_verified {
+            return Err(new_error(&format!(
+                "verify block merkle root hash failed, block={}",
+                block.number(),
+            )));
         }
+        if tapos_verified == Some(false) {
+            return Err(new_error(&format!("tapos validation failed, block={}", block.number())));
+        }
+
+        self.push_block_unchecked(&block, Some(transaction_owners))
+    }
 
-        if block.parent_hash() != self.latest_block_hash().as_bytes() {
-            warn!("TODO: handle chain fork!");
-            return Err(new_error("chain fork!"));
+    fn verify_witness_signature(&self, block: &IndexedBlock, recovered: Result<Address, ::keys::Error>) -> Result<()> {
+        let witness = Address::try_from(block.witness()).map_err(|_| new_error("invalid witness address"))?;
+        if recovered? != self.get_witness_signature_key(&witness)? {
+            return Err(new_error("verifying block witness signature failed"));
         }
+        Ok(())
+    }
+
+    fn push_block_unchecked(
+        &mut self,
+        block: &IndexedBlock,
+        transaction_owners: Option<RecoveredOwners>,
+    ) -> Result<bool> {
+        // NOTE: mainnet does not support shielded TRC10 transaction. No need to check shielded transaction count.
 
         // . block version check
         if block.version() > constants::CURRENT_BLOCK_VERSION as i32 {
@@ -191,16 +357,103 @@ impl Manager {
             );
         }
 
-        // basic check finished, begin process block
-        let started_at = Utc::now().timestamp_nanos();
-        self.new_layer();
+        // . reject solidified or known blocks
+        let solid_block_number = self.solid_block_number()?;
+        if block.number() <= solid_block_number {
+            warn!(
+                "reject block below solid block, solid={}, got={}",
+                solid_block_number,
+                block.number()
+            );
+            return Ok(false);
+        }
+        let latest_block_hash = self.latest_block_hash()?;
+        let latest_block_number = self.latest_block_number()?;
+        if self.fork_db.contains(block.hash()) || *block.hash() == latest_block_hash {
+            debug!("duplicated block #{} {:?}", block.number(), block.hash());
+            return Ok(false);
+        }
 
-        // . applyBlock = processBlock + updateFork
-        self.process_block(block)?;
+        if block.parent_hash() == latest_block_hash.as_bytes() {
+            if block.number() != latest_block_number + 1 {
+                return Err(new_error(&format!("malformed block number #{}", block.number())));
+            }
+            self.apply_block(block, transaction_owners, false)?;
+            self.fork_db.insert(block.clone());
+            self.solidify_layers(solid_block_number)?;
+            return Ok(true);
+        }
 
+        // . a block on another fork
+        if !self.fork_db.contains(&H256::from_slice(block.parent_hash())) {
+            if block.number() <= latest_block_number {
+                warn!(
+                    "reject smaller block number latest={}, got={}",
+                    latest_block_number,
+                    block.number()
+                );
+                return Ok(false);
+            }
+            return Err(new_error(&format!(
+                "unlinked block #{}, parent block not found",
+                block.number()
+            )));
+        }
+        self.fork_db.insert(block.clone());
+        if block.number() <= latest_block_number {
+            info!(
+                "fork block #{} {:?} saved, latest={}",
+                block.number(),
+                block.hash(),
+                latest_block_number
+            );
+            return Ok(false);
+        }
+
+        // . the fork becomes the longest chain
+        self.switch_fork(block)?;
+        self.solidify_layers(solid_block_number)?;
+        Ok(true)
+    }
+
+    /// Apply a block on top of the latest block. The state changes are kept in one state-db layer, until the
+    /// block is solidified.
+    ///
+    /// `reapply` is for blocks that were already on the main chain, i.e. restoring the original chain after a
+    /// failed fork switch. Their result mismatches and latency are not recorded again.
+    fn apply_block(
+        &mut self,
+        block: &IndexedBlock,
+        transaction_owners: Option<RecoveredOwners>,
+        reapply: bool,
+    ) -> Result<()> {
+        let started_at = Utc::now().timestamp_nanos();
+        let depth = self.state_db.num_layers();
+        let epoch = self.state_db.must_get(&keys::DynamicProperty::CurrentEpoch)?;
+        {
+            // Mismatches left by dry runs or block packing.
+            self.mismatch_reporter.discard_pending();
+            let mut manager = self.savepoint();
+
+            // . applyBlock = processBlock + updateFork
+            let processed = manager.process_block(block, transaction_owners);
+            // Reported even if the block fails, a strict transaction fails its block on result mismatch.
+            if reapply {
+                manager.mismatch_reporter.discard_pending();
+            } else {
+                manager.mismatch_reporter.report_pending()?;
+            }
+            processed?;
+            manager.commit();
+        }
         // NOTE: OpenTron use different logic to handle verson fork. So `updateFork` is removed.
         // And no need to updateFork.
-        self.commit_current_layers();
+        self.state_db.squash_layers(depth);
+
+        if !reapply {
+            let witness = Address::try_from(block.witness()).map_err(|_| new_error("invalid witness address"))?;
+            self.record_block_latency(witness, epoch, Utc::now().timestamp_millis() - block.timestamp());
+        }
 
         let elapsed = (Utc::now().timestamp_nanos() - started_at) as f64 / 1_000_000.0;
         if !block.transactions.is_empty() {
@@ -219,11 +472,111 @@ impl Manager {
                 elapsed
             );
         }
+        self.emit_event(ChainEvent::BlockApplied {
+            number: block.number(),
+            hash: *block.hash(),
+        });
+
+        // . PBFT pre-prepare
+        if self.state_db.must_get(&keys::ChainParameter::AllowPbft)? != 0 {
+            let epoch = self.state_db.must_get(&keys::DynamicProperty::CurrentEpoch)?;
+            let signers = self.get_pbft_signers()?;
+            self.pbft
+                .on_block_applied(block.number(), *block.hash(), block.timestamp(), epoch, &signers);
+        }
+        Ok(())
+    }
 
-        Ok(true)
+    /// Roll back to the common ancestor, then apply blocks of the new fork. On failure, the bad block is removed
+    /// from the fork db and the original chain is restored.
+    fn switch_fork(&mut self, new_head: &IndexedBlock) -> Result<()> {
+        let latest_block_hash = self.latest_block_hash()?;
+        let (new_branch, old_branch) = self
+            .fork_db
+            .branches(new_head.hash(), &latest_block_hash)
+            .map(|(new_branch, old_branch)| {
+                (
+                    new_branch.into_iter().rev().cloned().collect::<Vec<_>>(),
+                    old_branch.into_iter().rev().cloned().collect::<Vec<_>>(),
+                )
+            })
+            .ok_or_else(|| new_error("can not find common ancestor of forks"))?;
+        // Each unsolidified block has its own state-db layer.
+        if old_branch.len() > self.state_db.num_layers() {
+            return Err(new_error("fork is too deep to roll back"));
+        }
+
+        let ancestor_number = new_head.number() - new_branch.len() as i64;
+        warn!(
+            "switch fork at block #{}, roll back {} blocks, apply {} blocks",
+            ancestor_number,
+            old_branch.len(),
+            new_branch.len()
+        );
+        self.rollback_blocks(&old_branch, ancestor_number)?;
+
+        for (i, block) in new_branch.iter().enumerate() {
+            if let Err(e) = self.apply_block(block, None, false) {
+                warn!(
+                    "switch fork failed at block #{}, restore the original chain: {}",
+                    block.number(),
+                    e
+                );
+                self.fork_db.remove(block.hash());
+                self.rollback_blocks(&new_branch[..i], ancestor_number)?;
+                for block in &old_branch {
+                    self.apply_block(block, None, true)?;
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Discard state-db layers of the latest blocks, `blocks` are in applied order.
+    fn rollback_blocks(&mut self, blocks: &[IndexedBlock], ancestor_number: i64) -> Result<()> {
+        for block in blocks.iter().rev() {
+            self.state_db.discard_last_layer()?;
+            self.emit_event(ChainEvent::BlockRolledBack {
+                number: block.number(),
+                hash: *block.hash(),
+            });
+        }
+        self.ref_block_hashes.rollback_to(ancestor_number);
+        Ok(())
     }
 
-    fn process_block(&mut self, block: &IndexedBlock) -> Result<()> {
+    /// Write state-db layers of solidified blocks to disk.
+    fn solidify_layers(&mut self, prev_solid_block_number: i64) -> Result<()> {
+        let solid_block_number = self.solid_block_number()?;
+        let num_unsolid_blocks = (self.latest_block_number()? - solid_block_number).max(0) as usize;
+        while self.state_db.num_layers() > num_unsolid_blocks {
+            self.state_db.solidify_layer()?;
+        }
+
+        if solid_block_number > prev_solid_block_number {
+            // Newly solidified blocks on the current chain, from the fork db.
+            let mut solidified = vec![];
+            let mut hash = self.latest_block_hash()?;
+            while let Some(block) = self.fork_db.get(&hash) {
+                if block.number() <= prev_solid_block_number {
+                    break;
+                }
+                if block.number() <= solid_block_number {
+                    solidified.push((block.number(), hash));
+                }
+                hash = H256::from_slice(block.parent_hash());
+            }
+            for (number, hash) in solidified.into_iter().rev() {
+                self.emit_event(ChainEvent::BlockSolidified { number, hash });
+            }
+        }
+        self.fork_db.prune(solid_block_number);
+        self.pbft.prune(solid_block_number);
+        Ok(())
+    }
+
+    fn process_block(&mut self, block: &IndexedBlock, transaction_owners: Option<RecoveredOwners>) -> Result<()> {
         // 1. checkWitness - check block producing schedule
         // Block producer is strictly scheduled except block #1(where needSyncCheck=false).
         if !self.validate_block_schedule(block)? {
@@ -233,35 +586,39 @@ impl Manager {
         // 2. reset block energy statistics, used in adaptive energy
         self.block_energy_usage = 0;
 
-        // 3. Pre-check transaction signature in parallel.
-        let recovered_owners = block.recover_transaction_owners();
+        // 3. Pre-check transaction signature in parallel, unless done by the verifying pipeline.
+        let recovered_owners = transaction_owners.unwrap_or_else(|| block.recover_transaction_owners());
 
         // 3. Execute Transaction, TransactionRet / TransactionReceipt
         // TODO: handle accountState - AccountStateCallBack
-        for (txn, recovered_addrs) in block.transactions.iter().zip(recovered_owners.into_iter()) {
+        for (index, (txn, recovered_addrs)) in block.transactions.iter().zip(recovered_owners.into_iter()).enumerate() {
             debug!(
                 "transaction => {:?} at block #{} v{}",
                 txn.hash,
                 block.number(),
                 block.version()
             );
-            self.process_transaction(&txn, recovered_addrs, block)?;
+            self.process_transaction(&txn, index, recovered_addrs, block)?;
         }
 
+        // Roots of the note commitment tree at block ends are anchors of shielded spends.
+        #[cfg(feature = "nile")]
+        executor::actuators::shielded::save_shielded_anchor(self, block)?;
+
         // 4. Adaptive energy processor:
         if self.block_energy_usage > 0 {
-            if self.state_db.must_get(&keys::ChainParameter::AllowAdaptiveEnergy) != 0 {
+            if self.state_db.must_get(&keys::ChainParameter::AllowAdaptiveEnergy)? != 0 {
                 debug!("block energy = {}", self.block_energy_usage);
                 // updateTotalEnergyAverageUsage + updateAdaptiveTotalEnergyLimit
-                EnergyProcessor::new(self).update_adaptive_energy().unwrap();
+                EnergyProcessor::new(self).update_adaptive_energy()?;
             }
         }
 
         // 5. Block reward
-        self.pay_reward(block);
+        self.pay_reward(block)?;
 
         // 6. Handle proposal if maintenance
-        if self.state_db.must_get(&keys::DynamicProperty::NextMaintenanceTime) <= block.timestamp() {
+        if self.state_db.must_get(&keys::DynamicProperty::NextMaintenanceTime)? <= block.timestamp() {
             self.maintenance_started_at = Utc::now().timestamp_nanos();
             info!("beigin maintenance at block #{}", block.number());
             ProposalController::new(self).process_proposals()?;
@@ -271,11 +628,11 @@ impl Manager {
         // - statisticManager.applyBlock
         // - maintenanceManager.applyBlock
         // - updateSolidBlock
-        WitnessStatisticManager::new(self).apply_block(block)?;
+        WitnessStatisticManager::new(self)?.apply_block(block)?;
         MaintenanceManager::new(self).apply_block(block)?;
         self.update_solid_block(block)?;
 
-        self.update_ref_blocks(*block.hash());
+        self.ref_block_hashes.push(*block.hash());
 
         // 8. update latest block - updateDynamicProperties
         self.state_db
@@ -292,6 +649,7 @@ impl Manager {
     fn process_transaction(
         &mut self,
         txn: &IndexedTransaction,
+        txn_index: usize,
         recovered_addrs: Result<Vec<Address>, impl std::error::Error>,
         block: &IndexedBlock,
     ) -> Result<()> {
@@ -300,7 +658,7 @@ impl Manager {
             return Err(new_error("tapos validation failed"));
         }
         // 2.validateCommon
-        if !self.valide_transaction_common(txn) {
+        if !self.valide_transaction_common(txn)? {
             return Err(new_error("message size or expiration validation failed"));
         }
         // 3.validateDup
@@ -316,6 +674,15 @@ impl Manager {
 
         // 7. transaction is executed by TransactionTrace.
         let txn_receipt = TransactionExecutor::new(self).execute(txn, recovered_addrs, &block.header)?;
+        // Log index, by contract address with topic0 or block.
+        for (i, log) in txn_receipt.vm_logs.iter().enumerate() {
+            let addr = *Address::from_bytes(&log.address);
+            let topic0 = log.topics.first().map(|t| H256::from_slice(t)).unwrap_or_default();
+            self.state_db.put_transaction_log(
+                keys::TransactionLog(addr, topic0, block.number(), txn_index as i32, i as i32),
+                log.clone(),
+            )?;
+        }
         self.state_db.put_key(keys::TransactionReceipt(txn.hash), txn_receipt)?;
         Ok(())
     }
@@ -325,56 +692,70 @@ impl Manager {
         /*if !self.validate_transaction_tapos(txn) {
             return Err(new_error("tapos validation failed"));
         }
-        if !self.valide_transaction_common(txn) {
+        if !self.valide_transaction_common(txn)? {
             return Err(new_error("message size or expiration validation failed"));
         }*/
-        let fake_block_number = self.latest_block_number() + 1;
+        let fake_block_number = self.latest_block_number()? + 1;
         let block_header = IndexedBlockHeader::dummy(
             fake_block_number,
-            self.latest_block_timestamp() + constants::BLOCK_PRODUCING_INTERVAL,
+            self.latest_block_timestamp()? + constants::BLOCK_PRODUCING_INTERVAL,
         );
 
-        let old_layers = self.layers;
-        self.new_layer();
+        let recovered_addrs = txn.recover_owner()?;
+        let mut manager = self.savepoint();
+        // All changes are discarded when the savepoint is dropped.
+        let maybe_receipt = TransactionExecutor::new(&mut manager).execute(txn, recovered_addrs, &block_header);
+        Ok(maybe_receipt?)
+    }
 
-        let maybe_receipt = TransactionExecutor::new(self).execute(txn, txn.recover_owner()?, &block_header);
+    /// Dry run a block on top of the latest block, return receipts and the state diff.
+    ///
+    /// The witness signature is not checked. All changes are discarded.
+    pub fn dry_run_block(&mut self, block: &IndexedBlock) -> Result<BlockDryRun> {
+        if block.number() != self.latest_block_number()? + 1 ||
+            block.parent_hash() != self.latest_block_hash()?.as_bytes()
+        {
+            return Err(new_error("block is not the child of latest block"));
+        }
 
-        let added_layers = self.layers - old_layers;
-        debug!("dry run, rollback layers={}", added_layers);
-        self.rollback_layers(added_layers);
-        Ok(maybe_receipt?)
+        let depth = self.state_db.num_layers();
+        // TaPoS hashes are not part of the state-db, restore them after the dry run.
+        let saved_ref_block_hashes = self.ref_block_hashes.clone();
+        let result = (|| -> Result<BlockDryRun> {
+            let mut manager = self.savepoint();
+            manager.process_block(block, None)?;
+            let mut receipts = Vec::with_capacity(block.transactions.len());
+            for txn in &block.transactions {
+                receipts.push((
+                    txn.hash,
+                    manager.state_db.must_get(&keys::TransactionReceipt(txn.hash))?,
+                ));
+            }
+            let diff = StateDiff::since(&manager.state_db, depth)?;
+            // All changes are discarded when the savepoint is dropped.
+            Ok(BlockDryRun { receipts, diff })
+        })();
+        self.ref_block_hashes = saved_ref_block_hashes;
+        result
     }
 
     fn validate_transaction_tapos(&self, txn: &IndexedTransaction) -> bool {
-        let ref_block_hash = &txn.raw.raw_data.as_ref().unwrap().ref_block_hash;
-        let ref_block_bytes = {
-            let mut raw = [0u8; 2];
-            raw.copy_from_slice(&txn.raw.raw_data.as_ref().unwrap().ref_block_bytes);
-            raw
-        };
-        // debug!("ref block bytes=> {:?}", hex::encode(&ref_block_bytes[..]));
-        // debug!("ref block hash=> {:?}", hex::encode(&ref_block_hash[..]));
-        let ref_slot_index = u16::from_be_bytes(ref_block_bytes) as usize;
-
-        self.ref_block_hashes
-            .get(ref_slot_index)
-            .map(|block_hash| &block_hash.as_ref()[8..16] == &ref_block_hash[..])
-            .unwrap_or(false)
+        self.ref_block_hashes.verify(txn)
     }
 
-    fn valide_transaction_common(&self, txn: &IndexedTransaction) -> bool {
+    fn valide_transaction_common(&self, txn: &IndexedTransaction) -> Result<bool, StateError> {
         if txn.raw.encoded_len() > constants::MAX_TRANSACTION_SIZE {
             warn!("transaction is too big");
-            return false;
+            return Ok(false);
         }
-        let latest_block_ts = self.latest_block_timestamp();
+        let latest_block_ts = self.latest_block_timestamp()?;
         if txn.expiration() <= latest_block_ts ||
             txn.expiration() > latest_block_ts + constants::MAX_TRANSACTION_EXPIRATION
         {
             warn!("transaction expired");
-            return false;
+            return Ok(false);
         }
-        true
+        Ok(true)
     }
 
     fn validate_duplicated_transaction(&self, _txn: &IndexedTransaction) -> bool {
@@ -384,7 +765,7 @@ impl Manager {
 
     // consensus.validBlock
     fn validate_block_schedule(&self, block: &IndexedBlock) -> Result<bool> {
-        if self.state_db.get(&keys::DynamicProperty::LatestBlockNumber).unwrap() == Some(0) {
+        if self.state_db.get(&keys::DynamicProperty::LatestBlockNumber)? == Some(0) {
             return Ok(true);
         }
 
@@ -403,8 +784,8 @@ impl Manager {
             return Ok(false);
         }
 
-        let slot = self.get_slot(timestamp);
-        let scheduled = self.get_scheduled_witness(slot);
+        let slot = self.get_slot(timestamp)?;
+        let scheduled = self.get_scheduled_witness(slot)?;
 
         if block.witness() != scheduled.as_bytes() {
             warn!(
@@ -419,26 +800,37 @@ impl Manager {
         Ok(true)
     }
 
+    /// The block confirmed by 70% of active witnesses is the floor of the solid block, PBFT commits can only
+    /// move it forward.
     fn update_solid_block(&mut self, block: &IndexedBlock) -> Result<()> {
-        let mut wit_addrs = self.state_db.must_get(&keys::WitnessSchedule);
+        let mut wit_addrs = self.state_db.must_get(&keys::WitnessSchedule)?;
         if wit_addrs.is_empty() {
             panic!("no witness found");
         }
         if wit_addrs.len() > constants::MAX_NUM_OF_ACTIVE_WITNESSES {
             let _ = wit_addrs.split_off(constants::MAX_NUM_OF_ACTIVE_WITNESSES);
         }
-        let mut block_nums: Vec<_> = wit_addrs
+        let mut block_nums = wit_addrs
             .into_iter()
-            .map(|(addr, _, _)| self.state_db.must_get(&keys::Witness(addr)).latest_block_number)
-            .collect();
+            .map(|(addr, _, _)| {
+                self.state_db
+                    .must_get(&keys::Witness(addr))
+                    .map(|wit| wit.latest_block_number)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
         block_nums.sort();
 
         // NOTE: When there are 27 active witnesses, pos will be 8, that's 19 SR confirmations.
         let pos = (block_nums.len() as f64 * (1.0 - constants::SOLID_THRESHOLD_PERCENT as f64 / 100.0)) as usize;
         let new_solid_block_num = block_nums[pos];
 
-        let old_solid_block_num = self.state_db.must_get(&keys::DynamicProperty::LatestSolidBlockNumber);
+        let allow_pbft = self.state_db.must_get(&keys::ChainParameter::AllowPbft)? != 0;
+        let old_solid_block_num = self.state_db.must_get(&keys::DynamicProperty::LatestSolidBlockNumber)?;
         if new_solid_block_num < old_solid_block_num {
+            // PBFT commits are usually ahead of the 70% confirmations.
+            if allow_pbft {
+                return self.update_pbft_block(block);
+            }
             // NOTE: This warning must be ignored. When new active witness is ranked after maintenance,
             // new solid block number might become 0.
             warn!(
@@ -450,29 +842,69 @@ impl Manager {
                 info!("updated solid block number = {}", new_solid_block_num);
             }
             self.state_db
-                .put_key(keys::DynamicProperty::LatestSolidBlockNumber, new_solid_block_num)
-                .unwrap();
+                .put_key(keys::DynamicProperty::LatestSolidBlockNumber, new_solid_block_num)?;
         }
 
+        if allow_pbft {
+            self.update_pbft_block(block)?;
+        }
+        Ok(())
+    }
+
+    /// Record blocks confirmed by PBFT on the current chain, the latest one becomes the solid block.
+    fn update_pbft_block(&mut self, block: &IndexedBlock) -> Result<()> {
+        let pbft_block_num = self.state_db.must_get(&keys::DynamicProperty::LatestPbftBlockNumber)?;
+
+        // Current chain above the solid block. The block itself is not in the fork db yet.
+        let mut chain = HashMap::new();
+        chain.insert(block.number(), *block.hash());
+        let mut hash = H256::from_slice(block.parent_hash());
+        while let Some(parent) = self.fork_db.get(&hash) {
+            chain.insert(parent.number(), hash);
+            hash = H256::from_slice(parent.parent_hash());
+        }
+
+        let confirmed = self
+            .pbft
+            .confirmed_blocks()
+            .filter(|confirmed| {
+                confirmed.number > pbft_block_num && chain.get(&confirmed.number) == Some(&confirmed.hash)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if let Some(latest) = confirmed.last() {
+            let latest_num = latest.number;
+            for confirmed in confirmed {
+                self.state_db
+                    .put_key(keys::PbftCommit(confirmed.number), confirmed.commit_result)?;
+            }
+            self.state_db
+                .put_key(keys::DynamicProperty::LatestPbftBlockNumber, latest_num)?;
+            if latest_num > self.solid_block_number()? {
+                self.state_db
+                    .put_key(keys::DynamicProperty::LatestSolidBlockNumber, latest_num)?;
+            }
+        }
         Ok(())
     }
 
     /// Pay block producing reward.
-    fn pay_reward(&mut self, block: &IndexedBlock) {
-        let allow_change_delegation = self.state_db.must_get(&keys::ChainParameter::AllowChangeDelegation) != 0;
+    fn pay_reward(&mut self, block: &IndexedBlock) -> Result<()> {
+        let allow_change_delegation = self.state_db.must_get(&keys::ChainParameter::AllowChangeDelegation)? != 0;
         if allow_change_delegation {
             // So-called new-style reward scheme.
             // 1. delegationService.payBlockReward
             // 2. delegationService.payStandbyWitness
-            RewardController::new(self).pay_reward(block).unwrap();
+            RewardController::new(self).pay_reward(block)?;
         } else {
             // NOTE: In this legacy reward scheme, standby witnesses will be paid during maintenance cycle.
             let wit_key = keys::Account(block.witness().try_into().unwrap());
-            let mut wit_acct = self.state_db.must_get(&wit_key);
-            let reward_per_block = self.state_db.must_get(&keys::ChainParameter::WitnessPayPerBlock);
+            let mut wit_acct = self.state_db.must_get(&wit_key)?;
+            let reward_per_block = self.state_db.must_get(&keys::ChainParameter::WitnessPayPerBlock)?;
             wit_acct.allowance += reward_per_block;
-            self.state_db.put_key(wit_key, wit_acct).unwrap();
+            self.state_db.put_key(wit_key, wit_acct)?;
         }
+        Ok(())
     }
 
     // * DposSlot
@@ -480,105 +912,161 @@ impl Manager {
         (timestamp - self.genesis_block_timestamp) / constants::BLOCK_PRODUCING_INTERVAL
     }
 
-    fn get_slot(&self, timestamp: i64) -> i64 {
-        let first_slot_ts = self.get_slot_timestamp(1);
+    fn get_slot(&self, timestamp: i64) -> Result<i64, StateError> {
+        let first_slot_ts = self.get_slot_timestamp(1)?;
         if timestamp < first_slot_ts {
-            0
+            Ok(0)
         } else {
-            (timestamp - first_slot_ts) / constants::BLOCK_PRODUCING_INTERVAL + 1
+            Ok((timestamp - first_slot_ts) / constants::BLOCK_PRODUCING_INTERVAL + 1)
         }
     }
 
-    fn get_head_slot(&self) -> i64 {
-        self.get_absolute_slot(self.state_db.must_get(&keys::DynamicProperty::LatestBlockTimestamp))
+    fn get_head_slot(&self) -> Result<i64, StateError> {
+        Ok(self.get_absolute_slot(self.latest_block_timestamp()?))
     }
 
-    fn get_slot_timestamp(&self, mut slot: i64) -> i64 {
+    fn get_slot_timestamp(&self, mut slot: i64) -> Result<i64, StateError> {
         assert!(slot >= 0, "unreachable");
 
         if slot == 0 {
-            return Utc::now().timestamp_millis();
+            return Ok(Utc::now().timestamp_millis());
         }
 
-        if self.state_db.get(&keys::DynamicProperty::LatestBlockNumber).unwrap() == Some(0) {
-            return self.genesis_block_timestamp + slot * constants::BLOCK_PRODUCING_INTERVAL;
+        if self.latest_block_number()? == 0 {
+            return Ok(self.genesis_block_timestamp + slot * constants::BLOCK_PRODUCING_INTERVAL);
         }
 
-        if self.is_latest_block_maintenance() {
+        if self.is_latest_block_maintenance()? {
             slot += constants::NUM_OF_SKIPPED_SLOTS_IN_MAINTENANCE as i64;
         }
 
-        let mut ts = self.latest_block_timestamp();
+        let mut ts = self.latest_block_timestamp()?;
         ts -= (ts - self.genesis_block_timestamp) % constants::BLOCK_PRODUCING_INTERVAL;
-        ts + constants::BLOCK_PRODUCING_INTERVAL * slot
+        Ok(ts + constants::BLOCK_PRODUCING_INTERVAL * slot)
+    }
+
+    /// The address signing blocks for the witness, the witness permission key if multisig is allowed.
+    fn get_witness_signature_key(&self, witness: &Address) -> Result<Address> {
+        if self.state_db.must_get(&keys::ChainParameter::AllowMultisig)? == 1 {
+            if let Some(wit) = self.state_db.get(&keys::Witness(*witness))? {
+                if !wit.signature_key.is_empty() {
+                    return Address::try_from(&wit.signature_key)
+                        .map_err(|_| new_error("invalid witness signature key"));
+                }
+            }
+        }
+        Ok(*witness)
+    }
+
+    /// Signing addresses of active witnesses, mapped to witnesses.
+    fn get_pbft_signers(&self) -> Result<HashMap<Address, Address>> {
+        self.get_active_witnesses()?
+            .into_iter()
+            .map(|wit| Ok((self.get_witness_signature_key(&wit)?, wit)))
+            .collect()
     }
 
-    fn get_active_witnesses(&self) -> Vec<Address> {
-        let mut witnesses = self.state_db.get(&keys::WitnessSchedule).unwrap().unwrap();
+    fn get_active_witnesses(&self) -> Result<Vec<Address>, StateError> {
+        let mut witnesses = self.state_db.must_get(&keys::WitnessSchedule)?;
         if witnesses.is_empty() {
             panic!("no witness found");
         }
         if witnesses.len() > constants::MAX_NUM_OF_ACTIVE_WITNESSES {
             let _ = witnesses.split_off(constants::MAX_NUM_OF_ACTIVE_WITNESSES);
         }
-        witnesses.into_iter().map(|wit| wit.0).collect()
+        Ok(witnesses.into_iter().map(|wit| wit.0).collect())
     }
 
-    fn get_standby_witnesses(&self) -> Vec<Address> {
-        let mut witnesses = self.state_db.get(&keys::WitnessSchedule).unwrap().unwrap();
+    fn get_standby_witnesses(&self) -> Result<Vec<Address>, StateError> {
+        let mut witnesses = self.state_db.must_get(&keys::WitnessSchedule)?;
         if witnesses.is_empty() {
             panic!("no witness found");
         }
         if witnesses.len() > constants::MAX_NUM_OF_STANDBY_WITNESSES {
             let _ = witnesses.split_off(constants::MAX_NUM_OF_STANDBY_WITNESSES);
         }
-        witnesses.into_iter().map(|wit| wit.0).collect()
+        Ok(witnesses.into_iter().map(|wit| wit.0).collect())
     }
 
-    fn get_scheduled_witness(&self, slot: i64) -> Address {
-        let mut witnesses = self.state_db.get(&keys::WitnessSchedule).unwrap().unwrap();
+    fn get_scheduled_witness(&self, slot: i64) -> Result<Address, StateError> {
+        let mut witnesses = self.state_db.must_get(&keys::WitnessSchedule)?;
         if witnesses.is_empty() {
             panic!("no witness found");
         }
         if witnesses.len() > constants::MAX_NUM_OF_ACTIVE_WITNESSES {
             let _ = witnesses.split_off(constants::MAX_NUM_OF_ACTIVE_WITNESSES);
         }
-        let curr_slot = self.get_absolute_slot(self.latest_block_timestamp()) + slot;
+        let curr_slot = self.get_absolute_slot(self.latest_block_timestamp()?) + slot;
         assert!(curr_slot >= 0, "slot must be positive");
 
         let mut idx = (curr_slot as usize) % (witnesses.len() * constants::NUM_OF_CONSECUTIVE_BLOCKS_PER_ROUND);
         idx /= constants::NUM_OF_CONSECUTIVE_BLOCKS_PER_ROUND;
-        witnesses[idx].0
+        Ok(witnesses[idx].0)
     }
 
     // consensus
     #[inline]
-    fn is_latest_block_maintenance(&self) -> bool {
-        self.state_db.must_get(&keys::DynamicProperty::IsMaintenance) != 0
+    fn is_latest_block_maintenance(&self) -> Result<bool, StateError> {
+        Ok(self.state_db.must_get(&keys::DynamicProperty::IsMaintenance)? != 0)
     }
 
     #[inline]
-    fn latest_block_timestamp(&self) -> i64 {
+    pub fn latest_block_timestamp(&self) -> Result<i64, StateError> {
         self.state_db.must_get(&keys::DynamicProperty::LatestBlockTimestamp)
     }
 
     #[inline]
-    pub fn solid_block_number(&self) -> i64 {
+    pub fn solid_block_number(&self) -> Result<i64, StateError> {
         self.state_db.must_get(&keys::DynamicProperty::LatestSolidBlockNumber)
     }
 
+    /// Latest block confirmed by PBFT, `None` when PBFT is not enabled.
+    pub fn pbft_block_number(&self) -> Result<Option<i64>, StateError> {
+        if self.state_db.must_get(&keys::ChainParameter::AllowPbft)? == 0 {
+            return Ok(None);
+        }
+        self.state_db
+            .must_get(&keys::DynamicProperty::LatestPbftBlockNumber)
+            .map(Some)
+    }
+
     #[inline]
-    pub fn latest_block_number(&self) -> i64 {
+    pub fn latest_block_number(&self) -> Result<i64, StateError> {
         self.state_db.must_get(&keys::DynamicProperty::LatestBlockNumber)
     }
 
     #[inline]
-    fn latest_block_hash(&self) -> H256 {
+    pub fn latest_block_hash(&self) -> Result<H256, StateError> {
         self.state_db.must_get(&keys::LatestBlockHash)
     }
 }
 
-/// Update witnesses' statistics, and BlockFilledSlots.
+/// Blocks applied later than this are from syncing, their latency is not tracked, in milliseconds.
+const MAX_TRACKED_BLOCK_LATENCY: i64 = 60_000;
+/// Latency of older epochs is dropped.
+const NUM_OF_LATENCY_TRACKED_EPOCHS: i64 = 28;
+
+/// Delay from slot time to block applied, of a witness in an epoch, in milliseconds.
+///
+/// Only blocks received in time are tracked, not blocks from syncing.
+#[derive(Debug, Default, Clone)]
+pub struct BlockLatency {
+    pub num_samples: i64,
+    pub total: i64,
+    pub max: i64,
+}
+
+impl BlockLatency {
+    pub fn average(&self) -> Option<f64> {
+        if self.num_samples > 0 {
+            Some(self.total as f64 / self.num_samples as f64)
+        } else {
+            None
+        }
+    }
+}
+
+/// Update witnesses' statistics, per-epoch performance, and BlockFilledSlots.
 pub struct WitnessStatisticManager<'m> {
     manager: &'m mut Manager,
     filled_slots: Vec<u8>,
@@ -586,21 +1074,23 @@ pub struct WitnessStatisticManager<'m> {
 }
 
 impl WitnessStatisticManager<'_> {
-    pub fn new<'a>(manager: &'a mut Manager) -> WitnessStatisticManager<'a> {
-        let filled_slots = manager.state_db.must_get(&keys::BlockFilledSlots);
-        let filled_slots_index = manager.state_db.must_get(&keys::DynamicProperty::BlockFilledSlotsIndex);
+    pub fn new<'a>(manager: &'a mut Manager) -> Result<WitnessStatisticManager<'a>> {
+        let filled_slots = manager.state_db.must_get(&keys::BlockFilledSlots)?;
+        let filled_slots_index = manager
+            .state_db
+            .must_get(&keys::DynamicProperty::BlockFilledSlotsIndex)?;
 
-        WitnessStatisticManager {
+        Ok(WitnessStatisticManager {
             manager,
             filled_slots,
             filled_slots_index,
-        }
+        })
     }
 
     pub fn apply_block(mut self, block: &IndexedBlock) -> Result<()> {
         let wit_addr = Address::try_from(block.witness()).unwrap();
 
-        let mut wit = self.manager.state_db.must_get(&keys::Witness(wit_addr));
+        let mut wit = self.manager.state_db.must_get(&keys::Witness(wit_addr))?;
 
         wit.total_produced += 1;
         wit.latest_block_number = block.number();
@@ -608,10 +1098,14 @@ impl WitnessStatisticManager<'_> {
         // NOTE: This is used for fork controller.
         wit.latest_block_version = block.version();
 
-        self.manager.state_db.put_key(keys::Witness(wit_addr), wit).unwrap();
+        self.manager.state_db.put_key(keys::Witness(wit_addr), wit)?;
+
+        let epoch = self.manager.state_db.must_get(&keys::DynamicProperty::CurrentEpoch)?;
+        // NOTE: Latency depends on wall clock, it is tracked by `Manager::apply_block`, not in the state-db.
+        self.update_performance(wit_addr, epoch, |perf| perf.produced += 1)?;
 
         let slot = if block.number() != 1 {
-            self.manager.get_slot(block.timestamp())
+            self.manager.get_slot(block.timestamp())?
         } else {
             1
         };
@@ -619,8 +1113,8 @@ impl WitnessStatisticManager<'_> {
         // record missed blocks
         // TODO: reduce `put_key` operations.
         for i in 1..slot {
-            let wit_addr = self.manager.get_scheduled_witness(i);
-            let mut wit = self.manager.state_db.must_get(&keys::Witness(wit_addr));
+            let wit_addr = self.manager.get_scheduled_witness(i)?;
+            let mut wit = self.manager.state_db.must_get(&keys::Witness(wit_addr))?;
             wit.total_missed += 1;
             warn!(
                 "block #{}, witness={}, total_missed={}",
@@ -628,7 +1122,8 @@ impl WitnessStatisticManager<'_> {
                 wit_addr,
                 wit.total_missed
             );
-            self.manager.state_db.put_key(keys::Witness(wit_addr), wit).unwrap();
+            self.manager.state_db.put_key(keys::Witness(wit_addr), wit)?;
+            self.update_performance(wit_addr, epoch, |perf| perf.missed += 1)?;
 
             self.filled_slots[self.filled_slots_index as usize] = 0;
             self.filled_slots_index = (self.filled_slots_index + 1) % constants::NUM_OF_BLOCK_FILLED_SLOTS as i64;
@@ -639,13 +1134,733 @@ impl WitnessStatisticManager<'_> {
 
         self.manager
             .state_db
-            .put_key(keys::DynamicProperty::BlockFilledSlotsIndex, self.filled_slots_index)
-            .unwrap();
+            .put_key(keys::DynamicProperty::BlockFilledSlotsIndex, self.filled_slots_index)?;
         self.manager
             .state_db
-            .put_key(keys::BlockFilledSlots, self.filled_slots)
-            .unwrap();
+            .put_key(keys::BlockFilledSlots, self.filled_slots)?;
+
+        Ok(())
+    }
 
+    fn update_performance<F>(&mut self, wit_addr: Address, epoch: i64, update: F) -> Result<()>
+    where
+        F: FnOnce(&mut WitnessPerformance),
+    {
+        let key = keys::WitnessPerformance(wit_addr, epoch);
+        let mut perf = self.manager.state_db.get(&key)?.unwrap_or_default();
+        update(&mut perf);
+        self.manager.state_db.put_key(key, perf)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_manager() {
+        let config = Config::load_from_str(include_str!("../../etc/conf.toml")).unwrap();
+        let genesis_config = GenesisConfig::load_from_str(include_str!("../../etc/genesis.json")).unwrap();
+        let mut manager = Manager::with_state_db(StateDB::new_in_memory(), &config, &genesis_config);
+
+        assert_eq!(manager.latest_block_number().unwrap(), 0);
+        let genesis_block = genesis_config.to_indexed_block().unwrap();
+        assert_eq!(manager.latest_block_hash().unwrap(), *genesis_block.hash());
+
+        let proposal_id = manager
+            .state()
+            .must_get(&keys::DynamicProperty::LatestProposalId)
+            .unwrap();
+        {
+            let mut manager = manager.savepoint();
+            manager
+                .state_db
+                .put_key(keys::DynamicProperty::LatestProposalId, proposal_id + 1)
+                .unwrap();
+            {
+                let mut manager = manager.savepoint();
+                manager
+                    .state_db
+                    .put_key(keys::DynamicProperty::LatestProposalId, proposal_id + 2)
+                    .unwrap();
+                manager.commit();
+            }
+            assert_eq!(
+                manager
+                    .state()
+                    .must_get(&keys::DynamicProperty::LatestProposalId)
+                    .unwrap(),
+                proposal_id + 2
+            );
+        }
+        // rolled back on drop
+        assert_eq!(
+            manager
+                .state()
+                .must_get(&keys::DynamicProperty::LatestProposalId)
+                .unwrap(),
+            proposal_id
+        );
+    }
+    /// A manager with generated witnesses, and `AllowPbft` on.
+    fn new_pbft_test_manager(keypairs: &[KeyPair]) -> Manager {
+        let config = Config::load_from_str(include_str!("../../etc/conf.toml")).unwrap();
+        let mut genesis_config = GenesisConfig::load_from_str(include_str!("../../etc/genesis.json")).unwrap();
+        genesis_config.witnesses = keypairs
+            .iter()
+            .enumerate()
+            .map(|(i, keypair)| config::genesis::Witness {
+                address: keypair.address().to_string(),
+                url: format!("http://wit{}.com", i),
+                votes: 100_000_000 - i as i64,
+            })
+            .collect();
+        let mut manager = Manager::with_state_db(StateDB::new_in_memory(), &config, &genesis_config);
+        manager.state_db.new_layer();
+        manager.state_db.put_key(keys::ChainParameter::AllowPbft, 1).unwrap();
+        manager.state_db.solidify_layer().unwrap();
+        manager
+    }
+
+    /// Produce a block in the slot, by the scheduled witness.
+    fn produce_test_block(producer_manager: &mut Manager, keypairs: &[KeyPair], slot: i64) -> IndexedBlock {
+        use self::producer::BlockProducer;
+        use self::txpool::TransactionPool;
+
+        let witness = if producer_manager.latest_block_number().unwrap() == 0 {
+            keypairs[0].address()
+        } else {
+            producer_manager.get_scheduled_witness(slot).unwrap()
+        };
+        let keypair = keypairs.iter().find(|keypair| keypair.address() == witness).unwrap();
+        producer_manager.my_witness = witness.as_bytes().to_vec();
+        let now = producer_manager.get_slot_timestamp(slot).unwrap();
+        BlockProducer::new(producer_manager, keypair)
+            .produce_block(now, &mut TransactionPool::default())
+            .unwrap()
+            .expect("block produced")
+    }
+
+    /// Produce `n` blocks in turn by the scheduled witnesses, without missing a slot.
+    fn produce_test_blocks(keypairs: &[KeyPair], n: usize) -> Vec<IndexedBlock> {
+        let mut producer_manager = new_pbft_test_manager(keypairs);
+        (0..n)
+            .map(|_| produce_test_block(&mut producer_manager, keypairs, 1))
+            .collect()
+    }
+
+    #[test]
+    fn test_solidify_without_pbft_messages() {
+        let keypairs = (0..4).map(|_| KeyPair::generate()).collect::<Vec<_>>();
+        let blocks = produce_test_blocks(&keypairs, 20);
+
+        // Replay, as sync and `dev` do.
+        let mut manager = new_pbft_test_manager(&keypairs);
+        for block in &blocks {
+            assert!(manager.push_block(block).unwrap());
+        }
+        assert_eq!(manager.latest_block_number().unwrap(), 20);
+        assert_eq!(manager.pbft_block_number().unwrap(), Some(0));
+        assert!(
+            manager.solid_block_number().unwrap() >= 15,
+            "solid={}",
+            manager.solid_block_number().unwrap()
+        );
+        assert_eq!(
+            manager.state_db.num_layers() as i64,
+            manager.latest_block_number().unwrap() - manager.solid_block_number().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_missing_head_state() {
+        let keypairs = vec![KeyPair::generate()];
+        let blocks = produce_test_blocks(&keypairs, 1);
+
+        let mut manager = new_pbft_test_manager(&keypairs);
+        manager.state_db.new_layer();
+        manager
+            .state_db
+            .delete_key(&keys::DynamicProperty::LatestSolidBlockNumber)
+            .unwrap();
+        assert!(manager.solid_block_number().unwrap_err().is_not_found());
+        // Returned as an error, instead of panicking.
+        assert!(manager.push_block(&blocks[0]).is_err());
+        assert_eq!(manager.latest_block_number().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_witness_permission_signature_key() {
+        use self::producer::BlockProducer;
+        use self::txpool::TransactionPool;
+
+        let keypairs = vec![KeyPair::generate()];
+        let witness = keypairs[0].address();
+        let signer = KeyPair::generate();
+        let delegate_signing = |manager: &mut Manager| {
+            manager.state_db.new_layer();
+            manager
+                .state_db
+                .put_key(keys::ChainParameter::AllowMultisig, 1)
+                .unwrap();
+            let mut wit = manager.state_db.must_get(&keys::Witness(witness)).unwrap();
+            wit.signature_key = signer.address().as_bytes().to_vec();
+            manager.state_db.put_key(keys::Witness(witness), wit).unwrap();
+            manager.state_db.solidify_layer().unwrap();
+        };
+
+        let mut producer_manager = new_pbft_test_manager(&keypairs);
+        delegate_signing(&mut producer_manager);
+        producer_manager.my_witness = witness.as_bytes().to_vec();
+        let now = producer_manager.get_slot_timestamp(1).unwrap();
+        // The witness key is no longer allowed to sign.
+        assert!(BlockProducer::new(&mut producer_manager, &keypairs[0])
+            .produce_block(now, &mut TransactionPool::default())
+            .unwrap()
+            .is_none());
+        let block = BlockProducer::new(&mut producer_manager, &signer)
+            .produce_block(now, &mut TransactionPool::default())
+            .unwrap()
+            .expect("block produced");
+        assert_eq!(block.witness(), witness.as_bytes());
+        assert_eq!(block.recover_witness().unwrap(), signer.address());
+
+        // A block signed by the witness key itself.
+        let mut other_manager = new_pbft_test_manager(&keypairs);
+        other_manager.my_witness = witness.as_bytes().to_vec();
+        let block_by_witness_key = BlockProducer::new(&mut other_manager, &keypairs[0])
+            .produce_block(now, &mut TransactionPool::default())
+            .unwrap()
+            .expect("block produced");
+
+        let mut manager = new_pbft_test_manager(&keypairs);
+        delegate_signing(&mut manager);
+        assert!(manager.push_block(&block_by_witness_key).is_err());
+        assert!(manager.push_block(&block).unwrap());
+        assert_eq!(manager.latest_block_number().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_chain_events() {
+        use std::sync::{Arc, Mutex};
+
+        let keypairs = (0..4).map(|_| KeyPair::generate()).collect::<Vec<_>>();
+        let blocks = produce_test_blocks(&keypairs, 20);
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut manager = new_pbft_test_manager(&keypairs);
+        {
+            let events = events.clone();
+            manager.set_event_handler(move |event| events.lock().unwrap().push(event));
+        }
+        for block in &blocks {
+            assert!(manager.push_block(block).unwrap());
+        }
+
+        let events = events.lock().unwrap();
+        let applied: Vec<_> = events
+            .iter()
+            .filter_map(|event| match *event {
+                ChainEvent::BlockApplied { number, hash } => Some((number, hash)),
+                _ => None,
+            })
+            .collect();
+        let solidified: Vec<_> = events
+            .iter()
+            .filter_map(|event| match *event {
+                ChainEvent::BlockSolidified { number, hash } => Some((number, hash)),
+                _ => None,
+            })
+            .collect();
+        let expected: Vec<_> = blocks.iter().map(|block| (block.number(), *block.hash())).collect();
+
+        assert_eq!(applied, expected);
+        // Every solidified block once, in order, after it is applied.
+        let solid_block_number = manager.solid_block_number().unwrap() as usize;
+        assert!(solid_block_number > 0);
+        assert_eq!(solidified, expected[..solid_block_number]);
+        for (number, hash) in &solidified {
+            let applied_at = events
+                .iter()
+                .position(|event| {
+                    *event ==
+                        ChainEvent::BlockApplied {
+                            number: *number,
+                            hash: *hash,
+                        }
+                })
+                .unwrap();
+            let solidified_at = events
+                .iter()
+                .position(|event| {
+                    *event ==
+                        ChainEvent::BlockSolidified {
+                            number: *number,
+                            hash: *hash,
+                        }
+                })
+                .unwrap();
+            assert!(applied_at < solidified_at);
+        }
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, ChainEvent::BlockRolledBack { .. })));
+    }
+
+    #[test]
+    fn test_switch_fork() {
+        use proto::chain::transaction::Raw as TransactionRaw;
+        use proto::chain::{Block, BlockHeader, Transaction};
+        use std::sync::{Arc, Mutex};
+
+        // Blocks are not solidified before 19 witnesses have produced, all forks are above the solid block.
+        let keypairs = (0..27).map(|_| KeyPair::generate()).collect::<Vec<_>>();
+        let main_blocks = produce_test_blocks(&keypairs, 3);
+
+        // #1 <- #2 <- #3
+        //    \- #2' <- #3' <- #4', skipping the slot of #2.
+        let fork_blocks = {
+            let mut producer_manager = new_pbft_test_manager(&keypairs);
+            assert!(producer_manager.push_block(&main_blocks[0]).unwrap());
+            vec![2, 1, 1]
+                .into_iter()
+                .map(|slot| produce_test_block(&mut producer_manager, &keypairs, slot))
+                .collect::<Vec<_>>()
+        };
+        // #4' in the slot of another witness.
+        let bad_block = {
+            let mut raw_header = fork_blocks[2].header.raw.raw_data.clone().unwrap();
+            raw_header.timestamp += constants::BLOCK_PRODUCING_INTERVAL;
+            let mut buf = vec![];
+            raw_header.encode(&mut buf).unwrap();
+            let keypair = keypairs
+                .iter()
+                .find(|keypair| keypair.address().as_bytes() == fork_blocks[2].witness())
+                .unwrap();
+            let header = BlockHeader {
+                raw_data: Some(raw_header),
+                witness_signature: keypair.private().sign(&buf).unwrap().as_bytes().to_vec(),
+            };
+            IndexedBlock::from_raw(Block {
+                block_header: Some(header),
+                transactions: vec![],
+            })
+            .unwrap()
+        };
+
+        let refers_to = |manager: &Manager, block: &IndexedBlock| {
+            let raw = TransactionRaw {
+                ref_block_bytes: (block.number() as u16).to_be_bytes().to_vec(),
+                ref_block_hash: block.hash().as_bytes()[8..16].to_vec(),
+                ..Default::default()
+            };
+            let txn = IndexedTransaction::from_raw(Transaction {
+                raw_data: Some(raw),
+                ..Default::default()
+            })
+            .unwrap();
+            manager.ref_block_hashes().verify(&txn)
+        };
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut manager = new_pbft_test_manager(&keypairs);
+        manager.init_ref_blocks(vec![manager.latest_block_hash().unwrap()]);
+        {
+            let events = events.clone();
+            manager.set_event_handler(move |event| events.lock().unwrap().push(event));
+        }
+        for block in &main_blocks {
+            assert!(manager.push_block(block).unwrap());
+        }
+        // Blocks of a shorter fork are only saved.
+        assert!(!manager.push_block(&fork_blocks[0]).unwrap());
+        assert!(!manager.push_block(&fork_blocks[1]).unwrap());
+        assert_eq!(manager.latest_block_hash().unwrap(), *main_blocks[2].hash());
+        assert_eq!(manager.solid_block_number().unwrap(), 0);
+        events.lock().unwrap().clear();
+
+        // A bad block on the longer fork, the original chain is restored.
+        assert!(manager.push_block(&bad_block).is_err());
+        assert_eq!(manager.latest_block_hash().unwrap(), *main_blocks[2].hash());
+        assert_eq!(manager.state_db.num_layers(), 3);
+        assert!(!manager.fork_db.contains(bad_block.hash()));
+        assert!(manager.fork_db.contains(fork_blocks[1].hash()));
+        assert!(refers_to(&manager, &main_blocks[1]));
+        assert!(!refers_to(&manager, &fork_blocks[0]));
+        assert_eq!(
+            manager
+                .state()
+                .must_get(&keys::DynamicProperty::LatestBlockTimestamp)
+                .unwrap(),
+            main_blocks[2].timestamp()
+        );
+        let rolled_back = |events: &[ChainEvent]| {
+            events
+                .iter()
+                .filter_map(|event| match *event {
+                    ChainEvent::BlockRolledBack { number, hash } => Some((number, hash)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let applied = |events: &[ChainEvent]| {
+            events
+                .iter()
+                .filter_map(|event| match *event {
+                    ChainEvent::BlockApplied { number, hash } => Some((number, hash)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        {
+            let mut events = events.lock().unwrap();
+            assert_eq!(
+                rolled_back(&events),
+                vec![
+                    (3, *main_blocks[2].hash()),
+                    (2, *main_blocks[1].hash()),
+                    (3, *fork_blocks[1].hash()),
+                    (2, *fork_blocks[0].hash()),
+                ]
+            );
+            // Restored blocks are applied again, after rolled back.
+            assert_eq!(
+                applied(&events),
+                vec![
+                    (2, *fork_blocks[0].hash()),
+                    (3, *fork_blocks[1].hash()),
+                    (2, *main_blocks[1].hash()),
+                    (3, *main_blocks[2].hash()),
+                ]
+            );
+            events.clear();
+        }
+
+        // The longer fork wins, rolled back to the common ancestor #1.
+        assert!(manager.push_block(&fork_blocks[2]).unwrap());
+        assert_eq!(manager.latest_block_number().unwrap(), 4);
+        assert_eq!(manager.latest_block_hash().unwrap(), *fork_blocks[2].hash());
+        assert_eq!(manager.state_db.num_layers(), 4);
+        assert!(refers_to(&manager, &fork_blocks[0]));
+        assert!(!refers_to(&manager, &main_blocks[1]));
+        assert_eq!(
+            manager
+                .state()
+                .must_get(&keys::DynamicProperty::LatestBlockTimestamp)
+                .unwrap(),
+            fork_blocks[2].timestamp()
+        );
+        // The witness of #2 missed its slot on the new chain.
+        let witness = Address::try_from(main_blocks[1].witness()).unwrap();
+        assert_eq!(
+            manager
+                .state()
+                .must_get(&keys::Witness(witness))
+                .unwrap()
+                .latest_block_number,
+            0
+        );
+        let events = events.lock().unwrap();
+        assert_eq!(
+            rolled_back(&events),
+            vec![(3, *main_blocks[2].hash()), (2, *main_blocks[1].hash())]
+        );
+        assert_eq!(
+            applied(&events),
+            fork_blocks
+                .iter()
+                .map(|block| (block.number(), *block.hash()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_transaction_fee_pool_and_burn() {
+        let config = Config::load_from_str(include_str!("../../etc/conf.toml")).unwrap();
+        let genesis_config = GenesisConfig::load_from_str(include_str!("../../etc/genesis.json")).unwrap();
+        let mut manager = Manager::with_state_db(StateDB::new_in_memory(), &config, &genesis_config);
+        let blackhole = keys::Account(manager.blackhole);
+        manager.state_db.new_layer();
+        let blackhole_balance = manager.state_db.must_get(&blackhole).unwrap().balance;
+        let burned = manager.state_db.must_get(&keys::DynamicProperty::BurnedTrx).unwrap();
+
+        let set_param = |manager: &mut Manager, param: keys::ChainParameter, value: i64| {
+            manager.state_db.put_key(param, value).unwrap()
+        };
+        set_param(&mut manager, keys::ChainParameter::AllowTransactionFeePool, 0);
+        set_param(&mut manager, keys::ChainParameter::AllowBlackholeOptimization, 0);
+        manager.add_transaction_fee(100).unwrap();
+        assert_eq!(
+            manager.state_db.must_get(&blackhole).unwrap().balance,
+            blackhole_balance + 100
+        );
+
+        // Burnt to a counter.
+        set_param(&mut manager, keys::ChainParameter::AllowBlackholeOptimization, 1);
+        manager.add_transaction_fee(10).unwrap();
+        assert_eq!(
+            manager.state_db.must_get(&blackhole).unwrap().balance,
+            blackhole_balance + 100
+        );
+        assert_eq!(
+            manager.state_db.must_get(&keys::DynamicProperty::BurnedTrx).unwrap(),
+            burned + 10
+        );
+
+        // Accrued in the pool.
+        set_param(&mut manager, keys::ChainParameter::AllowTransactionFeePool, 1);
+        let pool = manager
+            .state_db
+            .must_get(&keys::DynamicProperty::TransactionFeePool)
+            .unwrap();
+        manager.add_transaction_fee(20).unwrap();
+        assert_eq!(
+            manager
+                .state_db
+                .must_get(&keys::DynamicProperty::TransactionFeePool)
+                .unwrap(),
+            pool + 20
+        );
+        assert_eq!(
+            manager.state_db.must_get(&keys::DynamicProperty::BurnedTrx).unwrap(),
+            burned + 10
+        );
+    }
+
+    #[test]
+    fn test_dry_run_block() {
+        use self::producer::BlockProducer;
+        use self::txpool::TransactionPool;
+
+        let keypairs = vec![KeyPair::generate()];
+        let mut producer_manager = new_pbft_test_manager(&keypairs);
+        producer_manager.my_witness = keypairs[0].address().as_bytes().to_vec();
+        let now = producer_manager.get_slot_timestamp(1).unwrap();
+        let block = BlockProducer::new(&mut producer_manager, &keypairs[0])
+            .produce_block(now, &mut TransactionPool::default())
+            .unwrap()
+            .expect("block produced");
+
+        let mut manager = new_pbft_test_manager(&keypairs);
+        let num_layers = manager.state_db.num_layers();
+        let result = manager.dry_run_block(&block).unwrap();
+        let diff = result.diff;
+
+        // Nothing is written.
+        assert_eq!(manager.latest_block_number().unwrap(), 0);
+        assert_eq!(manager.state_db.num_layers(), num_layers);
+        for change in &diff.accounts {
+            assert_eq!(
+                manager.state().get(&keys::Account(change.address)).unwrap(),
+                change.before
+            );
+        }
+        let latest_block_number = diff
+            .properties
+            .iter()
+            .find(|change| change.name == "LatestBlockNumber")
+            .expect("block number changed");
+        assert_eq!(
+            (latest_block_number.before, latest_block_number.after),
+            (Some(0), Some(1))
+        );
+        assert!(diff.properties.iter().all(|change| change.name != "DbVersion"));
+
+        // The diff is what applying the block does.
+        assert!(manager.push_block(&block).unwrap());
+        for change in &diff.accounts {
+            assert_eq!(
+                manager.state().get(&keys::Account(change.address)).unwrap(),
+                change.after
+            );
+        }
+        for change in &diff.storage {
+            let key = keys::ContractStorage(change.address, change.slot);
+            assert_eq!(manager.state().get(&key).unwrap(), change.after);
+        }
+        for change in &diff.properties {
+            let key = format!("k{}", change.name);
+            let value = manager.state().get_raw(state::db::COL_DEFAULT, key.as_bytes()).unwrap();
+            assert_eq!(
+                value.map(|raw| i64::from_be_bytes(raw[..].try_into().unwrap())),
+                change.after,
+                "{}",
+                change.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_block_latency_is_not_in_state() {
+        use self::producer::BlockProducer;
+        use self::txpool::TransactionPool;
+
+        let keypairs = vec![KeyPair::generate()];
+        let mut manager = new_pbft_test_manager(&keypairs);
+        let witness = keypairs[0].address();
+        manager.my_witness = witness.as_bytes().to_vec();
+        let now = manager.get_slot_timestamp(1).unwrap();
+        BlockProducer::new(&mut manager, &keypairs[0])
+            .produce_block(now, &mut TransactionPool::default())
+            .unwrap()
+            .expect("block produced");
+
+        let perf = manager.state().must_get(&keys::WitnessPerformance(witness, 0)).unwrap();
+        assert_eq!(perf.produced, 1);
+        assert_eq!(
+            (perf.num_latency_samples, perf.total_latency, perf.max_latency),
+            (0, 0, 0)
+        );
+
+        // Blocks from syncing are not tracked.
+        manager.record_block_latency(witness, 0, MAX_TRACKED_BLOCK_LATENCY);
+        manager.record_block_latency(witness, 0, -1);
+        assert!(manager.block_latency(&witness, 0).is_none());
+
+        manager.record_block_latency(witness, 0, 100);
+        manager.record_block_latency(witness, 0, 300);
+        let latency = manager.block_latency(&witness, 0).unwrap();
+        assert_eq!((latency.num_samples, latency.total, latency.max), (2, 400, 300));
+        assert_eq!(latency.average(), Some(200.0));
+
+        // Old epochs are dropped.
+        manager.record_block_latency(witness, NUM_OF_LATENCY_TRACKED_EPOCHS, 100);
+        assert!(manager.block_latency(&witness, 0).is_none());
+        assert!(manager.block_latency(&witness, NUM_OF_LATENCY_TRACKED_EPOCHS).is_some());
+    }
+
+    #[test]
+    fn test_result_mismatch_of_failed_block() {
+        use proto::chain::transaction::{
+            result::ContractStatus, Contract, Raw as TransactionRaw, Result as TransactionResult,
+        };
+        use proto::chain::{block_header::Raw as BlockHeaderRaw, Block, BlockHeader, ContractType, Transaction};
+        use proto::contract::TriggerSmartContract;
+        use proto::state::{Account, SmartContract};
+
+        use self::executor::actuators::BuiltinContractExt;
+
+        let keypairs = vec![KeyPair::generate()];
+        let mut manager = new_pbft_test_manager(&keypairs);
+        manager.init_ref_blocks(vec![manager.latest_block_hash().unwrap()]);
+
+        let owner = KeyPair::generate();
+        let cntr_address: Address = "TCCcBZEdTHmS1NfFtCYfwpjBKeTv515n71".parse().unwrap();
+        manager.state_db.new_layer();
+        manager.state_db.put_key(keys::ChainParameter::AllowTvm, 1).unwrap();
+        let mut owner_acct = Account::new(0);
+        owner_acct.balance = 10_000_000_000;
+        manager
+            .state_db
+            .put_key(keys::Account(owner.address()), owner_acct)
+            .unwrap();
+        manager
+            .state_db
+            .put_key(keys::Account(cntr_address), Account::new_contract_account(0))
+            .unwrap();
+        let mut cntr = SmartContract::new_inner();
+        cntr.origin_address = owner.address().as_bytes().to_vec();
+        cntr.contract_address = cntr_address.as_bytes().to_vec();
+        manager.state_db.put_key(keys::Contract(cntr_address), cntr).unwrap();
+        // Runtime: `stop`.
+        manager
+            .state_db
+            .put_key(keys::ContractCode(cntr_address), vec![0x00])
+            .unwrap();
+        manager.state_db.solidify_layer().unwrap();
+
+        // A successful call, with a reverted result in block.
+        let trigger = TriggerSmartContract {
+            owner_address: owner.address().as_bytes().to_vec(),
+            contract_address: cntr_address.as_bytes().to_vec(),
+            ..Default::default()
+        };
+        let raw = TransactionRaw {
+            contract: Some(Contract {
+                r#type: ContractType::TriggerSmartContract as i32,
+                parameter: trigger.to_any(),
+                ..Default::default()
+            }),
+            ref_block_bytes: vec![0, 0],
+            ref_block_hash: manager.latest_block_hash().unwrap().as_bytes()[8..16].to_vec(),
+            expiration: manager.latest_block_timestamp().unwrap() + 60_000,
+            fee_limit: 10_000_000,
+            ..Default::default()
+        };
+        let mut buf = vec![];
+        raw.encode(&mut buf).unwrap();
+        let txn = Transaction {
+            raw_data: Some(raw),
+            signatures: vec![owner.private().sign(&buf).unwrap().as_bytes().to_vec()],
+            result: vec![TransactionResult {
+                contract_status: ContractStatus::Revert as i32,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let raw_header = BlockHeaderRaw {
+            number: 1,
+            timestamp: manager.get_slot_timestamp(1).unwrap(),
+            parent_hash: manager.latest_block_hash().unwrap().as_bytes().to_vec(),
+            witness_address: keypairs[0].address().as_bytes().to_vec(),
+            version: constants::CURRENT_BLOCK_VERSION as i32,
+            // merkle_root_hash will be filled by indexed block
+            ..Default::default()
+        };
+        let mut block = IndexedBlock::from_raw(Block {
+            block_header: Some(BlockHeader {
+                raw_data: Some(raw_header),
+                ..Default::default()
+            }),
+            transactions: vec![txn],
+        })
+        .unwrap();
+        let mut buf = vec![];
+        block.header.raw.raw_data.as_ref().unwrap().encode(&mut buf).unwrap();
+        block.header.raw.witness_signature = keypairs[0].private().sign(&buf).unwrap().as_bytes().to_vec();
+
+        let err = manager.push_block(&block).unwrap_err();
+        assert!(err.to_string().contains("result check not passed"), "{}", err);
+        assert_eq!(manager.latest_block_number().unwrap(), 0);
+        assert_eq!(manager.num_result_mismatches(), 1);
+    }
+
+    #[test]
+    fn test_push_block_verifies_own_witness_address() {
+        use proto::chain::{block_header::Raw as BlockHeaderRaw, Block, BlockHeader};
+
+        let keypair = KeyPair::generate();
+        let mut config = Config::load_from_str(include_str!("../../etc/conf.toml")).unwrap();
+        config.witness.private_key = keypair.private().to_string();
+        let genesis_config = GenesisConfig::load_from_str(include_str!("../../etc/genesis.json")).unwrap();
+        let mut manager = Manager::with_state_db(StateDB::new_in_memory(), &config, &genesis_config);
+
+        // A network block claiming our witness address, signed by someone else.
+        let raw_header = BlockHeaderRaw {
+            number: 1,
+            timestamp: genesis_config.timestamp + constants::BLOCK_PRODUCING_INTERVAL,
+            parent_hash: manager.latest_block_hash().unwrap().as_bytes().to_vec(),
+            witness_address: keypair.address().as_bytes().to_vec(),
+            version: constants::CURRENT_BLOCK_VERSION as i32,
+            ..Default::default()
+        };
+        let mut buf = vec![];
+        raw_header.encode(&mut buf).unwrap();
+        let mut header = BlockHeader {
+            raw_data: Some(raw_header),
+            ..Default::default()
+        };
+        header.witness_signature = KeyPair::generate().private().sign(&buf).unwrap().as_bytes().to_vec();
+        let block = IndexedBlock::from_raw(Block {
+            block_header: Some(header),
+            transactions: vec![],
+        })
+        .unwrap();
+
+        let err = manager.push_block(&block).unwrap_err();
+        assert!(err.to_string().contains("signature"), "{}", err);
+        assert_eq!(manager.latest_block_number().unwrap(), 0);
+    }
+}
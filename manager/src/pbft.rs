@@ -0,0 +1,259 @@
+//! PBFT finality among active witnesses, since 4.1 behind `ChainParameter::AllowPbft`.
+//!
+//! Every applied block is a pre-prepare. Active witnesses broadcast PREPARE for it, and COMMIT once 2/3+1
+//! PREPAREs are seen. A block with 2/3+1 COMMITs is confirmed, the signatures form its commit certificate.
+//!
+//! COMMITs of a block share the same `Raw`, so a certificate is the raw data plus all signatures.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+
+use ::keys::{Address, KeyPair, Public, Signature};
+use chrono::Utc;
+use log::{debug, info, warn};
+use primitive_types::H256;
+use prost::Message;
+use proto::channel::pbft_message::{DataType as PbftDataType, Raw as PbftRaw, Type as PbftType};
+use proto::channel::PbftMessage;
+use proto::state::PbftCommitResult;
+
+/// Blocks older than this are not voted on, e.g. when syncing, in milliseconds.
+const MAX_PBFT_BLOCK_DELAY: i64 = 60_000;
+
+/// Number of votes required among the given number of active witnesses.
+pub fn agreement_threshold(num_witnesses: usize) -> usize {
+    num_witnesses * 2 / 3 + 1
+}
+
+/// Sign a PBFT message on a block.
+pub fn sign_message(keypair: &KeyPair, msg_type: PbftType, epoch: i64, number: i64, hash: &H256) -> PbftMessage {
+    let raw = PbftRaw {
+        msg_type: msg_type as i32,
+        data_type: PbftDataType::Block as i32,
+        view_n: number,
+        epoch,
+        data: hash.as_bytes().to_vec(),
+    };
+    let mut buf = Vec::with_capacity(raw.encoded_len());
+    raw.encode(&mut buf).unwrap();
+    let signature = keypair.private().sign(&buf).expect("sign with a valid private key");
+    PbftMessage {
+        raw_data: Some(raw),
+        signature: signature.as_bytes().to_vec(),
+    }
+}
+
+/// Recover the signing address of a signature on encoded raw data.
+fn recover_signer(raw: &[u8], signature: &[u8]) -> Option<Address> {
+    let sig = Signature::try_from(signature).ok()?;
+    Public::recover(raw, &sig)
+        .ok()
+        .map(|public| Address::from_public(&public))
+}
+
+/// Verify a commit certificate. `signers` are the allowed signing addresses.
+pub fn verify_commit_result(cert: &PbftCommitResult, signers: &[Address]) -> bool {
+    let mut signed = cert
+        .signature
+        .iter()
+        .filter_map(|sig| recover_signer(&cert.data, sig))
+        .filter(|addr| signers.contains(addr))
+        .collect::<Vec<_>>();
+    signed.sort();
+    signed.dedup();
+    signed.len() >= agreement_threshold(signers.len())
+}
+
+/// Votes of one block.
+#[derive(Default)]
+struct PbftRound {
+    prepares: Vec<Address>,
+    commits: HashMap<Address, Vec<u8>>,
+    commit_raw: Vec<u8>,
+    commit_sent: bool,
+}
+
+/// A block confirmed by PBFT, waiting to be recorded by a following block.
+#[derive(Debug, Clone)]
+pub struct ConfirmedBlock {
+    pub number: i64,
+    pub hash: H256,
+    pub commit_result: PbftCommitResult,
+}
+
+/// In-memory PBFT voting state. Confirmed blocks are recorded to the state-db while processing the next block,
+/// so they live in block layers like all other state changes.
+pub struct PbftState {
+    keypair: Option<KeyPair>,
+    rounds: BTreeMap<(i64, H256), PbftRound>,
+    confirmed: BTreeMap<i64, ConfirmedBlock>,
+    outbox: Vec<PbftMessage>,
+}
+
+impl PbftState {
+    pub fn new(keypair: Option<KeyPair>) -> Self {
+        PbftState {
+            keypair,
+            rounds: BTreeMap::new(),
+            confirmed: BTreeMap::new(),
+            outbox: vec![],
+        }
+    }
+
+    /// Confirmed blocks not yet recorded, by block number.
+    pub fn confirmed_blocks(&self) -> impl DoubleEndedIterator<Item = &ConfirmedBlock> {
+        self.confirmed.values()
+    }
+
+    /// Messages signed by this node, to be broadcasted.
+    pub fn take_messages(&mut self) -> Vec<PbftMessage> {
+        std::mem::replace(&mut self.outbox, vec![])
+    }
+
+    /// Drop votes of blocks at or below the solid block.
+    pub fn prune(&mut self, solid_block_number: i64) {
+        self.rounds = self.rounds.split_off(&(solid_block_number + 1, H256::zero()));
+        self.confirmed = self.confirmed.split_off(&(solid_block_number + 1));
+    }
+
+    /// A block is applied, which is the pre-prepare. Send PREPARE if this node is an active witness.
+    ///
+    /// `signers` maps signing addresses of active witnesses to witnesses.
+    pub fn on_block_applied(
+        &mut self,
+        number: i64,
+        hash: H256,
+        timestamp: i64,
+        epoch: i64,
+        signers: &HashMap<Address, Address>,
+    ) {
+        if Utc::now().timestamp_millis() - timestamp > MAX_PBFT_BLOCK_DELAY {
+            return;
+        }
+        let keypair = match self.keypair.as_ref() {
+            Some(keypair) if signers.contains_key(&keypair.address()) => keypair,
+            _ => return,
+        };
+        let msg = sign_message(keypair, PbftType::Prepare, epoch, number, &hash);
+        self.outbox.push(msg.clone());
+        self.on_message(&msg, signers);
+    }
+
+    /// Handle a PREPARE or COMMIT message. Returns whether the message is new, i.e. should be relayed.
+    pub fn on_message(&mut self, msg: &PbftMessage, signers: &HashMap<Address, Address>) -> bool {
+        let raw = match msg.raw_data.as_ref() {
+            Some(raw) if raw.data_type == PbftDataType::Block as i32 && raw.data.len() == 32 => raw,
+            _ => return false,
+        };
+        let mut buf = Vec::with_capacity(raw.encoded_len());
+        raw.encode(&mut buf).unwrap();
+        let witness = match recover_signer(&buf, &msg.signature).and_then(|addr| signers.get(&addr)) {
+            Some(witness) => *witness,
+            None => {
+                debug!("pbft message #{} not signed by an active witness", raw.view_n);
+                return false;
+            }
+        };
+
+        let number = raw.view_n;
+        let hash = H256::from_slice(&raw.data);
+        let threshold = agreement_threshold(signers.len());
+        let round = self.rounds.entry((number, hash)).or_default();
+
+        match PbftType::from_i32(raw.msg_type) {
+            Some(PbftType::Prepare) => {
+                if round.prepares.contains(&witness) {
+                    return false;
+                }
+                round.prepares.push(witness);
+                if round.prepares.len() < threshold || round.commit_sent {
+                    return true;
+                }
+                round.commit_sent = true;
+
+                let commit = self
+                    .keypair
+                    .as_ref()
+                    .filter(|keypair| signers.contains_key(&keypair.address()))
+                    .map(|keypair| sign_message(keypair, PbftType::Commit, raw.epoch, number, &hash));
+                if let Some(commit) = commit {
+                    self.outbox.push(commit.clone());
+                    self.on_message(&commit, signers);
+                }
+                true
+            }
+            Some(PbftType::Commit) => {
+                if round.commits.contains_key(&witness) {
+                    return false;
+                }
+                round.commits.insert(witness, msg.signature.clone());
+                round.commit_raw = buf;
+                if round.commits.len() >= threshold && !self.confirmed.contains_key(&number) {
+                    info!("block #{} {:?} confirmed by pbft", number, hash);
+                    let commit_result = PbftCommitResult {
+                        data: round.commit_raw.clone(),
+                        signature: round.commits.values().cloned().collect(),
+                    };
+                    self.confirmed.insert(
+                        number,
+                        ConfirmedBlock {
+                            number,
+                            hash,
+                            commit_result,
+                        },
+                    );
+                }
+                true
+            }
+            _ => {
+                warn!("unsupported pbft message type {}", raw.msg_type);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pbft_commit() {
+        let keypairs = (0..4).map(|_| KeyPair::generate()).collect::<Vec<_>>();
+        let signers = keypairs
+            .iter()
+            .map(|kp| (kp.address(), kp.address()))
+            .collect::<HashMap<_, _>>();
+        let hash = H256::repeat_byte(0x42);
+        let now = Utc::now().timestamp_millis();
+
+        let mut state = PbftState::new(Some(keypairs[0].clone()));
+        state.on_block_applied(100, hash, now, 1, &signers);
+        assert_eq!(state.take_messages().len(), 1);
+
+        // 2/3+1 of 4 is 3.
+        for kp in &keypairs[1..3] {
+            let prepare = sign_message(kp, PbftType::Prepare, 1, 100, &hash);
+            assert!(state.on_message(&prepare, &signers));
+            assert!(!state.on_message(&prepare, &signers));
+        }
+        let commits = state.take_messages();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].raw_data.as_ref().unwrap().msg_type, PbftType::Commit as i32);
+
+        for kp in &keypairs[1..3] {
+            let commit = sign_message(kp, PbftType::Commit, 1, 100, &hash);
+            assert!(state.on_message(&commit, &signers));
+        }
+        let confirmed = state.confirmed_blocks().next().cloned().unwrap();
+        assert_eq!(confirmed.number, 100);
+        assert_eq!(confirmed.hash, hash);
+
+        let addrs = keypairs.iter().map(|kp| kp.address()).collect::<Vec<_>>();
+        assert!(verify_commit_result(&confirmed.commit_result, &addrs));
+        assert!(!verify_commit_result(&confirmed.commit_result, &addrs[2..]));
+
+        state.prune(100);
+        assert!(state.confirmed_blocks().next().is_none());
+    }
+}
@@ -0,0 +1,157 @@
+//! DPoS block producing.
+
+use std::convert::TryFrom;
+
+use ::keys::{Address, KeyPair};
+use chain::{IndexedBlock, IndexedBlockHeader, IndexedTransaction};
+use log::{debug, info, warn};
+use prost::Message;
+use proto::chain::{block_header::Raw as BlockHeaderRaw, Block, BlockHeader};
+use state::keys;
+
+use super::txpool::TransactionPool;
+use super::Manager;
+
+type Error = Box<dyn std::error::Error>;
+
+/// Reserved for block header and witness signature.
+const BLOCK_HEADER_RESERVED_SIZE: usize = 1_000;
+
+/// Produces blocks in the slots scheduled to the witness.
+pub struct BlockProducer<'m> {
+    manager: &'m mut Manager,
+    keypair: &'m KeyPair,
+}
+
+impl<'m> BlockProducer<'m> {
+    pub fn new(manager: &'m mut Manager, keypair: &'m KeyPair) -> Self {
+        BlockProducer { manager, keypair }
+    }
+
+    /// Produce a block if current time falls in a slot of the witness. Transactions are packed from the
+    /// pending pool, expired and invalid ones are dropped.
+    ///
+    /// The block is signed and applied to the state-db, the caller should save and broadcast it.
+    pub fn produce_block(&mut self, now: i64, pending: &mut TransactionPool) -> Result<Option<IndexedBlock>, Error> {
+        let slot = self.manager.get_slot(now)?;
+        if slot == 0 {
+            return Ok(None);
+        }
+        let timestamp = self.manager.get_slot_timestamp(slot)?;
+        if now < timestamp || now - timestamp >= constants::BLOCK_PRODUCING_INTERVAL / 2 {
+            // Not yet, or too late for the slot.
+            return Ok(None);
+        }
+
+        let my_address = Address::try_from(&self.manager.my_witness).map_err(|_| "witness is not configured")?;
+        if !self.is_scheduled(slot, &my_address)? {
+            return Ok(None);
+        }
+        // With multisig allowed, the witness might delegate block signing to its witness permission key.
+        let signature_key = self.manager.get_witness_signature_key(&my_address)?;
+        if signature_key != self.keypair.address() {
+            warn!(
+                "witness {} must be signed by {}, skip producing block",
+                my_address, signature_key
+            );
+            return Ok(None);
+        }
+
+        let number = self.manager.latest_block_number()? + 1;
+        let raw_header = BlockHeaderRaw {
+            number,
+            timestamp,
+            parent_hash: self.manager.latest_block_hash()?.as_bytes().to_vec(),
+            witness_address: my_address.as_bytes().to_vec(),
+            version: constants::CURRENT_BLOCK_VERSION as i32,
+            // merkle_root_hash will be filled by indexed block
+            ..Default::default()
+        };
+        let header = BlockHeader {
+            raw_data: Some(raw_header),
+            ..Default::default()
+        };
+
+        let transactions = self.pack_transactions(&header, pending)?;
+        let mut block = IndexedBlock::from_raw(Block {
+            block_header: Some(header),
+            transactions: transactions.into_iter().map(|txn| txn.raw).collect(),
+        })
+        .ok_or("malformed block")?;
+
+        let mut buf = Vec::with_capacity(255);
+        block.header.raw.raw_data.as_ref().unwrap().encode(&mut buf)?;
+        block.header.raw.witness_signature = self.keypair.private().sign(&buf)?.as_bytes().to_vec();
+
+        if !self.manager.push_produced_block(&block)? {
+            return Err(From::from("produced block is rejected"));
+        }
+        info!(
+            "produced block #{} txns={} witness={}",
+            block.number(),
+            block.transactions.len(),
+            my_address
+        );
+        Ok(Some(block))
+    }
+
+    fn is_scheduled(&self, slot: i64, addr: &Address) -> Result<bool, Error> {
+        // Witness schedule is inited in the first maintenance, any genesis witness can produce block #1.
+        if self.manager.latest_block_number()? == 0 {
+            return Ok(self
+                .manager
+                .genesis_config
+                .witnesses
+                .iter()
+                .any(|wit| wit.address.parse::<Address>().ok().as_ref() == Some(addr)));
+        }
+        Ok(self.manager.get_scheduled_witness(slot)? == *addr)
+    }
+
+    /// Try transactions on a savepoint, keep valid ones until `MAX_BLOCK_SIZE` is reached.
+    fn pack_transactions(
+        &mut self,
+        header: &BlockHeader,
+        pending: &mut TransactionPool,
+    ) -> Result<Vec<IndexedTransaction>, Error> {
+        let block = IndexedBlock::new(
+            IndexedBlockHeader::from_raw(header.clone()).ok_or("malformed block header")?,
+            vec![],
+        );
+
+        let num_expired = pending.remove_expired(block.header.timestamp());
+        if num_expired > 0 {
+            debug!("drop {} expired transactions", num_expired);
+        }
+
+        let mut transactions = vec![];
+        let mut block_size = BLOCK_HEADER_RESERVED_SIZE;
+
+        // All changes are discarded, the block is applied again by `push_block`.
+        let mut manager = self.manager.savepoint();
+        while let Some(txn) = pending.pop_front() {
+            let txn_size = txn.raw.encoded_len();
+            if block_size + txn_size > constants::MAX_BLOCK_SIZE {
+                pending.push_front(txn);
+                break;
+            }
+            if manager.state_db.get(&keys::TransactionReceipt(txn.hash))?.is_some() {
+                debug!("drop duplicated transaction {:?}", txn.hash);
+                continue;
+            }
+
+            let mut txn_manager = manager.savepoint();
+            match txn_manager.process_transaction(&txn, transactions.len(), txn.recover_owner(), &block) {
+                Ok(()) => {
+                    txn_manager.commit();
+                    block_size += txn_size;
+                    transactions.push(txn);
+                }
+                Err(e) => {
+                    warn!("drop transaction {:?}: {}", txn.hash, e);
+                }
+            }
+        }
+        Ok(transactions)
+    }
+}
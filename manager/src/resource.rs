@@ -12,7 +12,7 @@ use state::keys;
 
 use super::executor::actuators::asset::find_asset_by_name;
 use super::executor::actuators::BuiltinContractExt;
-use super::executor::TransactionContext;
+use super::executor::{ExecutionError, TransactionContext};
 use super::version_fork::ForkController;
 use super::Manager;
 
@@ -45,7 +45,7 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
             return Err("transaction result is too big".into());
         }
         let owner_address = *Address::from_bytes(cntr.owner_address());
-        let owner_acct = manager.state_db.must_get(&keys::Account(owner_address));
+        let owner_acct = manager.state_db.must_get(&keys::Account(owner_address))?;
 
         Ok(BandwidthProcessor {
             manager,
@@ -56,8 +56,8 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
         })
     }
 
-    pub fn consume(mut self, ctx: &mut TransactionContext) -> Result<(), String> {
-        let byte_size = if self.manager.state_db.must_get(&keys::ChainParameter::AllowTvm) == 1 {
+    pub fn consume(mut self, ctx: &mut TransactionContext) -> Result<(), ExecutionError> {
+        let byte_size = if self.manager.state_db.must_get(&keys::ChainParameter::AllowTvm)? == 1 {
             if self.txn.raw.result.is_empty() {
                 self.txn.raw.encoded_len() + constants::MAX_TRANSACTION_RESULT_SIZE
             } else {
@@ -76,65 +76,86 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
             debug!("consume multisig fee");
             self.acct
                 .adjust_balance(-ctx.multisig_fee)
-                .map_err(|_| "insufficient balance to multisig")?;
+                .map_err(|_| ExecutionError::InsufficientBalance)?;
         }
 
         // NOTE: `now` is not a timestamp, it's a `slot`.
-        let now = self.manager.get_head_slot();
+        let now = self.manager.get_head_slot()?;
 
         if ctx.new_account_created {
             // consumeForCreateNewAccount
-            if self.consume_frozen_bandwidth_for_new_account_creation(byte_size, now) ||
-                self.consume_fee_for_new_account_creation(ctx)
+            if self.consume_frozen_bandwidth_for_new_account_creation(byte_size, now)? ||
+                self.consume_fee_for_new_account_creation(ctx)?
             {
                 // covers all bw expense
                 return Ok(());
             } else {
-                return Err("insufficient balance to create new account".into());
+                return Err(ExecutionError::BandwidthExhausted);
             }
         }
 
         // NOTE: Since Rust has no simple downcast support, use unsafe here.
         if self.cntr.type_code() == ContractType::TransferAssetContract &&
-            self.consume_asset_bandwidth(unsafe { std::mem::transmute(self.cntr) }, byte_size, now, ctx)
+            self.consume_asset_bandwidth(unsafe { std::mem::transmute(self.cntr) }, byte_size, now, ctx)?
         {
             return Ok(());
         }
 
         // NOTE: first use frozen bw, then free bw
 
-        if self.consume_frozen_bandwidth(byte_size, now, ctx) {
+        if self.consume_frozen_bandwidth(byte_size, now, ctx)? {
             return Ok(());
         }
 
-        if self.consume_free_bandwidth(byte_size, now, ctx) {
+        if self.consume_free_bandwidth(byte_size, now, ctx)? {
             return Ok(());
         }
 
         // burn for bandwidth
-        if self.consume_burnt_bandwidth(byte_size, ctx) {
+        if self.consume_burnt_bandwidth(byte_size, ctx)? {
             return Ok(());
         }
 
-        Err("insufficient bandwidth".into())
+        Err(ExecutionError::BandwidthExhausted)
     }
 
     // Renamed: useTransactionFee
-    fn consume_burnt_bandwidth(&mut self, nbytes: i64, ctx: &mut TransactionContext) -> bool {
-        let bw_fee = self.manager.state_db.must_get(&keys::ChainParameter::BandwidthFee) * nbytes;
+    fn consume_burnt_bandwidth(&mut self, nbytes: i64, ctx: &mut TransactionContext) -> Result<bool, String> {
+        let bw_fee = self.manager.state_db.must_get(&keys::ChainParameter::BandwidthFee)? * nbytes;
         if self.acct.adjust_balance(-bw_fee).is_err() {
-            return false;
+            return Ok(false);
         }
 
         ctx.bandwidth_fee = bw_fee;
-        true
+        self.accrue_bandwidth_fee(bw_fee)?;
+        Ok(true)
+    }
+
+    /// Bandwidth fees are accrued in the fee pool since `AllowTransactionFeePool`.
+    ///
+    /// NOTE: Before that, bandwidth fees are not credited to the Blackhole account.
+    fn accrue_bandwidth_fee(&mut self, fee: i64) -> Result<(), String> {
+        if self
+            .manager
+            .state_db
+            .must_get(&keys::ChainParameter::AllowTransactionFeePool)? !=
+            0
+        {
+            self.manager.add_transaction_fee(fee).map_err(|e| e.to_string())?;
+        }
+        Ok(())
     }
 
     // Renamed: useAccountNet
-    fn consume_frozen_bandwidth(&mut self, nbytes: i64, now: i64, _ctx: &mut TransactionContext) -> bool {
+    fn consume_frozen_bandwidth(
+        &mut self,
+        nbytes: i64,
+        now: i64,
+        _ctx: &mut TransactionContext,
+    ) -> Result<bool, String> {
         let bw_usage = self.acct.resource().frozen_bandwidth_used;
         let bw_latest_slot = self.acct.resource().frozen_bandwidth_latest_slot;
-        let bw_limit = self.calculate_global_bandwidth_limit(&self.acct);
+        let bw_limit = self.calculate_global_bandwidth_limit(&self.acct)?;
 
         let mut new_bw_usage = adjust_usage(bw_usage, 0, bw_latest_slot, now);
 
@@ -146,14 +167,14 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
                     new_bw_usage, bw_limit, nbytes
                 );
             }
-            return false;
+            return Ok(false);
         }
 
         // consume frozen/delegated bw
         let latest_op_ts = self
             .manager
             .state_db
-            .must_get(&keys::DynamicProperty::LatestBlockTimestamp);
+            .must_get(&keys::DynamicProperty::LatestBlockTimestamp)?;
         new_bw_usage = adjust_usage(new_bw_usage, nbytes, now, now);
 
         self.acct.latest_operation_timestamp = latest_op_ts;
@@ -161,11 +182,11 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
         self.acct.resource_mut().frozen_bandwidth_latest_slot = now;
 
         debug!("frozen BW usage: {}/{} (+{})", new_bw_usage, bw_limit, nbytes);
-        true
+        Ok(true)
     }
 
     // Renamed: useFreeNet.
-    fn consume_free_bandwidth(&mut self, nbytes: i64, now: i64, _ctx: &mut TransactionContext) -> bool {
+    fn consume_free_bandwidth(&mut self, nbytes: i64, now: i64, _ctx: &mut TransactionContext) -> Result<bool, String> {
         let free_bw_limit = constants::FREE_BANDWIDTH;
         let free_bw_usage = self.acct.resource().free_bandwidth_used;
         let mut free_bw_latest_slot = self.acct.resource().free_bandwidth_latest_slot;
@@ -176,33 +197,33 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
                 "free BW is insufficient {}/{}, require {}, will burn",
                 new_free_bw_usage, free_bw_limit, nbytes
             );
-            return false;
+            return Ok(false);
         }
 
         // global free bandwidth
         let g_bw_limit = self
             .manager
             .state_db
-            .must_get(&keys::DynamicProperty::GlobalFreeBandwidthLimit);
+            .must_get(&keys::DynamicProperty::GlobalFreeBandwidthLimit)?;
         let g_bw_usage = self
             .manager
             .state_db
-            .must_get(&keys::DynamicProperty::GlobalFreeBandwidthUsed);
+            .must_get(&keys::DynamicProperty::GlobalFreeBandwidthUsed)?;
         let mut g_bw_latest_slot = self
             .manager
             .state_db
-            .must_get(&keys::DynamicProperty::GlobalFreeBandwidthLatestSlot);
+            .must_get(&keys::DynamicProperty::GlobalFreeBandwidthLatestSlot)?;
 
         let mut new_g_bw_usage = adjust_usage(g_bw_usage, 0, g_bw_latest_slot, now);
         if nbytes > g_bw_limit - new_g_bw_usage {
             debug!("global free BW is insufficient");
-            return false;
+            return Ok(false);
         }
 
         free_bw_latest_slot = now;
         g_bw_latest_slot = now;
         // FIXME: Is getHeadBlockTimeStamp current block?
-        let lastes_op_ts = self.manager.latest_block_timestamp();
+        let lastes_op_ts = self.manager.latest_block_timestamp()?;
         new_free_bw_usage = adjust_usage(new_free_bw_usage, nbytes, free_bw_latest_slot, now);
         new_g_bw_usage = adjust_usage(new_g_bw_usage, nbytes, g_bw_latest_slot, now);
 
@@ -214,14 +235,12 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
 
         self.manager
             .state_db
-            .put_key(keys::DynamicProperty::GlobalFreeBandwidthUsed, new_g_bw_usage)
-            .unwrap();
+            .put_key(keys::DynamicProperty::GlobalFreeBandwidthUsed, new_g_bw_usage)?;
         self.manager
             .state_db
-            .put_key(keys::DynamicProperty::GlobalFreeBandwidthLatestSlot, g_bw_latest_slot)
-            .unwrap();
+            .put_key(keys::DynamicProperty::GlobalFreeBandwidthLatestSlot, g_bw_latest_slot)?;
 
-        true
+        Ok(true)
     }
 
     // useAssetAccountNet
@@ -231,17 +250,17 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
         nbytes: i64,
         now: i64,
         _ctx: &mut TransactionContext,
-    ) -> bool {
+    ) -> Result<bool, String> {
         let allow_same_token_name = self
             .manager
             .state_db
-            .must_get(&keys::ChainParameter::AllowSameTokenName) !=
+            .must_get(&keys::ChainParameter::AllowSameTokenName)? !=
             0;
         let mut asset = if allow_same_token_name {
             let token_id = cntr.asset_name.parse().unwrap();
-            self.manager.state_db.must_get(&keys::Asset(token_id))
+            self.manager.state_db.must_get(&keys::Asset(token_id))?
         } else {
-            find_asset_by_name(self.manager, &cntr.asset_name).expect("must find by asset name")
+            find_asset_by_name(self.manager, &cntr.asset_name)?.expect("must find by asset name")
         };
         let token_id = asset.id;
 
@@ -251,7 +270,7 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
             // Avoid calling `consume_frozen_bandwidth` twice.
             //
             // return self.consume_frozen_bandwidth(addr, acct, nbytes, now, ctx);
-            return false;
+            return Ok(false);
         }
 
         // check public limit
@@ -263,7 +282,7 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
         );
         if nbytes > asset.public_free_asset_bandwidth_limit - new_public_free_asset_bw_usage {
             debug!("asset {} public free BW is insufficient", token_id);
-            return false;
+            return Ok(false);
         }
 
         // check pre-account-limit
@@ -286,13 +305,13 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
 
         if nbytes > asset.free_asset_bandwidth_limit - new_free_asset_bw_usage {
             debug!("asset {} free BW is insufficient", token_id);
-            return false;
+            return Ok(false);
         }
 
         // check issuer's frozen bw
         let issuer_addr = *Address::from_bytes(&asset.owner_address);
-        let mut issuer_acct = self.manager.state_db.must_get(&keys::Account(issuer_addr));
-        let issuer_bw_limit = self.calculate_global_bandwidth_limit(&issuer_acct);
+        let mut issuer_acct = self.manager.state_db.must_get(&keys::Account(issuer_addr))?;
+        let issuer_bw_limit = self.calculate_global_bandwidth_limit(&issuer_acct)?;
 
         let new_issuer_bw_usage = adjust_usage(
             issuer_acct.resource().frozen_bandwidth_used,
@@ -303,11 +322,11 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
 
         if nbytes > issuer_bw_limit - new_issuer_bw_usage {
             debug!("asset {} issuer bandwidth is insufficient", token_id);
-            return false;
+            return Ok(false);
         }
 
         // now consume
-        let latest_op_ts = self.manager.latest_block_timestamp();
+        let latest_op_ts = self.manager.latest_block_timestamp()?;
 
         let new_issuer_bw_usage = adjust_usage(new_issuer_bw_usage, nbytes, now, now);
         let new_free_asset_bw_usage = adjust_usage(new_free_asset_bw_usage, nbytes, now, now);
@@ -342,19 +361,19 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
             .insert(token_id, new_free_asset_bw_usage);
 
         // now save
-        self.manager
-            .state_db
-            .put_key(keys::Account(issuer_addr), issuer_acct)
-            .unwrap();
-        self.manager.state_db.put_key(keys::Asset(token_id), asset).unwrap();
+        self.manager.state_db.put_key(keys::Account(issuer_addr), issuer_acct)?;
+        self.manager.state_db.put_key(keys::Asset(token_id), asset)?;
 
-        true
+        Ok(true)
     }
 
     /// `consumeFeeForCreateNewAccount`
-    fn consume_fee_for_new_account_creation(&mut self, ctx: &mut TransactionContext) -> bool {
+    fn consume_fee_for_new_account_creation(&mut self, ctx: &mut TransactionContext) -> Result<bool, String> {
         // NOTE: distinguish `AccountCreateFee` from `CreateNewAccountFeeInSystemContract`
-        let creation_fee = self.manager.state_db.must_get(&keys::ChainParameter::AccountCreateFee);
+        let creation_fee = self
+            .manager
+            .state_db
+            .must_get(&keys::ChainParameter::AccountCreateFee)?;
         // consumeFee
         if self.acct.balance >= creation_fee {
             debug!("create account by BW fee");
@@ -362,26 +381,27 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
             assert!(self.acct.adjust_balance(-creation_fee).is_ok());
             ctx.bandwidth_fee = creation_fee;
             ctx.bandwidth_usage = 0;
-            true
+            self.accrue_bandwidth_fee(creation_fee)?;
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     }
 
     /// `consumeBandwidthForCreateNewAccount`
     ///
     /// When an account has frozen enough bandwidth, it can create account freely.
-    fn consume_frozen_bandwidth_for_new_account_creation(&mut self, nbytes: i64, now: i64) -> bool {
+    fn consume_frozen_bandwidth_for_new_account_creation(&mut self, nbytes: i64, now: i64) -> Result<bool, String> {
         let new_acct_bw_ratio = self
             .manager
             .state_db
-            .must_get(&keys::ChainParameter::CreateNewAccountBandwidthRate);
+            .must_get(&keys::ChainParameter::CreateNewAccountBandwidthRate)?;
 
         // prost use optional fields for sub field.
 
         let bw_usage = self.acct.resource().frozen_bandwidth_used;
         let bw_latest_slot = self.acct.resource().frozen_bandwidth_latest_slot;
-        let bw_limit = self.calculate_global_bandwidth_limit(&self.acct);
+        let bw_limit = self.calculate_global_bandwidth_limit(&self.acct)?;
 
         let mut new_bw_usage = adjust_usage(bw_usage, 0, bw_latest_slot, now);
 
@@ -390,7 +410,7 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
             let latest_op_ts = self
                 .manager
                 .state_db
-                .must_get(&keys::DynamicProperty::LatestBlockTimestamp);
+                .must_get(&keys::DynamicProperty::LatestBlockTimestamp)?;
             new_bw_usage = adjust_usage(new_bw_usage, nbytes * new_acct_bw_ratio, now, now);
 
             debug!(
@@ -404,17 +424,17 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
             self.acct.resource_mut().frozen_bandwidth_latest_slot = now;
             self.acct.resource_mut().frozen_bandwidth_used = new_bw_usage;
 
-            true
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     }
 
     /// `calculateGlobalNetLimit`
-    fn calculate_global_bandwidth_limit(&self, acct: &Account) -> i64 {
+    fn calculate_global_bandwidth_limit(&self, acct: &Account) -> Result<i64, String> {
         let amount_for_bw = acct.amount_for_bandwidth();
         if amount_for_bw < 1_000_000 {
-            return 0;
+            return Ok(0);
         }
         let bw_weight = amount_for_bw / 1_000_000;
         // NOTE: Although resource weight values update as new freeze and unfreeze transactions handled,
@@ -429,17 +449,17 @@ impl<C: BuiltinContractExt> BandwidthProcessor<'_, C> {
         let total_bw_limit = self
             .manager
             .state_db
-            .must_get(&keys::DynamicProperty::TotalBandwidthLimit);
+            .must_get(&keys::DynamicProperty::TotalBandwidthLimit)?;
 
         let total_bw_weight = self
             .manager
             .state_db
-            .must_get(&keys::DynamicProperty::TotalBandwidthWeight);
+            .must_get(&keys::DynamicProperty::TotalBandwidthWeight)?;
 
         if total_bw_weight == 0 {
-            return 0;
+            return Ok(0);
         }
-        return (bw_weight as f64 * (total_bw_limit as f64 / total_bw_weight as f64)) as i64;
+        Ok((bw_weight as f64 * (total_bw_limit as f64 / total_bw_weight as f64)) as i64)
     }
 }
 
@@ -504,24 +524,22 @@ impl EnergyProcessor<'_> {
 
         // NOTE: Won't handle origin = NULL due to type safety.
 
-        let now = self.manager.get_head_slot();
-        let caller_acct = self.manager.state_db.must_get(&keys::Account(caller));
+        let now = self.manager.get_head_slot()?;
+        let caller_acct = self.manager.state_db.must_get(&keys::Account(caller))?;
 
         if caller == origin {
             debug!("E usage: caller=origin={}", energy_used);
             return self.consume_energy(caller, caller_acct, energy_used, now, ctx);
         }
 
-        let mut origin_acct = self.manager.state_db.must_get(&keys::Account(origin));
+        let mut origin_acct = self.manager.state_db.must_get(&keys::Account(origin))?;
 
         let mut origin_usage = energy_used * (100 - caller_percent) / 100;
-        origin_usage = EnergyUtil::new(self.manager).get_origin_usage(&origin_acct, origin_energy_limit, origin_usage);
+        origin_usage =
+            EnergyUtil::new(self.manager).get_origin_usage(&origin_acct, origin_energy_limit, origin_usage)?;
 
-        if self.consume_frozen_energy(&mut origin_acct, origin_usage, now) {
-            self.manager
-                .state_db
-                .put_key(keys::Account(origin), origin_acct)
-                .unwrap();
+        if self.consume_frozen_energy(&mut origin_acct, origin_usage, now)? {
+            self.manager.state_db.put_key(keys::Account(origin), origin_acct)?;
             ctx.origin_energy_usage = origin_usage;
         }
 
@@ -542,16 +560,16 @@ impl EnergyProcessor<'_> {
         now: i64,
         ctx: &mut TransactionContext,
     ) -> Result<(), String> {
-        let energy_left = EnergyUtil::new(self.manager).get_left_frozen_energy(&acct);
+        let energy_left = EnergyUtil::new(self.manager).get_left_frozen_energy(&acct)?;
 
         if energy_left >= energy_used {
-            assert!(self.consume_frozen_energy(&mut acct, energy_used, now) || energy_used == 0);
+            assert!(self.consume_frozen_energy(&mut acct, energy_used, now)? || energy_used == 0);
             ctx.energy_usage = energy_used;
             if energy_used > 0 {
                 debug!("E usage: total={} frozen={}", energy_used, energy_used,);
             }
         } else {
-            let consumed = self.consume_frozen_energy(&mut acct, energy_left, now);
+            let consumed = self.consume_frozen_energy(&mut acct, energy_left, now)?;
             ctx.energy_usage = energy_left;
             if consumed {
                 assert!(energy_left >= 0);
@@ -562,14 +580,16 @@ impl EnergyProcessor<'_> {
             // NOTE: Since this implementation is lightweight, no need to check pass VERSION_3_6_5.
             self.manager.block_energy_usage += energy_used - energy_left;
 
-            let energy_price = self.manager.state_db.must_get(&keys::ChainParameter::EnergyFee);
+            let energy_price = self.manager.state_db.must_get(&keys::ChainParameter::EnergyFee)?;
             let energy_fee = (energy_used - energy_left) * energy_price;
 
             if acct.adjust_balance(-energy_fee).is_err() {
                 return Err("insufficient balance to burn for energy".into());
             }
             ctx.energy_fee = energy_fee;
-            self.manager.add_to_blackhole(energy_fee).unwrap();
+            self.manager
+                .add_transaction_fee(energy_fee)
+                .map_err(|e| e.to_string())?;
 
             debug!(
                 "E usage: total={} frozen={} burnt={} fee={}",
@@ -579,16 +599,16 @@ impl EnergyProcessor<'_> {
                 energy_fee
             );
         }
-        self.manager.state_db.put_key(keys::Account(addr), acct).unwrap();
+        self.manager.state_db.put_key(keys::Account(addr), acct)?;
 
         Ok(())
     }
 
     // energyProcessor.useEnergy - caller must save acct.
-    fn consume_frozen_energy(&mut self, acct: &mut Account, energy_used: i64, now: i64) -> bool {
+    fn consume_frozen_energy(&mut self, acct: &mut Account, energy_used: i64, now: i64) -> Result<bool, String> {
         let e_usage = acct.resource().energy_used;
         let e_latest_slot = acct.resource().energy_latest_slot;
-        let e_limit = EnergyUtil::new(self.manager).calculate_global_energy_limit(&acct);
+        let e_limit = EnergyUtil::new(self.manager).calculate_global_energy_limit(&acct)?;
 
         let mut new_e_usage = adjust_usage(e_usage, 0, e_latest_slot, now);
 
@@ -598,14 +618,14 @@ impl EnergyProcessor<'_> {
             e_limit - new_e_usage
         );
         if energy_used > e_limit - new_e_usage {
-            return false;
+            return Ok(false);
         }
 
         debug!("E: used={} remain={}", energy_used, e_limit - new_e_usage);
         let latest_op_ts = self
             .manager
             .state_db
-            .must_get(&keys::DynamicProperty::LatestBlockTimestamp);
+            .must_get(&keys::DynamicProperty::LatestBlockTimestamp)?;
         new_e_usage = adjust_usage(new_e_usage, energy_used, now, now);
 
         acct.resource_mut().energy_used = new_e_usage;
@@ -617,23 +637,23 @@ impl EnergyProcessor<'_> {
 
         self.manager.block_energy_usage += energy_used;
 
-        true
+        Ok(true)
     }
 
     // updateTotalEnergyAverageUsage + updateAdaptiveTotalEnergyLimit
     pub fn update_adaptive_energy(&mut self) -> Result<(), String> {
         // updateTotalEnergyAverageUsage
-        let now = self.manager.get_head_slot();
+        let now = self.manager.get_head_slot()?;
 
         let block_energy_usage = self.manager.block_energy_usage;
         let total_energy_average_usage = self
             .manager
             .state_db
-            .must_get(&keys::DynamicProperty::TotalEnergyAverageUsage);
+            .must_get(&keys::DynamicProperty::TotalEnergyAverageUsage)?;
         let total_energy_average_slot = self
             .manager
             .state_db
-            .must_get(&keys::DynamicProperty::TotalEnergyAverageSlot);
+            .must_get(&keys::DynamicProperty::TotalEnergyAverageSlot)?;
 
         let new_total_energy_average_usage = adjust_usage(
             total_energy_average_usage,
@@ -647,28 +667,27 @@ impl EnergyProcessor<'_> {
             new_total_energy_average_usage, block_energy_usage
         );
 
+        self.manager.state_db.put_key(
+            keys::DynamicProperty::TotalEnergyAverageUsage,
+            new_total_energy_average_usage,
+        )?;
         self.manager
             .state_db
-            .put_key(
-                keys::DynamicProperty::TotalEnergyAverageUsage,
-                new_total_energy_average_usage,
-            )
-            .unwrap();
-        self.manager
-            .state_db
-            .put_key(keys::DynamicProperty::TotalEnergyAverageSlot, now)
-            .unwrap();
+            .put_key(keys::DynamicProperty::TotalEnergyAverageSlot, now)?;
 
         // updateAdaptiveTotalEnergyLimit
         let total_energy_target_limit = self
             .manager
             .state_db
-            .must_get(&keys::DynamicProperty::TotalEnergyTargetLimit);
+            .must_get(&keys::DynamicProperty::TotalEnergyTargetLimit)?;
         let total_energy_curr_limit = self
             .manager
             .state_db
-            .must_get(&keys::ChainParameter::TotalEnergyCurrentLimit);
-        let total_energy_limit = self.manager.state_db.must_get(&keys::ChainParameter::TotalEnergyLimit);
+            .must_get(&keys::ChainParameter::TotalEnergyCurrentLimit)?;
+        let total_energy_limit = self
+            .manager
+            .state_db
+            .must_get(&keys::ChainParameter::TotalEnergyLimit)?;
 
         let mut new_curr_limit = if total_energy_average_usage > total_energy_target_limit {
             total_energy_curr_limit * constants::ADAPTIVE_ENERGY_DECREASE_RATE_NUMERATOR /
@@ -682,12 +701,11 @@ impl EnergyProcessor<'_> {
             total_energy_limit *
                 self.manager
                     .state_db
-                    .must_get(&keys::ChainParameter::AdaptiveResourceLimitMultiplier),
+                    .must_get(&keys::ChainParameter::AdaptiveResourceLimitMultiplier)?,
         );
         self.manager
             .state_db
-            .put_key(keys::ChainParameter::TotalEnergyCurrentLimit, new_curr_limit)
-            .unwrap();
+            .put_key(keys::ChainParameter::TotalEnergyCurrentLimit, new_curr_limit)?;
 
         debug!(
             "total energy current limit update: {} => {}",
@@ -709,48 +727,50 @@ impl EnergyUtil<'_> {
     }
 
     // getAccountLeftEnergyFromFreeze
-    pub fn get_left_frozen_energy(&self, acct: &Account) -> i64 {
-        let now = self.manager.get_head_slot();
+    pub fn get_left_frozen_energy(&self, acct: &Account) -> Result<i64, String> {
+        let now = self.manager.get_head_slot()?;
 
         let e_usage = acct.resource().energy_used;
         let e_latest_slot = acct.resource().energy_latest_slot;
-        let e_limit = self.calculate_global_energy_limit(acct);
+        let e_limit = self.calculate_global_energy_limit(acct)?;
 
         let new_e_usage = adjust_usage(e_usage, 0, e_latest_slot, now);
 
-        return (e_limit - new_e_usage).max(0);
+        Ok((e_limit - new_e_usage).max(0))
     }
 
     // getOriginUsage
-    pub fn get_origin_usage(&self, origin_acct: &Account, origin_energy_limit: i64, origin_usage: i64) -> i64 {
-        let energy_left = self.get_left_frozen_energy(origin_acct);
-        if ForkController::new(self.manager)
-            .pass_version(BlockVersion::ENERGY_LIMIT())
-            .unwrap()
-        {
-            origin_usage.min(energy_left).min(origin_energy_limit)
+    pub fn get_origin_usage(
+        &self,
+        origin_acct: &Account,
+        origin_energy_limit: i64,
+        origin_usage: i64,
+    ) -> Result<i64, String> {
+        let energy_left = self.get_left_frozen_energy(origin_acct)?;
+        if ForkController::new(self.manager).pass_version(BlockVersion::ENERGY_LIMIT())? {
+            Ok(origin_usage.min(energy_left).min(origin_energy_limit))
         } else {
-            origin_usage.min(energy_left)
+            Ok(origin_usage.min(energy_left))
         }
     }
 
     // calculateGlobalEnergyLimit
-    pub fn calculate_global_energy_limit(&self, acct: &Account) -> i64 {
+    pub fn calculate_global_energy_limit(&self, acct: &Account) -> Result<i64, String> {
         let amount_for_energy = acct.amount_for_energy();
         if amount_for_energy < 1_000_000 {
-            return 0;
+            return Ok(0);
         }
         let energy_weight = amount_for_energy / 1_000_000;
         let total_energy_limit = self
             .manager
             .state_db
-            .must_get(&keys::ChainParameter::TotalEnergyCurrentLimit);
+            .must_get(&keys::ChainParameter::TotalEnergyCurrentLimit)?;
         let total_energy_weight = self
             .manager
             .state_db
-            .must_get(&keys::DynamicProperty::TotalEnergyWeight);
+            .must_get(&keys::DynamicProperty::TotalEnergyWeight)?;
 
         assert!(total_energy_limit > 0, "total energy limit must be greater than 0");
-        (energy_weight as f64 * (total_energy_limit as f64 / total_energy_weight as f64)) as i64
+        Ok((energy_weight as f64 * (total_energy_limit as f64 / total_energy_weight as f64)) as i64)
     }
 }
@@ -0,0 +1,127 @@
+//! Pending transactions, waiting to be packed into a block.
+
+use std::collections::{HashSet, VecDeque};
+
+use chain::IndexedTransaction;
+use primitive_types::H256;
+
+/// A bounded FIFO pool of pending transactions, deduplicated by hash.
+pub struct TransactionPool {
+    queue: VecDeque<IndexedTransaction>,
+    hashes: HashSet<H256>,
+    capacity: usize,
+}
+
+impl Default for TransactionPool {
+    fn default() -> Self {
+        TransactionPool::new(constants::MAX_NUM_OF_PENDING_TRANSACTIONS)
+    }
+}
+
+impl TransactionPool {
+    pub fn new(capacity: usize) -> Self {
+        TransactionPool {
+            queue: VecDeque::new(),
+            hashes: HashSet::new(),
+            capacity,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn contains(&self, hash: &H256) -> bool {
+        self.hashes.contains(hash)
+    }
+
+    /// Add a transaction. Returns false if it is already in the pool, expired at `now`, or the pool is full.
+    pub fn push(&mut self, txn: IndexedTransaction, now: i64) -> bool {
+        if txn.raw.raw_data.is_none() || txn.expiration() <= now {
+            return false;
+        }
+        if self.queue.len() >= self.capacity || self.hashes.contains(&txn.hash) {
+            return false;
+        }
+        self.hashes.insert(txn.hash);
+        self.queue.push_back(txn);
+        true
+    }
+
+    /// Take the oldest transaction.
+    pub fn pop_front(&mut self) -> Option<IndexedTransaction> {
+        let txn = self.queue.pop_front()?;
+        self.hashes.remove(&txn.hash);
+        Some(txn)
+    }
+
+    /// Put back a transaction taken by `pop_front`, i.e. one not fitting into the current block.
+    pub fn push_front(&mut self, txn: IndexedTransaction) {
+        if self.hashes.insert(txn.hash) {
+            self.queue.push_front(txn);
+        }
+    }
+
+    /// Drop transactions expired at `now`, returns the number of dropped transactions.
+    pub fn remove_expired(&mut self, now: i64) -> usize {
+        let hashes = &mut self.hashes;
+        let before = self.queue.len();
+        self.queue.retain(|txn| {
+            if txn.expiration() <= now {
+                hashes.remove(&txn.hash);
+                false
+            } else {
+                true
+            }
+        });
+        before - self.queue.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proto::chain::{transaction::Raw as TransactionRaw, Transaction};
+
+    use super::*;
+
+    fn new_transaction(ref_block_num: i64, expiration: i64) -> IndexedTransaction {
+        IndexedTransaction::from_raw(Transaction {
+            raw_data: Some(TransactionRaw {
+                ref_block_num,
+                expiration,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_transaction_pool() {
+        let mut pool = TransactionPool::new(2);
+        let txn = new_transaction(1, 2_000);
+
+        assert!(pool.push(txn.clone(), 1_000));
+        assert!(!pool.push(txn.clone(), 1_000), "duplicated");
+        assert!(!pool.push(new_transaction(2, 1_000), 1_000), "expired");
+        assert!(pool.push(new_transaction(3, 3_000), 1_000));
+        assert!(!pool.push(new_transaction(4, 3_000), 1_000), "full");
+        assert_eq!(pool.len(), 2);
+
+        let first = pool.pop_front().unwrap();
+        assert_eq!(first.hash, txn.hash);
+        assert!(!pool.contains(&txn.hash));
+        pool.push_front(first);
+        assert!(pool.contains(&txn.hash));
+
+        assert_eq!(pool.remove_expired(2_000), 1);
+        assert!(!pool.contains(&txn.hash));
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.pop_front().unwrap().raw.raw_data.unwrap().ref_block_num, 3);
+        assert!(pool.is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+//! Stateless block verification, ahead of execution.
+//!
+//! Recovering witness and transaction signatures is the most expensive part of block verification. The
+//! pipeline verifies upcoming blocks on a worker pool while the manager executes the current one.
+
+use std::sync::mpsc;
+use std::thread;
+
+use ::keys::Address;
+use chain::{IndexedBlock, IndexedTransaction};
+use log::debug;
+use primitive_types::H256;
+use rayon::prelude::*;
+
+/// Recovered owner addresses of transactions in a block.
+pub(crate) type RecoveredOwners = Vec<Result<Vec<Address>, ::keys::Error>>;
+
+/// Number of recent block hashes kept for TaPoS check.
+const NUM_OF_REF_BLOCK_HASHES: usize = 65536;
+
+/// Recent block hashes for TaPoS check, indexed by the lower 2 bytes of block number. 2MB.
+#[derive(Clone, Default)]
+pub struct RefBlockHashes(Vec<H256>);
+
+impl RefBlockHashes {
+    pub fn new(hashes: Vec<H256>) -> Self {
+        RefBlockHashes(hashes)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&H256> {
+        self.0.get(index)
+    }
+
+    /// Add hash of a new block.
+    pub fn push(&mut self, new_hash: H256) {
+        if self.0.len() < NUM_OF_REF_BLOCK_HASHES {
+            self.0.push(new_hash);
+        } else {
+            let ref_block_bytes = {
+                let mut raw = [0u8; 2];
+                raw.copy_from_slice(&new_hash.as_bytes()[6..8]);
+                raw
+            };
+            let ref_slot_index = u16::from_be_bytes(ref_block_bytes) as usize;
+            self.0[ref_slot_index] = new_hash;
+        }
+    }
+
+    /// Remove hashes of blocks above the block number, when rolling back.
+    ///
+    /// Once all slots are filled, replaced hashes are overwritten when the longer chain is applied.
+    pub fn rollback_to(&mut self, block_number: i64) {
+        if self.0.len() < NUM_OF_REF_BLOCK_HASHES {
+            self.0.truncate(block_number as usize + 1);
+        }
+    }
+
+    /// TaPoS check, the transaction must refer to a recent block.
+    pub fn verify(&self, txn: &IndexedTransaction) -> bool {
+        let ref_block_hash = &txn.raw.raw_data.as_ref().unwrap().ref_block_hash;
+        let ref_block_bytes = &txn.raw.raw_data.as_ref().unwrap().ref_block_bytes;
+        if ref_block_bytes.len() != 2 {
+            return false;
+        }
+        let ref_slot_index = u16::from_be_bytes([ref_block_bytes[0], ref_block_bytes[1]]) as usize;
+
+        self.0
+            .get(ref_slot_index)
+            .map(|block_hash| &block_hash.as_ref()[8..16] == &ref_block_hash[..])
+            .unwrap_or(false)
+    }
+}
+
+/// A block with its stateless verification results.
+pub struct VerifiedBlock {
+    pub block: IndexedBlock,
+    pub(crate) witness: Result<Address, ::keys::Error>,
+    pub(crate) merkle_root_verified: bool,
+    /// `None` for blocks off the verified chain, i.e. fork blocks. Their TaPoS is checked on execution.
+    pub(crate) tapos_verified: Option<bool>,
+    pub(crate) transaction_owners: RecoveredOwners,
+}
+
+impl VerifiedBlock {
+    /// Recover signatures and check merkle root. TaPoS is checked later in block order.
+    fn new(block: IndexedBlock) -> Self {
+        let witness = block.recover_witness();
+        let merkle_root_verified = block.verify_merkle_root_hash();
+        let transaction_owners = block.transactions.iter().map(|txn| txn.recover_owner()).collect();
+        VerifiedBlock {
+            block,
+            witness,
+            merkle_root_verified,
+            tapos_verified: None,
+            transaction_owners,
+        }
+    }
+}
+
+/// Verify blocks on a worker pool, `batch_size` blocks at a time.
+///
+/// Verified blocks are yielded in order. TaPoS is checked against the chain formed by the blocks, starting
+/// from `ref_block_hashes`, blocks not linked to it are left to execution. The pipeline stops when the
+/// receiver is dropped.
+pub fn verify_blocks<I>(
+    blocks: I,
+    mut ref_block_hashes: RefBlockHashes,
+    batch_size: usize,
+) -> mpsc::Receiver<VerifiedBlock>
+where
+    I: Iterator<Item = IndexedBlock> + Send + 'static,
+{
+    // Keep at most 2 batches ahead of execution.
+    let (tx, rx) = mpsc::sync_channel(batch_size.max(1) * 2);
+
+    thread::spawn(move || {
+        let mut blocks = blocks.fuse();
+        // Latest block of the verified chain.
+        let mut tip: Option<H256> = None;
+        loop {
+            let batch = blocks.by_ref().take(batch_size.max(1)).collect::<Vec<_>>();
+            if batch.is_empty() {
+                break;
+            }
+            let verified = batch.into_par_iter().map(VerifiedBlock::new).collect::<Vec<_>>();
+            for mut verified in verified {
+                let linked = tip.map_or(true, |hash| verified.block.parent_hash() == hash.as_bytes());
+                if linked {
+                    verified.tapos_verified = Some(
+                        verified
+                            .block
+                            .transactions
+                            .iter()
+                            .all(|txn| ref_block_hashes.verify(txn)),
+                    );
+                    ref_block_hashes.push(*verified.block.hash());
+                    tip = Some(*verified.block.hash());
+                }
+                if tx.send(verified).is_err() {
+                    debug!("block verifier closed");
+                    return;
+                }
+            }
+        }
+    });
+
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::chain::{transaction::Raw as TransactionRaw, Transaction};
+
+    fn txn_refer_to(number: u16, hash: &H256) -> IndexedTransaction {
+        let raw = TransactionRaw {
+            ref_block_bytes: number.to_be_bytes().to_vec(),
+            ref_block_hash: hash.as_bytes()[8..16].to_vec(),
+            ..Default::default()
+        };
+        IndexedTransaction::from_raw(Transaction {
+            raw_data: Some(raw),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_ref_block_hashes() {
+        let hashes = (0..10u8).map(H256::repeat_byte).collect::<Vec<_>>();
+        let mut ref_hashes = RefBlockHashes::new(hashes[..5].to_vec());
+
+        assert!(ref_hashes.verify(&txn_refer_to(4, &hashes[4])));
+        assert!(!ref_hashes.verify(&txn_refer_to(4, &hashes[3])));
+        assert!(!ref_hashes.verify(&txn_refer_to(5, &hashes[5])));
+
+        ref_hashes.push(hashes[5]);
+        assert!(ref_hashes.verify(&txn_refer_to(5, &hashes[5])));
+
+        ref_hashes.rollback_to(3);
+        assert_eq!(ref_hashes.len(), 4);
+        assert!(!ref_hashes.verify(&txn_refer_to(4, &hashes[4])));
+    }
+}
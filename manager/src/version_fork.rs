@@ -27,12 +27,14 @@ impl ForkController<'_> {
 
     pub fn pass_version(&self, version: BlockVersion) -> Result<bool, String> {
         match version.fork_policy() {
-            ForkPolicy::AtBlock { block_number } => Ok(self.manager.latest_block_number() >= block_number),
+            ForkPolicy::AtBlock { block_number } => Ok(self.manager.latest_block_number()? >= block_number),
             ForkPolicy::Old => {
-                let active_wit_addrs = self.manager.get_active_witnesses();
+                let active_wit_addrs = self.manager.get_active_witnesses()?;
                 let all_passed = active_wit_addrs
                     .into_iter()
                     .map(|addr| self.manager.state_db.must_get(&keys::Witness(addr)))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into_iter()
                     .all(|wit| wit.latest_block_version >= version as _);
                 Ok(all_passed)
             }
@@ -43,18 +45,20 @@ impl ForkController<'_> {
                 let maintenance_interval = self
                     .manager
                     .state_db
-                    .must_get(&keys::ChainParameter::MaintenanceInterval);
+                    .must_get(&keys::ChainParameter::MaintenanceInterval)?;
                 let hard_fork_ts = ((timestamp - 1) / maintenance_interval + 1) * maintenance_interval;
 
-                if self.manager.latest_block_number() < hard_fork_ts {
+                if self.manager.latest_block_number()? < hard_fork_ts {
                     return Ok(false);
                 }
 
-                let active_wit_addrs = self.manager.get_active_witnesses();
+                let active_wit_addrs = self.manager.get_active_witnesses()?;
                 let min_num_required = (active_wit_addrs.len() * min_upgrade_percent as usize) as f64 / 100.0;
                 let num_passed = active_wit_addrs
                     .into_iter()
                     .map(|addr| self.manager.state_db.must_get(&keys::Witness(addr)))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into_iter()
                     .map(|wit| wit.latest_block_version >= version as _)
                     .count();
                 Ok(num_passed as f64 >= min_num_required)
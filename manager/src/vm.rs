@@ -9,7 +9,7 @@ use lazy_static::lazy_static;
 use log::debug;
 use primitive_types::{H160, H256, U256};
 use proto::state::{Account, AccountType, SmartContract, TransactionLog};
-use state::db::StateDB;
+use state::db::{KeyValueDB, StateDB};
 use state::keys;
 use tvm::backend::{Apply, ApplyBackend, Backend, Basic, Log};
 
@@ -43,14 +43,41 @@ impl<'m, 'c, 'ctx> StateBackend<'m, 'c, 'ctx> {
     }
 
     /// Get the underlying `StateDB` storing the state.
-    fn state(&self) -> &StateDB {
+    fn state(&self) -> &StateDB<dyn KeyValueDB> {
         &self.manager.state_db
     }
 
     /// Get the underlying `StateDB` storing the state, mutable.
-    fn state_mut(&mut self) -> &mut StateDB {
+    fn state_mut(&mut self) -> &mut StateDB<dyn KeyValueDB> {
         &mut self.manager.state_db
     }
+
+    /// Remove an account, along with its contract, code and storage.
+    fn delete_account(&mut self, addr: Address) {
+        self.state_mut().delete_key(&keys::Account(addr)).unwrap();
+        self.state_mut().delete_key(&keys::Contract(addr)).unwrap();
+        self.state_mut().delete_key(&keys::ContractCode(addr)).unwrap();
+        self.wipe_storage(addr);
+    }
+
+    /// Remove all storage slots of a contract, returns number of slots removed.
+    fn wipe_storage(&mut self, addr: Address) -> usize {
+        let mut to_be_deleted: Vec<keys::ContractStorage> = Vec::new();
+        {
+            let to_be_deleted = &mut to_be_deleted;
+            self.state()
+                .for_each_by_prefix(addr.as_bytes(), move |key: &keys::ContractStorage, value| {
+                    debug!("DELETE {} ({} => {:?})", key.0, key.1, value);
+                    to_be_deleted.push(key.clone());
+                })
+                .unwrap();
+        }
+        let num_wiped = to_be_deleted.len();
+        for key in to_be_deleted {
+            self.state_mut().delete_key(&key).unwrap()
+        }
+        num_wiped
+    }
 }
 
 #[allow(unused_variables)]
@@ -92,8 +119,10 @@ impl Backend for StateBackend<'_, '_, '_> {
         0.into()
     }
 
+    // CHAINID: hash of the genesis block.
+    // NOTE: Only the last 4 bytes are used after AllowTvmCompatibleEvm, which is not supported yet.
     fn chain_id(&self) -> U256 {
-        unimplemented!("hash of block #0")
+        U256::from_big_endian(self.manager.genesis_block_hash.as_bytes())
     }
 
     fn exists(&self, address: H160) -> bool {
@@ -119,8 +148,20 @@ impl Backend for StateBackend<'_, '_, '_> {
             .unwrap_or_default()
     }
 
+    // EXTCODEHASH: zero for non-existent accounts, hash of empty code for normal accounts.
+    // NOTE: java-tron also saves the hash when it is missing in `SmartContract`, which can not be done here.
     fn code_hash(&self, address: H160) -> H256 {
-        unimplemented!()
+        let addr = Address::from_tvm_bytes(address.as_bytes());
+        if self.state().get(&keys::Account(addr)).unwrap().is_none() {
+            return H256::zero();
+        }
+        match self.state().get(&keys::Contract(addr)).unwrap() {
+            Some(cntr) if cntr.code_hash.len() == 32 && cntr.code_hash.iter().any(|&b| b != 0) => {
+                H256::from_slice(&cntr.code_hash)
+            }
+            Some(_) => keccak256(&self.code(address)),
+            None => keccak256(&[]),
+        }
     }
 
     fn code_size(&self, address: H160) -> usize {
@@ -200,24 +241,28 @@ impl ApplyBackend for StateBackend<'_, '_, '_> {
                     reset_storage,
                 } => {
                     let addr = Address::from_tvm_bytes(address.as_bytes());
+                    let maybe_account = self.state().get(&keys::Account(addr)).expect("db query");
+
+                    // EIP-161: touched empty accounts are never created.
+                    // NOTE: Existing accounts are never removed, as in java-tron. An account without balance
+                    // can still hold frozen balance, votes or permissions.
                     if delete_empty &&
+                        maybe_account.is_none() &&
                         basic.balance == U256::zero() &&
                         basic.nonce == U256::zero() &&
-                        code.is_none() &&
-                        basic.token_balance.is_empty()
+                        basic.token_balance.iter().all(|(_, value)| *value == U256::zero()) &&
+                        code.is_none()
                     {
-                        unimplemented!("TODO: delete empty");
+                        continue;
                     }
 
-                    let (mut account, created) = self
-                        .state()
-                        .get(&keys::Account(addr))
-                        .expect("db query")
-                        .map(|acct| (acct, false))
-                        .unwrap_or_else(|| {
-                            log::debug!("create new account in TVM: {} {:?}", addr, address);
-                            (Account::new(self.manager.latest_block_timestamp()), true)
-                        });
+                    let (mut account, created) = maybe_account.map(|acct| (acct, false)).unwrap_or_else(|| {
+                        log::debug!("create new account in TVM: {} {:?}", addr, address);
+                        (
+                            Account::new(self.manager.latest_block_timestamp().expect("db query")),
+                            true,
+                        )
+                    });
 
                     account.balance = basic.balance.as_u64() as i64;
                     for (token_id, token_value) in basic.token_balance {
@@ -243,8 +288,11 @@ impl ApplyBackend for StateBackend<'_, '_, '_> {
                         account.r#type = AccountType::Contract as i32;
                     }
 
+                    // CREATE or CREATE2 over an existing account, i.e. a pre-funded address or a redeploy.
+                    // Old storage must not leak into the new contract.
                     if !created && reset_storage {
-                        log::warn!("TODO: reset_storage {}", addr);
+                        let num_wiped = self.wipe_storage(addr);
+                        debug!("reset storage of {}, {} slots wiped", addr, num_wiped);
                     }
                     self.state_mut().put_key(keys::Account(addr), account).unwrap();
 
@@ -264,24 +312,8 @@ impl ApplyBackend for StateBackend<'_, '_, '_> {
                 // Suicided
                 Apply::Delete { address } => {
                     let addr = Address::from_tvm_bytes(address.as_bytes());
-                    self.state_mut().delete_key(&keys::Account(addr)).unwrap();
-                    self.state_mut().delete_key(&keys::Contract(addr)).unwrap();
-                    self.state_mut().delete_key(&keys::ContractCode(addr)).unwrap();
                     debug!("suicide and delete account: {}", addr);
-                    let mut to_be_deleted: Vec<keys::ContractStorage> = Vec::new();
-                    {
-                        let to_be_deleted = &mut to_be_deleted;
-                        self.state()
-                            .for_each_by_prefix(addr.as_bytes(), move |key: &keys::ContractStorage, value| {
-                                debug!("suicide DELETE {} ({} => {:?})", key.0, key.1, value);
-                                to_be_deleted.push(key.clone());
-                            });
-                    }
-                    if !to_be_deleted.is_empty() {
-                        for key in to_be_deleted {
-                            self.state_mut().delete_key(&key).unwrap()
-                        }
-                    }
+                    self.delete_account(addr);
                 }
             }
         }
@@ -296,3 +328,257 @@ impl ApplyBackend for StateBackend<'_, '_, '_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use chain::IndexedBlockHeader;
+    use config::{Config, GenesisConfig};
+    use proto::common::Vote;
+    use proto::state::Votes;
+    use tvm::TvmUpgrade;
+
+    use super::*;
+
+    // Runtime: without calldata `selfdestruct(msg.sender)`, otherwise `sstore(1, 2)`.
+    const CHILD_CODE: &str = "3660065733ff5b600260015500";
+    // Constructor: `sstore(0, 1)`, then returns `CHILD_CODE`.
+    const CHILD_INIT_CODE: &str = "6001600055600d6011600039600d6000f3";
+    // Runtime: `create2(0, CHILD_INIT_CODE, salt=0)`, returns the created address.
+    const FACTORY_CODE: &str = "601e60186000396000601e60006000f560005260206000f3";
+
+    fn new_test_manager() -> (Manager, IndexedBlockHeader) {
+        let config = Config::load_from_str(include_str!("../../etc/conf.toml")).unwrap();
+        let genesis_config = GenesisConfig::load_from_str(include_str!("../../etc/genesis.json")).unwrap();
+        let manager = Manager::with_state_db(StateDB::new_in_memory(), &config, &genesis_config);
+        let header = genesis_config.to_indexed_block().unwrap().header;
+        (manager, header)
+    }
+
+    /// Run `code` at `address` and apply the result, returns the return value.
+    fn execute(
+        manager: &mut Manager,
+        header: &IndexedBlockHeader,
+        address: H160,
+        code: Vec<u8>,
+        data: Vec<u8>,
+    ) -> Vec<u8> {
+        let owner_address: Address = "TN21Wx2yoNYiZ7znuQonmZMJnH5Vdfxu78".parse().unwrap();
+        let upgrade = TvmUpgrade {
+            asset_transfer: true,
+            constantinople: true,
+            istanbul: true,
+            ..Default::default()
+        };
+        let precompile = upgrade.precompile();
+        let config = upgrade.to_tvm_config();
+
+        let mut ctx = TransactionContext::dummy(header);
+        let mut backend = StateBackend::new(owner_address, manager, &mut ctx);
+        let mut executor = tvm::StackExecutor::new_with_precompile(&backend, 1_000_000, &config, precompile);
+        let vm_ctx = tvm::Context {
+            address,
+            caller: H160::from_slice(owner_address.as_tvm_bytes()),
+            call_value: 0.into(),
+            call_token_id: 0.into(),
+            call_token_value: 0.into(),
+        };
+        let mut rt = tvm::Runtime::new(Rc::new(code), Rc::new(data), vm_ctx, &config);
+        let exit_reason = executor.execute(&mut rt);
+        assert!(matches!(exit_reason, tvm::ExitReason::Succeed(_)), "{:?}", exit_reason);
+        let ret_val = rt.machine().return_value();
+
+        let (applies, logs) = executor.deconstruct();
+        backend.apply(applies, logs, false);
+        ret_val
+    }
+
+    fn slot(manager: &Manager, addr: Address, index: u64) -> Option<H256> {
+        manager
+            .state()
+            .get(&keys::ContractStorage(addr, H256::from_low_u64_be(index)))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_create2_destroy_and_redeploy() {
+        let (mut manager, header) = new_test_manager();
+        let factory = H160::from_low_u64_be(0xfac7);
+        let factory_code = hex::decode([FACTORY_CODE, CHILD_INIT_CODE, CHILD_CODE].concat()).unwrap();
+
+        // deploy
+        let ret = execute(&mut manager, &header, factory, factory_code.clone(), vec![]);
+        let child = H160::from_slice(&ret[12..]);
+        assert_ne!(child, H160::zero());
+        let child_addr = Address::from_tvm_bytes(child.as_bytes());
+        let child_code = manager.state().get(&keys::ContractCode(child_addr)).unwrap().unwrap();
+        assert_eq!(child_code, hex::decode(CHILD_CODE).unwrap());
+        assert_eq!(slot(&manager, child_addr, 0), Some(H256::from_low_u64_be(1)));
+
+        execute(&mut manager, &header, child, child_code.clone(), vec![0x01]);
+        assert_eq!(slot(&manager, child_addr, 1), Some(H256::from_low_u64_be(2)));
+
+        // destroy
+        execute(&mut manager, &header, child, child_code.clone(), vec![]);
+        assert!(manager.state().get(&keys::Account(child_addr)).unwrap().is_none());
+        assert!(manager.state().get(&keys::ContractCode(child_addr)).unwrap().is_none());
+        assert_eq!(slot(&manager, child_addr, 0), None);
+        assert_eq!(slot(&manager, child_addr, 1), None);
+
+        // redeploy
+        let ret2 = execute(&mut manager, &header, factory, factory_code.clone(), vec![]);
+        assert_eq!(ret, ret2);
+        assert_eq!(slot(&manager, child_addr, 0), Some(H256::from_low_u64_be(1)));
+        assert_eq!(slot(&manager, child_addr, 1), None);
+
+        // destroy, then redeploy over a pre-funded address with stale storage
+        execute(&mut manager, &header, child, child_code.clone(), vec![]);
+        let mut acct = Account::new(0);
+        acct.balance = 1_000_000;
+        manager.state_db.put_key(keys::Account(child_addr), acct).unwrap();
+        manager
+            .state_db
+            .put_key(
+                keys::ContractStorage(child_addr, H256::from_low_u64_be(1)),
+                H256::from_low_u64_be(2),
+            )
+            .unwrap();
+
+        let ret3 = execute(&mut manager, &header, factory, factory_code, vec![]);
+        assert_eq!(ret, ret3);
+        let acct = manager.state().get(&keys::Account(child_addr)).unwrap().unwrap();
+        assert_eq!(acct.balance, 1_000_000);
+        assert_eq!(acct.r#type, AccountType::Contract as i32);
+        assert_eq!(slot(&manager, child_addr, 0), Some(H256::from_low_u64_be(1)));
+        assert_eq!(slot(&manager, child_addr, 1), None);
+    }
+
+    #[test]
+    fn test_chain_id() {
+        let (mut manager, header) = new_test_manager();
+        let genesis_block_hash = manager.latest_block_hash().unwrap();
+
+        // chainid, mstore(0), return(0, 32)
+        let code = hex::decode("4660005260206000f3").unwrap();
+        let ret = execute(&mut manager, &header, H160::from_low_u64_be(0xc0de), code, vec![]);
+        assert_eq!(ret, genesis_block_hash.as_bytes());
+    }
+
+    #[test]
+    fn test_code_hash() {
+        let (mut manager, header) = new_test_manager();
+        let cntr_code = hex::decode(CHILD_CODE).unwrap();
+
+        // contract without code_hash saved
+        let cntr = H160::from_low_u64_be(0xc1);
+        let cntr_addr = Address::from_tvm_bytes(cntr.as_bytes());
+        manager
+            .state_db
+            .put_key(keys::Account(cntr_addr), Account::new(0))
+            .unwrap();
+        manager
+            .state_db
+            .put_key(keys::Contract(cntr_addr), SmartContract::default())
+            .unwrap();
+        manager
+            .state_db
+            .put_key(keys::ContractCode(cntr_addr), cntr_code.clone())
+            .unwrap();
+        // contract with code_hash saved
+        let hashed_cntr = H160::from_low_u64_be(0xc2);
+        let hashed_cntr_addr = Address::from_tvm_bytes(hashed_cntr.as_bytes());
+        let mut smart_contract = SmartContract::default();
+        smart_contract.code_hash = keccak256(&cntr_code).as_bytes().to_vec();
+        manager
+            .state_db
+            .put_key(keys::Account(hashed_cntr_addr), Account::new(0))
+            .unwrap();
+        manager
+            .state_db
+            .put_key(keys::Contract(hashed_cntr_addr), smart_contract)
+            .unwrap();
+        manager
+            .state_db
+            .put_key(keys::ContractCode(hashed_cntr_addr), cntr_code.clone())
+            .unwrap();
+        // normal account
+        let normal = H160::from_low_u64_be(0xa1);
+        manager
+            .state_db
+            .put_key(
+                keys::Account(Address::from_tvm_bytes(normal.as_bytes())),
+                Account::new(0),
+            )
+            .unwrap();
+        let non_existent = H160::from_low_u64_be(0xa2);
+
+        for &(address, expected) in &[
+            (cntr, keccak256(&cntr_code)),
+            (hashed_cntr, keccak256(&cntr_code)),
+            (normal, keccak256(&[])),
+            (non_existent, H256::zero()),
+        ] {
+            // extcodehash(address), mstore(0), return(0, 32)
+            let code = [
+                &[0x73][..],
+                address.as_bytes(),
+                &hex::decode("3f60005260206000f3").unwrap(),
+            ]
+            .concat();
+            let ret = execute(&mut manager, &header, H160::from_low_u64_be(0xc0de), code, vec![]);
+            assert_eq!(ret, expected.as_bytes(), "extcodehash of {:?}", address);
+        }
+
+        // the saved code_hash is preferred
+        let mut smart_contract = SmartContract::default();
+        smart_contract.code_hash = H256::repeat_byte(0xcc).as_bytes().to_vec();
+        manager
+            .state_db
+            .put_key(keys::Contract(hashed_cntr_addr), smart_contract)
+            .unwrap();
+        let mut ctx = TransactionContext::dummy(&header);
+        let owner_address: Address = "TN21Wx2yoNYiZ7znuQonmZMJnH5Vdfxu78".parse().unwrap();
+        let backend = StateBackend::new(owner_address, &mut manager, &mut ctx);
+        assert_eq!(backend.code_hash(hashed_cntr), H256::repeat_byte(0xcc));
+        assert_eq!(backend.code_hash(cntr), keccak256(&cntr_code));
+    }
+
+    #[test]
+    fn test_delete_empty_account() {
+        let (mut manager, header) = new_test_manager();
+        let owner_address: Address = "TN21Wx2yoNYiZ7znuQonmZMJnH5Vdfxu78".parse().unwrap();
+        let empty = H160::from_low_u64_be(0xe);
+        let empty_addr = Address::from_tvm_bytes(empty.as_bytes());
+        let mut acct = Account::new(0);
+        acct.frozen_amount_for_bandwidth = 1_000_000;
+        manager.state_db.put_key(keys::Account(empty_addr), acct).unwrap();
+        let votes = Votes {
+            votes: vec![Vote {
+                vote_address: owner_address.as_bytes().to_vec(),
+                vote_count: 1,
+            }],
+            epoch: 0,
+        };
+        manager.state_db.put_key(keys::Votes(empty_addr), votes).unwrap();
+        let untouched = H160::from_low_u64_be(0xf);
+
+        let mut ctx = TransactionContext::dummy(&header);
+        let mut backend = StateBackend::new(owner_address, &mut manager, &mut ctx);
+        let applies = vec![empty, untouched].into_iter().map(|address| Apply::Modify {
+            address,
+            basic: Basic::default(),
+            code: None,
+            storage: Vec::<(H256, H256)>::new(),
+            reset_storage: false,
+        });
+        backend.apply(applies, Vec::<Log>::new(), true);
+
+        // An account with zero balance but frozen balance and votes must survive.
+        let acct = manager.state().get(&keys::Account(empty_addr)).unwrap().unwrap();
+        assert_eq!(acct.frozen_amount_for_bandwidth, 1_000_000);
+        assert!(manager.state().get(&keys::Votes(empty_addr)).unwrap().is_some());
+        let untouched_addr = Address::from_tvm_bytes(untouched.as_bytes());
+        assert!(manager.state().get(&keys::Account(untouched_addr)).unwrap().is_none());
+    }
+}
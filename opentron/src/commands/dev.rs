@@ -1,20 +1,49 @@
+use std::iter;
+use std::sync::Arc;
+
 use chrono::Utc;
 use log::info;
 
 use context::AppContext;
+use manager::verifier;
+
+/// Number of blocks verified ahead of execution, at a time.
+const VERIFYING_BATCH_SIZE: usize = 64;
 
 pub async fn main(ctx: AppContext) -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = Arc::new(ctx);
     let mut manager = ctx.manager.write().unwrap();
 
     let mut start_time = Utc::now().timestamp_millis();
     let mut n_blocks = 0;
 
-    let start_block = manager.latest_block_number() as u64 + 1;
-
-    for i in start_block.. {
-        let blk = ctx.chain_db.get_block_by_number(i)?;
-
-        manager.push_block(&blk)?;
+    let start_block = manager.latest_block_number()? as u64 + 1;
+
+    // Signatures, merkle root and TaPoS are verified on the worker pool, while blocks are being executed.
+    //
+    // All blocks of a height are replayed, fork blocks are saved in the fork db and switched to once they
+    // form the longer chain. Blocks from the network are saved to chain-db by the channel service, and are
+    // applied by this command.
+    let blocks = {
+        let ctx = ctx.clone();
+        let mut num = start_block;
+        iter::from_fn(move || {
+            let headers = ctx.chain_db.get_block_headers_by_number(num);
+            num += 1;
+            if headers.is_empty() {
+                return None;
+            }
+            headers
+                .into_iter()
+                .map(|header| ctx.chain_db.get_block_from_header(header).ok())
+                .collect::<Option<Vec<_>>>()
+        })
+        .flatten()
+    };
+    let verified_blocks = verifier::verify_blocks(blocks, manager.ref_block_hashes().clone(), VERIFYING_BATCH_SIZE);
+
+    for verified in verified_blocks {
+        manager.push_verified_block(verified)?;
 
         n_blocks += 1;
 
@@ -24,6 +53,11 @@ pub async fn main(ctx: AppContext) -> Result<(), Box<dyn std::error::Error>> {
             start_time = Utc::now().timestamp_millis();
         }
     }
+    info!(
+        "no more blocks in chain-db, {} blocks applied, {} transaction result mismatches",
+        n_blocks,
+        manager.num_result_mismatches()
+    );
 
     Ok(())
 }
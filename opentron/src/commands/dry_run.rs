@@ -0,0 +1,95 @@
+use std::io::{self, Write};
+
+use clap::ArgMatches;
+use log::info;
+use serde_json::json;
+
+use context::AppContext;
+
+fn json_bytes(val: &[u8]) -> String {
+    format!("0x{}", hex::encode(val))
+}
+
+/// Dry run a block from chain-db on top of the state-db, dump receipts and the state diff as JSON, to stdout.
+pub async fn main(ctx: AppContext, matches: &ArgMatches<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut manager = ctx.manager.write().unwrap();
+    let num = match matches.value_of("NUM") {
+        Some(num) => num.parse()?,
+        None => manager.latest_block_number()? + 1,
+    };
+    let block = ctx.chain_db.get_block_by_number(num as u64)?;
+    let result = manager.dry_run_block(&block)?;
+    let diff = &result.diff;
+
+    let receipts: Vec<_> = result
+        .receipts
+        .iter()
+        .map(|(hash, receipt)| {
+            let resource = receipt.resource_receipt.clone().unwrap_or_default();
+            json!({
+                "hash": format!("{:?}", hash),
+                "success": receipt.success,
+                "fee": receipt.fee,
+                "bandwidth": resource.bandwidth_usage,
+                "energy": resource.energy,
+                "vmStatus": receipt.vm_status,
+            })
+        })
+        .collect();
+    let accounts: Vec<_> = diff
+        .accounts
+        .iter()
+        .map(|change| {
+            json!({
+                "address": change.address.to_string(),
+                "balanceBefore": change.before.as_ref().map(|acct| acct.balance),
+                "balanceAfter": change.after.as_ref().map(|acct| acct.balance),
+            })
+        })
+        .collect();
+    let storage: Vec<_> = diff
+        .storage
+        .iter()
+        .map(|change| {
+            json!({
+                "address": change.address.to_string(),
+                "slot": json_bytes(change.slot.as_bytes()),
+                "before": change.before.as_ref().map(|val| json_bytes(val.as_bytes())),
+                "after": change.after.as_ref().map(|val| json_bytes(val.as_bytes())),
+            })
+        })
+        .collect();
+    let properties: serde_json::Map<_, _> = diff
+        .properties
+        .iter()
+        .map(|change| {
+            (
+                change.name.clone(),
+                json!({ "before": change.before, "after": change.after }),
+            )
+        })
+        .collect();
+
+    let output = json!({
+        "block": block.number(),
+        "hash": format!("{:?}", block.hash()),
+        "receipts": receipts,
+        "accounts": accounts,
+        "storage": storage,
+        "properties": properties,
+    });
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    serde_json::to_writer_pretty(&mut out, &output)?;
+    writeln!(out)?;
+
+    info!(
+        "dry run block #{}, {} receipts, {} accounts, {} storage slots, {} properties changed",
+        block.number(),
+        result.receipts.len(),
+        diff.accounts.len(),
+        diff.storage.len(),
+        diff.properties.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::io::{self, BufWriter, Write};
+
+use clap::ArgMatches;
+use keys::Address;
+use log::info;
+use state::keys as state_keys;
+
+use context::AppContext;
+
+/// Dump all storage slots of a contract as JSON, to stdout.
+pub async fn main(ctx: AppContext, matches: &ArgMatches<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    let addr: Address = matches.value_of("ADDRESS").expect("required in cli.yml; qed").parse()?;
+    let manager = ctx.manager.read().unwrap();
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    writeln!(out, "{{")?;
+    writeln!(out, "  \"address\": \"{}\",", addr)?;
+    write!(out, "  \"storage\": {{")?;
+
+    let mut num_slots = 0;
+    let mut ret = Ok(());
+    manager
+        .state()
+        .for_each_by_prefix(addr.as_bytes(), |key: &state_keys::ContractStorage, value| {
+            if ret.is_err() {
+                return;
+            }
+            let sep = if num_slots == 0 { "" } else { "," };
+            ret = write!(
+                out,
+                "{}\n    \"0x{}\": \"0x{}\"",
+                sep,
+                hex::encode(key.1.as_bytes()),
+                hex::encode(value.as_bytes())
+            );
+            num_slots += 1;
+        })?;
+    ret?;
+
+    writeln!(out, "\n  }}")?;
+    writeln!(out, "}}")?;
+    out.flush()?;
+
+    info!("dumped {} storage slots of {}", num_slots, addr);
+    Ok(())
+}
@@ -1,3 +1,6 @@
 pub mod check;
 pub mod dev;
+pub mod dry_run;
+pub mod dump;
 pub mod fix;
+pub mod reward_audit;
@@ -0,0 +1,70 @@
+use std::fs;
+use std::io::{self, Write};
+
+use clap::ArgMatches;
+use keys::Address;
+use log::{info, warn};
+use manager::governance::audit::{compare_reports, EpochRewardReport, RewardAudit};
+use state::keys as state_keys;
+
+use context::AppContext;
+
+/// Dump per-epoch reward reports as JSON, to stdout. Or compare them against a JSON fixture of expected reports.
+pub async fn main(ctx: AppContext, matches: &ArgMatches<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    let manager = ctx.manager.read().unwrap();
+    let curr_epoch = manager.state().must_get(&state_keys::DynamicProperty::CurrentEpoch)?;
+
+    let voter: Option<Address> = matches.value_of("voter").map(|addr| addr.parse()).transpose()?;
+    let expected: Option<Vec<EpochRewardReport>> = match matches.value_of("expected") {
+        Some(path) => Some(serde_json::from_str(&fs::read_to_string(path)?)?),
+        None => None,
+    };
+
+    // Defaults to epochs in the fixture, or the current epoch.
+    let (from, to) = match (matches.value_of("from"), matches.value_of("to"), expected.as_ref()) {
+        (Some(from), to, _) => (
+            from.parse::<i64>()?,
+            to.map(|to| to.parse::<i64>()).transpose()?.unwrap_or(curr_epoch),
+        ),
+        (None, _, Some(reports)) if !reports.is_empty() => (
+            reports.iter().map(|report| report.epoch).min().unwrap(),
+            reports.iter().map(|report| report.epoch).max().unwrap(),
+        ),
+        _ => (curr_epoch, curr_epoch),
+    };
+
+    let audit = RewardAudit::new(&manager);
+    let reports = (from..=to.min(curr_epoch))
+        .map(|epoch| audit.epoch_report(epoch, voter.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match expected {
+        Some(expected) => {
+            let mismatches = compare_reports(&reports, &expected);
+            for mismatch in &mismatches {
+                warn!(
+                    "epoch #{} {} {}: expected={:?} actual={:?}",
+                    mismatch.epoch, mismatch.subject, mismatch.field, mismatch.expected, mismatch.actual
+                );
+            }
+            match mismatches.first() {
+                Some(first) => {
+                    return Err(format!(
+                        "{} reward mismatches, first found in epoch #{}",
+                        mismatches.len(),
+                        first.epoch
+                    )
+                    .into())
+                }
+                None => info!("rewards of epoch #{}..=#{} match the expected", from, to),
+            }
+        }
+        None => {
+            let stdout = io::stdout();
+            let mut out = stdout.lock();
+            serde_json::to_writer_pretty(&mut out, &reports)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
@@ -15,6 +15,7 @@ use context::AppContext;
 use discovery_service::server::discovery_server;
 use graphql_service::server::graphql_server;
 use opentron::util::get_my_ip;
+use producer_service::server::producer_server;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // ! init app command line arguments
@@ -63,6 +64,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let fut = opentron::commands::fix::main(ctx, arg_matches);
             rt.block_on(fut)
         }
+        ("dump", Some(arg_matches)) => {
+            let fut = opentron::commands::dump::main(ctx, arg_matches);
+            rt.block_on(fut)
+        }
+        ("dry-run-block", Some(arg_matches)) => {
+            let fut = opentron::commands::dry_run::main(ctx, arg_matches);
+            rt.block_on(fut)
+        }
+        ("reward-audit", Some(arg_matches)) => {
+            let fut = opentron::commands::reward_audit::main(ctx, arg_matches);
+            rt.block_on(fut)
+        }
         ("dev", Some(_)) => {
             let fut = opentron::commands::dev::main(ctx);
             rt.block_on(fut)
@@ -124,7 +137,14 @@ async fn run(ctx: AppContext) -> Result<(), Box<dyn Error>> {
         let logger = slog_scope::logger().new(o!("service" => "discovery"));
         discovery_server(ctx, done_signal).with_logger(logger)
     };
-    let _ = join!(graphql_service, channel_service, discovery_service);
+
+    let producer_service = {
+        let ctx = ctx.clone();
+        let done_signal = ctx.termination_signal.subscribe();
+        let logger = slog_scope::logger().new(o!("service" => "producer"));
+        producer_server(ctx, done_signal).with_logger(logger)
+    };
+    let _ = join!(graphql_service, channel_service, discovery_service, producer_service);
 
     Ok(termination_done.await?)
 }
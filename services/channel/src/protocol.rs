@@ -5,7 +5,7 @@ use prost::Message;
 use proto::chain::Block;
 use proto::channel::{
     inventory::Type as InventoryType, BlockInventory, ChainInventory, HandshakeDisconnect, HandshakeHello, Inventory,
-    ReasonCode as DisconnectReasonCode, Transactions,
+    PbftMessage, ReasonCode as DisconnectReasonCode, Transactions,
 };
 use std::convert::TryFrom;
 use std::io::{self, Cursor};
@@ -37,6 +37,9 @@ pub enum ChannelMessage {
 
     Ping,
     Pong,
+
+    // original: PBFT_MSG
+    PbftMessage(PbftMessage),
 }
 
 impl ChannelMessage {
@@ -58,6 +61,8 @@ impl ChannelMessage {
 
             Ping => 0x22,
             Pong => 0x23,
+
+            PbftMessage(_) => 0x41,
         }
     }
 
@@ -84,6 +89,7 @@ impl ChannelMessage {
             BlockchainInventory(ref chain_inv) => chain_inv.encode(dst),
             HandshakeHello(ref hello) => hello.encode(dst),
             HandshakeDisconnect(ref disconnect) => disconnect.encode(dst),
+            PbftMessage(ref msg) => msg.encode(dst),
         };
         ret.map_err(From::from)
     }
@@ -103,6 +109,7 @@ impl ChannelMessage {
             BlockchainInventory(ref chain_inv) => chain_inv.encoded_len(),
             HandshakeHello(ref hello) => hello.encoded_len(),
             HandshakeDisconnect(ref disconnect) => disconnect.encoded_len(),
+            PbftMessage(ref msg) => msg.encoded_len(),
         };
         pb_len + 1
     }
@@ -157,6 +164,14 @@ impl ::std::fmt::Debug for ChannelMessage {
                 hello.timestamp,
             ),
             HandshakeDisconnect(ref disconnect) => write!(f, "HandshakeDisconnect(reason={})", disconnect.reason),
+            PbftMessage(ref msg) => match msg.raw_data.as_ref() {
+                Some(raw) => write!(
+                    f,
+                    "PbftMessage(type={}, number={}, epoch={})",
+                    raw.msg_type, raw.view_n, raw.epoch
+                ),
+                None => write!(f, "PbftMessage(empty)"),
+            },
         }
     }
 }
@@ -201,6 +216,7 @@ impl TryFrom<&[u8]> for ChannelMessage {
                 assert!(buf[1] == 0xC0);
                 Ok(ChannelMessage::Pong)
             }
+            0x41 => Ok(ChannelMessage::PbftMessage(Message::decode(&buf[1..])?)),
             _ => Err(io::Error::new(io::ErrorKind::InvalidData, "invalid data")),
         }
     }
@@ -4,7 +4,7 @@ use std::net::SocketAddr;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use chain::IndexedBlock;
+use chain::{IndexedBlock, IndexedTransaction};
 use chrono::Utc;
 use futures::future::FutureExt;
 use futures::join;
@@ -316,6 +316,8 @@ async fn sync_channel_handler(
     let (tx, mut rx) = mpsc::channel::<ChannelMessage>(1000);
 
     let mut done = ctx.termination_signal.subscribe();
+    let mut new_block = ctx.new_block_signal.subscribe();
+    let mut pbft_message = ctx.pbft_messages.subscribe();
 
     const READING_TIMEOUT: u64 = 18;
     loop {
@@ -329,6 +331,20 @@ async fn sync_channel_handler(
                 debug!("termination, close channel connection");
                 return Ok(());
             }
+            Ok(block_id) = new_block.recv() => {
+                if !syncing {
+                    let inv = Inventory {
+                        r#type: 1, // BLOCK
+                        ids: vec![block_id.hash],
+                    };
+                    writer.send(ChannelMessage::BlockInventory(inv)).await?;
+                }
+            }
+            Ok(msg) = pbft_message.recv() => {
+                if !syncing {
+                    writer.send(ChannelMessage::PbftMessage(msg)).await?;
+                }
+            }
             task = timeout(Duration::from_secs(READING_TIMEOUT), reader.next().fuse()) => {
                 let payload = match task {
                     Err(_) if pinged => {
@@ -385,12 +401,39 @@ async fn sync_channel_handler(
                         }
                         */
                     }
+                    Ok(ChannelMessage::PbftMessage(msg)) => {
+                        let (is_new, signed) = {
+                            let mut manager = ctx.manager.write().unwrap();
+                            let is_new = match manager.handle_pbft_message(&msg) {
+                                Ok(is_new) => is_new,
+                                Err(e) => {
+                                    warn!("handle pbft message failed: {}", e);
+                                    false
+                                }
+                            };
+                            (is_new, manager.take_pbft_messages())
+                        };
+                        if is_new {
+                            let _ = ctx.pbft_messages.send(msg);
+                        }
+                        for msg in signed {
+                            let _ = ctx.pbft_messages.send(msg);
+                        }
+                    }
                     Ok(ChannelMessage::FetchTransactionInventory(inv)) => {
                         info!("fetch transactions {:?}", inv);
                     }
                     Ok(ChannelMessage::Transactions(Transactions { transactions })) => {
-                        for txn in &transactions {
-                            info!("got txn {:?}", txn);
+                        if !ctx.is_producing_enabled() {
+                            continue;
+                        }
+                        for txn in transactions.into_iter().filter_map(IndexedTransaction::from_raw) {
+                            let hash = txn.hash;
+                            match ctx.add_pending_transaction(txn) {
+                                Ok(true) => debug!("got txn {:?}", hash),
+                                Ok(false) => {}
+                                Err(e) => warn!("drop txn {:?}: {}", hash, e),
+                            }
                         }
                     }
                     Ok(ChannelMessage::BlockInventory(inv)) => {
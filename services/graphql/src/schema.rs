@@ -3,14 +3,17 @@ use std::mem;
 use std::str;
 use std::sync::{Arc, RwLock};
 
-use async_graphql::{Context, Enum, Error, InputObject, Object, Result, SimpleObject};
+use async_graphql::{Context, Enum, Error, ErrorExtensions, InputObject, Object, Result, SimpleObject};
 use byteorder::{ByteOrder, BE};
 use chrono::{DateTime, TimeZone, Utc};
 use primitive_types::H256;
 
 use ::state::keys;
 use chain::{IndexedBlockHeader, IndexedTransaction};
+use chain_db::ChainDB;
 use context::AppContext;
+use manager::executor::ExecutionError;
+use manager::Manager;
 use proto::state;
 
 use super::contract::{AccountType, Contract};
@@ -88,6 +91,38 @@ impl Account {
         Ok(Bytes32(val))
     }
 
+    /// StorageEntries pages through all storage slots of a contract account,
+    /// ordered by slot. Use the last slot as `after` to fetch the next page.
+    async fn storage_entries(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<Bytes32>,
+    ) -> Result<Vec<StorageEntry>> {
+        let first = first.unwrap_or(100);
+        if first < 0 || first as i64 > MAX_NUMBER_OF_BATCH_ITEMS_PER_REQUEST {
+            return Err("first should be between 0 and 1000".into());
+        }
+        self.require_inner(ctx)?;
+        let inner = self.inner.read().unwrap();
+        if inner.as_ref().unwrap().r#type != state::AccountType::Contract as i32 {
+            return Ok(vec![]);
+        }
+        let ref manager = ctx.data_unchecked::<Arc<AppContext>>().manager.read().unwrap();
+        let entries = manager.state().contract_storage_entries(
+            &self.address.0,
+            after.as_ref().map(|slot| &slot.0),
+            first as usize,
+        )?;
+        Ok(entries
+            .into_iter()
+            .map(|(slot, value)| StorageEntry {
+                slot: Bytes32(slot),
+                value: Bytes32(value),
+            })
+            .collect())
+    }
+
     /// Token balance of token id, in minimum unit.
     async fn token_balance(&self, ctx: &Context<'_>, id: i64) -> Result<Long> {
         self.require_inner(ctx)?;
@@ -187,6 +222,93 @@ impl Asset {
     }
 }
 
+/// Witness is a block producer candidate, aka. SR or SR partner.
+pub struct Witness(state::Witness);
+
+#[Object]
+impl Witness {
+    /// Address of the witness.
+    async fn address(&self) -> Address {
+        Address(TryFrom::try_from(&self.0.address).unwrap())
+    }
+
+    /// URL of the witness.
+    async fn url(&self) -> &str {
+        &self.0.url
+    }
+
+    /// Received votes.
+    async fn vote_count(&self) -> Long {
+        self.0.vote_count.into()
+    }
+
+    /// Total number of blocks produced.
+    async fn total_produced(&self) -> Long {
+        self.0.total_produced.into()
+    }
+
+    /// Total number of blocks missed.
+    async fn total_missed(&self) -> Long {
+        self.0.total_missed.into()
+    }
+
+    /// Percentage of scheduled slots filled by the witness, 0-100.
+    async fn filled_slot_rate(&self) -> f64 {
+        let total = self.0.total_produced + self.0.total_missed;
+        if total == 0 {
+            0.0
+        } else {
+            self.0.total_produced as f64 * 100.0 / total as f64
+        }
+    }
+
+    /// Number of the latest produced block.
+    async fn latest_block_number(&self) -> Long {
+        self.0.latest_block_number.into()
+    }
+
+    /// Block version of the latest produced block, used for version fork.
+    async fn latest_block_version(&self) -> i32 {
+        self.0.latest_block_version
+    }
+
+    /// Performance of the `last` recent epochs(default 1), latest first.
+    async fn epochs(&self, ctx: &Context<'_>, last: Option<i32>) -> Result<Vec<WitnessPerformance>> {
+        let ref manager = ctx.data_unchecked::<Arc<AppContext>>().manager.read().unwrap();
+        let mut perfs = vec![];
+        manager.state().for_each_by_prefix(
+            &self.0.address,
+            |key: &keys::WitnessPerformance, perf: &state::WitnessPerformance| {
+                let latency = manager.block_latency(&key.0, key.1);
+                perfs.push(WitnessPerformance {
+                    epoch: key.1,
+                    produced: perf.produced.into(),
+                    missed: perf.missed.into(),
+                    average_latency: latency.and_then(|latency| latency.average()),
+                    max_latency: latency.map(|latency| latency.max).unwrap_or_default().into(),
+                })
+            },
+        )?;
+        perfs.sort_by(|a, b| b.epoch.cmp(&a.epoch));
+        perfs.truncate(last.unwrap_or(1).max(0) as usize);
+        Ok(perfs)
+    }
+}
+
+/// Block producing performance of a witness in one epoch(maintenance cycle).
+#[derive(SimpleObject)]
+pub struct WitnessPerformance {
+    epoch: i64,
+    produced: Long,
+    missed: Long,
+    /// Average delay from slot time to block applied, in milliseconds. Null if not tracked.
+    ///
+    /// Latency is local to the node, and only tracked since started.
+    average_latency: Option<f64>,
+    /// Max delay from slot time to block applied, in milliseconds.
+    max_latency: Long,
+}
+
 /// Rename from `ContractStatus`, or `contractResult`.
 #[derive(Enum, Copy, Clone, Eq, PartialEq, Debug)]
 #[repr(i32)]
@@ -344,6 +466,31 @@ fn matches_topics(topics: &Option<Vec<Vec<Bytes32>>>, target: &[Vec<u8>]) -> boo
     }
 }
 
+/// Execution error, with the failure kind as the `code` extension.
+fn execution_error(err: ExecutionError) -> Error {
+    Error::new(err.to_string()).extend_with(|_, ext| match err {
+        ExecutionError::AccountNotFound(addr) => {
+            ext.set("code", "ACCOUNT_NOT_FOUND");
+            ext.set("address", addr.to_string());
+        }
+        ExecutionError::InsufficientBalance => ext.set("code", "INSUFFICIENT_BALANCE"),
+        ExecutionError::PermissionDenied { ref reason } => {
+            ext.set("code", "PERMISSION_DENIED");
+            ext.set("reason", reason.as_str());
+        }
+        ExecutionError::BandwidthExhausted => ext.set("code", "BANDWIDTH_EXHAUSTED"),
+        ExecutionError::ContractValidate { ref reason } => {
+            ext.set("code", "CONTRACT_VALIDATE");
+            ext.set("reason", reason.as_str());
+        }
+        ExecutionError::ContractExecute { ref reason } => {
+            ext.set("code", "CONTRACT_EXECUTE");
+            ext.set("reason", reason.as_str());
+        }
+        ExecutionError::State(_) => ext.set("code", "INTERNAL"),
+    })
+}
+
 /// CallData represents the data associated with a local contract call.
 /// All fields are optional.
 #[derive(InputObject)]
@@ -411,6 +558,144 @@ impl CallResult {
     }
 }
 
+/// Receipt is the execution result of a transaction.
+///
+/// Contract specific fields are null for other contract types.
+pub struct Receipt(state::TransactionReceipt);
+
+fn non_zero(val: i64) -> Option<Long> {
+    if val != 0 {
+        Some(val.into())
+    } else {
+        None
+    }
+}
+
+#[Object]
+impl Receipt {
+    async fn success(&self) -> bool {
+        self.0.success
+    }
+    /// Fee is the total fee of bandwidth, energy, multisig and the contract, in sun.
+    async fn fee(&self) -> Long {
+        self.0
+            .resource_receipt
+            .as_ref()
+            .map(|receipt| receipt.bandwidth_fee + receipt.energy_fee + receipt.contract_fee + receipt.multisig_fee)
+            .unwrap_or_default()
+            .into()
+    }
+    /// EnergyUsed is the amount of energy used, including the one paid by the contract origin.
+    async fn energy_used(&self) -> Long {
+        self.0
+            .resource_receipt
+            .as_ref()
+            .map(|receipt| receipt.energy)
+            .unwrap_or_default()
+            .into()
+    }
+    /// BandwidthUsed is the amount of bandwidth used.
+    async fn bandwidth_used(&self) -> Long {
+        self.0
+            .resource_receipt
+            .as_ref()
+            .map(|receipt| receipt.bandwidth_usage)
+            .unwrap_or_default()
+            .into()
+    }
+    /// VmStatus is the result of a smart contract transaction.
+    async fn vm_status(&self) -> VmStatus {
+        unsafe { mem::transmute(self.0.vm_status) }
+    }
+    /// CreatedContractAddress is the address of the newly created smart contract.
+    async fn created_contract_address(&self) -> Option<Address> {
+        ::keys::Address::try_from(&self.0.vm_created_contract_address)
+            .ok()
+            .map(Address)
+    }
+    /// AssetCreatedTokenId is the id of the newly issued TRC10 token.
+    async fn asset_created_token_id(&self) -> Option<Long> {
+        non_zero(self.0.asset_created_token_id)
+    }
+    /// WithdrawalAmount is the reward withdrawn, in sun.
+    async fn withdrawal_amount(&self) -> Option<Long> {
+        non_zero(self.0.withdrawal_amount)
+    }
+    /// UnfrozenAmount is the amount of unfrozen TRX or TRC10 token.
+    async fn unfrozen_amount(&self) -> Option<Long> {
+        non_zero(self.0.unfrozen_amount)
+    }
+    /// ExchangeCreatedExchangeId is the id of the newly created exchange.
+    async fn exchange_created_exchange_id(&self) -> Option<Long> {
+        non_zero(self.0.exchange_created_exchange_id)
+    }
+    /// ExchangeReceivedAmount is the amount of token bought from an exchange.
+    async fn exchange_received_amount(&self) -> Option<Long> {
+        non_zero(self.0.exchange_received_amount)
+    }
+    /// ExchangeInjectedAmount is the amount of the other token injected into an exchange.
+    async fn exchange_injected_amount(&self) -> Option<Long> {
+        non_zero(self.0.exchange_injected_amount)
+    }
+    /// ExchangeWithdrawalAmount is the amount of the other token withdrawn from an exchange.
+    async fn exchange_withdrawal_amount(&self) -> Option<Long> {
+        non_zero(self.0.exchange_withdrawal_amount)
+    }
+}
+
+/// BlockDryRun is the result of a dry-run block.
+#[derive(SimpleObject)]
+pub struct BlockDryRun {
+    /// Receipts of transactions, in block order.
+    receipts: Vec<TransactionDryRun>,
+    /// Changed accounts.
+    accounts: Vec<AccountDiff>,
+    /// Changed storage slots.
+    storage: Vec<StorageDiff>,
+    /// Changed dynamic properties.
+    properties: Vec<PropertyDiff>,
+}
+
+#[derive(SimpleObject)]
+pub struct TransactionDryRun {
+    hash: Bytes32,
+    result: CallResult,
+}
+
+/// AccountDiff is a changed account, values are debug-formatted. Null if not exists.
+#[derive(SimpleObject)]
+pub struct AccountDiff {
+    address: Address,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+/// StorageDiff is a changed storage slot. Null if not exists.
+#[derive(SimpleObject)]
+pub struct StorageDiff {
+    address: Address,
+    slot: Bytes32,
+    before: Option<Bytes32>,
+    after: Option<Bytes32>,
+}
+
+/// PropertyDiff is a changed dynamic property. Null if not exists.
+#[derive(SimpleObject)]
+pub struct PropertyDiff {
+    name: String,
+    before: Option<Long>,
+    after: Option<Long>,
+}
+
+/// StorageEntry is a storage slot of a contract account.
+#[derive(SimpleObject)]
+pub struct StorageEntry {
+    /// Slot is the 32 byte slot identifier.
+    slot: Bytes32,
+    /// Value is the value stored in the slot.
+    value: Bytes32,
+}
+
 /// SyncState contains the current synchronisation state of the client.
 #[derive(SimpleObject)]
 pub struct SyncState {
@@ -420,6 +705,9 @@ pub struct SyncState {
     highest_block: Long,
     /// SolidBlock is the safe block for comfirmations.
     solid_block: Long,
+    /// PbftBlock is the latest block confirmed by PBFT commits, or null if PBFT
+    /// is not enabled. It is the solid block once PBFT is enabled.
+    pbft_block: Option<Long>,
     /// StateBlock is the block number of StateDB.
     state_block: Long,
     /// PulledStates is the number of state entries fetched so far, or null
@@ -518,18 +806,13 @@ impl Transaction {
         })
     }
 
-    // NOTE: for debug
-    async fn receipt(&self, ctx: &Context<'_>) -> Result<String> {
+    /// Receipt is the execution result of this transaction, like `TransactionInfo` in java-tron.
+    async fn receipt(&self, ctx: &Context<'_>) -> Result<Option<Receipt>> {
         let ref manager = ctx.data_unchecked::<Arc<AppContext>>().manager.read().unwrap();
-        if let Some(receipt) = manager.state().get(&keys::TransactionReceipt(self.inner.hash))? {
-            Ok(format!(
-                "resource_receipt={:?} vm_logs={}",
-                receipt.resource_receipt,
-                receipt.vm_logs.len()
-            ))
-        } else {
-            Ok("not found".to_owned())
-        }
+        Ok(manager
+            .state()
+            .get(&keys::TransactionReceipt(self.inner.hash))?
+            .map(Receipt))
     }
     // nonce
     // status
@@ -786,7 +1069,7 @@ impl Chain {
                     key: format!("{:?}", key),
                     value: *value,
                 });
-            });
+            })?;
         }
         Ok(params)
     }
@@ -795,7 +1078,7 @@ impl Chain {
     async fn parameter(&self, ctx: &Context<'_>, id: i32) -> Result<ChainParameter> {
         let ref manager = ctx.data_unchecked::<Arc<AppContext>>().manager.read().unwrap();
         let param = keys::ChainParameter::from_i32(id).ok_or_else(|| "invalid parameter id")?;
-        let value = manager.state().must_get(&param);
+        let value = manager.state().must_get(&param)?;
         Ok(ChainParameter {
             id: id,
             key: format!("{:?}", param),
@@ -803,14 +1086,52 @@ impl Chain {
         })
     }
 
+    /// Percentage of filled slots in the recent 128 slots, 0-100.
+    async fn block_filled_slot_rate(&self, ctx: &Context<'_>) -> Result<f64> {
+        let ref manager = ctx.data_unchecked::<Arc<AppContext>>().manager.read().unwrap();
+        let filled_slots = manager.state().must_get(&keys::BlockFilledSlots)?;
+        let num_filled = filled_slots.iter().filter(|&&filled| filled == 1).count();
+        Ok(num_filled as f64 * 100.0 / filled_slots.len().max(1) as f64)
+    }
+
     /// Next maintenance time.
-    async fn next_maintenance_time(&self, ctx: &Context<'_>) -> DateTime<Utc> {
+    async fn next_maintenance_time(&self, ctx: &Context<'_>) -> Result<DateTime<Utc>> {
         let ref manager = ctx.data_unchecked::<Arc<AppContext>>().manager.read().unwrap();
-        let ts = manager.state().must_get(&keys::DynamicProperty::NextMaintenanceTime);
-        Utc.timestamp(ts / 1_000, ts as u32 % 1_000 * 1_000_000)
+        let ts = manager.state().must_get(&keys::DynamicProperty::NextMaintenanceTime)?;
+        Ok(Utc.timestamp(ts / 1_000, ts as u32 % 1_000 * 1_000_000))
     }
 }
 
+/// Scan logs from transaction receipts of blocks.
+fn scan_logs(
+    db: &ChainDB,
+    manager: &Manager,
+    filter: &FilterCriteria,
+    from_block: i64,
+    to_block: i64,
+) -> Result<Vec<Log>> {
+    let mut logs = vec![];
+    for block_num in from_block..=to_block {
+        let txn_hashes = db.get_transaction_hashes_by_block_number(block_num)?;
+        for (index, &txn_hash) in txn_hashes.iter().enumerate() {
+            if let Some(receipt) = manager.state().get(&keys::TransactionReceipt(txn_hash))? {
+                receipt
+                    .vm_logs
+                    .into_iter()
+                    .filter(|log_entry| filter.matches(log_entry))
+                    .for_each(|log_entry| {
+                        logs.push(Log {
+                            index: index as i32,
+                            inner: log_entry,
+                            txn_hash: txn_hash,
+                        });
+                    });
+            }
+        }
+    }
+    Ok(logs)
+}
+
 pub struct QueryRoot;
 
 #[Object]
@@ -891,10 +1212,13 @@ impl QueryRoot {
     }
 
     /// Logs returns log entries matching the provided filter.
+    ///
+    /// Filters with addresses are served by the log index on any block range, returning at most 1000 logs.
+    /// Otherwise, or for blocks before the index starts, at most 1000 blocks are scanned.
     async fn logs(&self, ctx: &Context<'_>, filter: FilterCriteria) -> Result<Vec<Log>> {
         let ref db = ctx.data_unchecked::<Arc<AppContext>>().chain_db;
         let ref manager = ctx.data_unchecked::<Arc<AppContext>>().manager.read().unwrap();
-        let defaut_block = manager.latest_block_number();
+        let defaut_block = manager.latest_block_number()?;
 
         let from_block = filter.from_block.unwrap_or(defaut_block.into()).0;
         let to_block = filter.to_block.unwrap_or(defaut_block.into()).0;
@@ -903,46 +1227,95 @@ impl QueryRoot {
             return Err("fromBlock should be lower than toBlock".into());
         }
 
-        if to_block - from_block > MAX_NUMBER_OF_BATCH_ITEMS_PER_REQUEST {
-            return Err(Error::from("exceeds the maximum number of blocks per request"));
+        // Blocks without index are scanned, with a limit on the block range.
+        let scan = |from_block: i64, to_block: i64| {
+            if to_block.saturating_sub(from_block) > MAX_NUMBER_OF_BATCH_ITEMS_PER_REQUEST {
+                return Err(Error::from("exceeds the maximum number of blocks per request"));
+            }
+            scan_logs(db, manager, &filter, from_block, to_block)
+        };
+
+        let addrs = match filter.addresses.as_ref().filter(|addrs| !addrs.is_empty()) {
+            Some(addrs) => addrs,
+            None => return scan(from_block, to_block),
+        };
+
+        // Blocks applied before the log index was introduced are not indexed.
+        let index_start = manager
+            .state()
+            .get(&keys::DynamicProperty::TransactionLogIndexStart)?
+            .unwrap_or_default();
+        let mut logs = if from_block < index_start {
+            scan(from_block, to_block.min(index_start - 1))?
+        } else {
+            vec![]
+        };
+        let from_block = from_block.max(index_start);
+        if from_block > to_block {
+            return Ok(logs);
         }
 
-        let mut logs = vec![];
-        for block_num in from_block..=to_block {
-            let txn_hashes = db.get_transaction_hashes_by_block_number(block_num)?;
-            for (index, &txn_hash) in txn_hashes.iter().enumerate() {
-                if let Some(receipt) = manager.state().get(&keys::TransactionReceipt(txn_hash))? {
-                    receipt
-                        .vm_logs
-                        .into_iter()
-                        .filter(|log_entry| filter.matches(log_entry))
-                        .for_each(|log_entry| {
-                            logs.push(Log {
-                                index: index as i32,
-                                inner: log_entry,
-                                txn_hash: txn_hash,
-                            });
-                        });
+        // Without topic0, logs of all topics are read from the by-block index.
+        let topic0s: Vec<Option<&H256>> = match filter.topics.as_ref().and_then(|topics| topics.first()) {
+            Some(topic0s) if !topic0s.is_empty() => topic0s.iter().map(|topic| Some(&topic.0)).collect(),
+            _ => vec![None],
+        };
+        let max_num_of_logs = MAX_NUMBER_OF_BATCH_ITEMS_PER_REQUEST as usize;
+        let mut indexed_logs = vec![];
+        for addr in addrs {
+            for &topic0 in &topic0s {
+                // One more than allowed, to detect an oversized result.
+                let limit = (max_num_of_logs + 1).saturating_sub(logs.len() + indexed_logs.len());
+                indexed_logs.extend(manager.state().transaction_logs(
+                    &addr.0,
+                    topic0,
+                    from_block,
+                    to_block,
+                    limit,
+                    |log_entry| filter.matches(log_entry),
+                )?);
+                if logs.len() + indexed_logs.len() > max_num_of_logs {
+                    return Err(Error::from(
+                        "exceeds the maximum number of logs per request, narrow the block range",
+                    ));
                 }
             }
         }
+        indexed_logs.sort_by_key(|(key, _)| (key.2, key.3, key.4));
+
+        let mut txn_hashes = (-1, vec![]);
+        for (key, log_entry) in indexed_logs {
+            if txn_hashes.0 != key.2 {
+                txn_hashes = (key.2, db.get_transaction_hashes_by_block_number(key.2)?);
+            }
+            let txn_hash = *txn_hashes
+                .1
+                .get(key.3 as usize)
+                .ok_or_else(|| "transaction not found")?;
+            logs.push(Log {
+                index: key.3,
+                inner: log_entry,
+                txn_hash,
+            });
+        }
         Ok(logs)
     }
 
     /// Syncing returns information on the current synchronisation state.
-    async fn syncing(&self, ctx: &Context<'_>) -> SyncState {
+    async fn syncing(&self, ctx: &Context<'_>) -> Result<SyncState> {
         let ref db = ctx.data_unchecked::<Arc<AppContext>>().chain_db;
         let ref manager = ctx.data_unchecked::<Arc<AppContext>>().manager.read().unwrap();
 
-        SyncState {
+        Ok(SyncState {
             current_block: Long(db.get_block_height()),
             // FIXME: wrong impl
             highest_block: Long(db.get_block_height()),
-            solid_block: Long(manager.solid_block_number()),
-            state_block: Long(manager.latest_block_number()),
+            solid_block: Long(manager.solid_block_number()?),
+            pbft_block: manager.pbft_block_number()?.map(Long),
+            state_block: Long(manager.latest_block_number()?),
             pulled_states: None,
             known_states: None,
-        }
+        })
     }
 
     // NOTE: Tron does not support block history, so the following query is moved from Block to Query.
@@ -978,7 +1351,9 @@ impl QueryRoot {
         let ref mut manager = ctx.data_unchecked::<Arc<AppContext>>().manager.write().unwrap();
         let energy_limit = data.energy_limit.map(|val| val.0).unwrap_or(100_000_000);
 
-        let receipt = TransactionExecutor::new(manager).execute_smart_contract(&trigger, energy_limit)?;
+        let receipt = TransactionExecutor::new(manager)
+            .execute_smart_contract(&trigger, energy_limit)
+            .map_err(execution_error)?;
         Ok(CallResult { receipt })
     }
 
@@ -1024,6 +1399,17 @@ impl QueryRoot {
         Ok(Asset(asset))
     }
 
+    /// Witnesses, ordered by votes.
+    async fn witnesses(&self, ctx: &Context<'_>) -> Result<Vec<Witness>> {
+        let ref manager = ctx.data_unchecked::<Arc<AppContext>>().manager.read().unwrap();
+        let mut witnesses = vec![];
+        manager
+            .state()
+            .for_each(|_: &keys::Witness, wit: &state::Witness| witnesses.push(Witness(wit.clone())))?;
+        witnesses.sort_by(|a, b| b.0.vote_count.cmp(&a.0.vote_count));
+        Ok(witnesses)
+    }
+
     /// Chain query.
     async fn chain(&self) -> Chain {
         Chain
@@ -1035,8 +1421,25 @@ pub struct MutationRoot;
 #[Object]
 impl MutationRoot {
     /// SendRawTransaction sends an protobuf-encoded transaction to the network.
-    async fn send_raw_transaction(&self, _data: Bytes) -> Result<Bytes32> {
-        unimplemented!()
+    async fn send_raw_transaction(&self, ctx: &Context<'_>, data: Bytes) -> Result<Bytes32> {
+        use chain::IndexedTransaction;
+        use prost::Message;
+        use proto::chain::Transaction;
+
+        let app = ctx.data_unchecked::<Arc<AppContext>>();
+
+        let txn = Transaction::decode(&*data.0)?;
+        let indexed_txn = IndexedTransaction::from_raw(txn).ok_or("invalid transaction")?;
+        indexed_txn.recover_owner()?;
+
+        if !app.is_producing_enabled() {
+            return Err("block producing is disabled, transaction is not accepted".into());
+        }
+        let hash = indexed_txn.hash;
+        if !app.add_pending_transaction(indexed_txn)? {
+            return Err("transaction is duplicated, expired or the pending pool is full".into());
+        }
+        Ok(Bytes32(hash))
     }
 
     /// DryRunRawTransaction runs an protobuf-encoded transaction and returns the receipt as json.
@@ -1050,8 +1453,111 @@ impl MutationRoot {
         let txn = Transaction::decode(&*data.0)?;
         let indexed_txn = IndexedTransaction::from_raw(txn).ok_or("invalid transaction")?;
 
-        let receipt = manager.dry_run_transaction(&indexed_txn)?;
+        let receipt = manager
+            .dry_run_transaction(&indexed_txn)
+            .map_err(|e| match e.downcast::<ExecutionError>() {
+                Ok(err) => execution_error(*err),
+                Err(e) => Error::new(e.to_string()),
+            })?;
 
         Ok(CallResult { receipt })
     }
+
+    /// DryRunRawBlock applies an protobuf-encoded block on top of the latest block, and returns receipts and
+    /// the state diff. All changes are discarded.
+    async fn dry_run_raw_block(&self, ctx: &Context<'_>, data: Bytes) -> Result<BlockDryRun> {
+        use chain::IndexedBlock;
+        use prost::Message;
+        use proto::chain::Block;
+
+        let ref mut manager = ctx.data_unchecked::<Arc<AppContext>>().manager.write().unwrap();
+
+        let block = Block::decode(&*data.0)?;
+        let indexed_block = IndexedBlock::from_raw(block).ok_or("invalid block")?;
+
+        let result = manager.dry_run_block(&indexed_block)?;
+        let diff = result.diff;
+
+        Ok(BlockDryRun {
+            receipts: result
+                .receipts
+                .into_iter()
+                .map(|(hash, receipt)| TransactionDryRun {
+                    hash: Bytes32(hash),
+                    result: CallResult { receipt },
+                })
+                .collect(),
+            accounts: diff
+                .accounts
+                .into_iter()
+                .map(|change| AccountDiff {
+                    address: Address(change.address),
+                    before: change.before.map(|acct| format!("{:?}", acct)),
+                    after: change.after.map(|acct| format!("{:?}", acct)),
+                })
+                .collect(),
+            storage: diff
+                .storage
+                .into_iter()
+                .map(|change| StorageDiff {
+                    address: Address(change.address),
+                    slot: Bytes32(change.slot),
+                    before: change.before.map(Bytes32),
+                    after: change.after.map(Bytes32),
+                })
+                .collect(),
+            properties: diff
+                .properties
+                .into_iter()
+                .map(|change| PropertyDiff {
+                    name: change.name,
+                    before: change.before.map(Long),
+                    after: change.after.map(Long),
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::state::StateError;
+    use serde_json::Value;
+
+    fn extensions(err: &Error) -> Value {
+        serde_json::to_value(&err.extensions).unwrap()
+    }
+
+    #[test]
+    fn test_execution_error() {
+        let addr: ::keys::Address = "TN21Wx2yoNYiZ7znuQonmZMJnH5Vdfxu78".parse().unwrap();
+        let err = execution_error(ExecutionError::AccountNotFound(addr));
+        assert_eq!(extensions(&err)["code"], "ACCOUNT_NOT_FOUND");
+        assert_eq!(extensions(&err)["address"], "TN21Wx2yoNYiZ7znuQonmZMJnH5Vdfxu78");
+
+        let err = execution_error(ExecutionError::InsufficientBalance);
+        assert_eq!(extensions(&err)["code"], "INSUFFICIENT_BALANCE");
+
+        let err = execution_error(ExecutionError::ContractValidate {
+            reason: "invalid owner_address".into(),
+        });
+        assert_eq!(err.message, "invalid owner_address");
+        assert_eq!(extensions(&err)["code"], "CONTRACT_VALIDATE");
+        assert_eq!(extensions(&err)["reason"], "invalid owner_address");
+
+        // Not a validation error.
+        let err = execution_error(ExecutionError::ContractExecute {
+            reason: "balance overflow".into(),
+        });
+        assert_eq!(err.message, "balance overflow");
+        assert_eq!(extensions(&err)["code"], "CONTRACT_EXECUTE");
+        assert_eq!(extensions(&err)["reason"], "balance overflow");
+
+        let err = execution_error(ExecutionError::State(StateError::NotFound {
+            col: "default",
+            key: vec![],
+        }));
+        assert_eq!(extensions(&err)["code"], "INTERNAL");
+    }
 }
@@ -0,0 +1,62 @@
+use std::error::Error;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use chrono::Utc;
+use context::AppContext;
+use keys::KeyPair;
+use log::{error, info, warn};
+use manager::producer::BlockProducer;
+use tokio::sync::broadcast;
+use tokio::time::{sleep, Duration};
+
+/// Interval of checking the producing slot, in milliseconds.
+const PRODUCING_CHECK_INTERVAL: u64 = 500;
+
+pub async fn producer_server(ctx: Arc<AppContext>, mut signal: broadcast::Receiver<()>) -> Result<(), Box<dyn Error>> {
+    if ctx.config.witness.private_key.is_empty() {
+        warn!("producer service disabled, no witness private key");
+        return Ok(());
+    }
+    let keypair = KeyPair::from_private(ctx.config.witness.private_key.parse()?)?;
+    info!("block producer started, signing address={}", keypair.address());
+
+    loop {
+        tokio::select! {
+            _ = signal.recv() => {
+                info!("block producer closed");
+                return Ok(());
+            }
+            _ = sleep(Duration::from_millis(PRODUCING_CHECK_INTERVAL)) => {
+                if ctx.syncing.load(Ordering::Relaxed) {
+                    continue;
+                }
+                if let Err(e) = produce_block(&ctx, &keypair) {
+                    error!("producing block failed: {}", e);
+                }
+            }
+        }
+    }
+}
+
+fn produce_block(ctx: &AppContext, keypair: &KeyPair) -> Result<(), Box<dyn Error>> {
+    let now = Utc::now().timestamp_millis();
+
+    let (block, pbft_messages) = {
+        let mut manager = ctx.manager.write().unwrap();
+        let mut pending = ctx.pending_transactions.lock().unwrap();
+        let block = BlockProducer::new(&mut manager, keypair).produce_block(now, &mut pending)?;
+        (block, manager.take_pbft_messages())
+    };
+
+    if let Some(block) = block {
+        ctx.recent_blk_ids.write().unwrap().insert(block.header.hash);
+        ctx.chain_db.insert_block(&block)?;
+        ctx.chain_db.update_block_height(block.number());
+        let _ = ctx.new_block_signal.send(block.block_id());
+    }
+    for msg in pbft_messages {
+        let _ = ctx.pbft_messages.send(msg);
+    }
+    Ok(())
+}
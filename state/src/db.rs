@@ -1,102 +1,212 @@
 //! The state-db implementation.
 
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::io;
 use std::iter;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
 use std::path::Path;
 
 use ::keys::Address;
 use config::genesis::GenesisConfig;
 use config::ChainConfig;
-use log::info;
+use log::{error, info};
+use primitive_types::H256;
 use proto::common::AccountType;
 use proto::state as state_pb;
 use rocks::prelude::*;
 
+use super::error::StateError;
 use super::keys;
+use super::migration;
 use super::parameter::default_parameters_from_config;
 use super::DynamicProperty;
 
 pub type BoxError = Box<dyn ::std::error::Error>;
 
-pub trait KeyValueDB {
-    type Column;
-
+/// Storage backend of the state-db. Columns are indexed by `COL_*`.
+pub trait KeyValueDB: Send + Sync {
     /// Get a value by key.
-    fn get(&self, col: &Self::Column, key: &[u8]) -> io::Result<Option<Vec<u8>>>;
-
-    /// Get the first value matching the given prefix.
-    fn get_by_prefix(&self, col: &Self::Column, prefix: &[u8]) -> Option<Box<[u8]>>;
+    fn get(&self, col: usize, key: &[u8]) -> io::Result<Option<Vec<u8>>>;
 
     /// Write a batch of changes to the backing store.
-    fn write(&self, batch: &WriteBatch) -> io::Result<()>;
-
-    /// Iterate over the data for a given column.
-    fn iter<'a>(&'a self, col: &Self::Column) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>;
+    fn write(&mut self, batch: &OverlayWriteBatch) -> io::Result<()>;
 
-    /// Iterate over the data for a given column, returning all key/value pairs
+    /// Iterate over the data for a given column, visiting all key/value pairs
     /// where the key starts with the given prefix.
-    fn iter_with_prefix<'a>(
-        &'a self,
-        col: &Self::Column,
-        prefix: &'a [u8],
-    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>;
+    fn for_each_by_prefix(&self, col: usize, prefix: &[u8], func: &mut dyn FnMut(&[u8], &[u8]));
+
+    /// Iterate over the data for a given column in key order, visiting all key/value pairs
+    /// where `from <= key < to`.
+    fn for_each_in_range(&self, col: usize, from: &[u8], to: &[u8], func: &mut dyn FnMut(&[u8], &[u8]));
 }
 
-pub struct OverlayWriteBatch {
-    wb: WriteBatch,
-    // CF => (Key => Value)
-    // TODO: replace with VecMap
-    cache: HashMap<u32, BTreeMap<Vec<u8>, Option<Vec<u8>>>>,
+/// The RocksDB backend.
+pub struct RocksDB {
+    db: DB,
+    cols: Vec<ColumnFamily>,
 }
 
-impl std::ops::Deref for OverlayWriteBatch {
-    type Target = WriteBatch;
-    fn deref(&self) -> &Self::Target {
-        &self.wb
+impl KeyValueDB for RocksDB {
+    fn get(&self, col: usize, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        match self.db.get_cf(ReadOptions::default_instance(), &self.cols[col], key) {
+            Ok(val) => Ok(Some(val.to_vec())),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string())),
+        }
+    }
+
+    fn write(&mut self, batch: &OverlayWriteBatch) -> io::Result<()> {
+        let mut wb = WriteBatch::with_reserved_bytes(4 * 1024);
+        for (&col, cf) in &batch.cache {
+            for (key, value) in cf {
+                match value {
+                    Some(val) => {
+                        wb.put_cf(&self.cols[col], key, val);
+                    }
+                    None => {
+                        wb.delete_cf(&self.cols[col], key);
+                    }
+                }
+            }
+        }
+        self.db
+            .write(WriteOptions::default_instance(), &wb)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+    }
+
+    fn for_each_by_prefix(&self, col: usize, prefix: &[u8], func: &mut dyn FnMut(&[u8], &[u8])) {
+        if prefix.is_empty() {
+            for (key, value) in self.db.new_iterator_cf(&ReadOptions::default(), &self.cols[col]) {
+                func(key, value);
+            }
+            return;
+        }
+        for (key, value) in self
+            .db
+            .new_iterator_cf(&ReadOptions::default().iterate_lower_bound(prefix), &self.cols[col])
+        {
+            if !key.starts_with(prefix) {
+                return;
+            }
+            func(key, value);
+        }
+    }
+
+    fn for_each_in_range(&self, col: usize, from: &[u8], to: &[u8], func: &mut dyn FnMut(&[u8], &[u8])) {
+        for (key, value) in self
+            .db
+            .new_iterator_cf(&ReadOptions::default().iterate_lower_bound(from), &self.cols[col])
+        {
+            if key >= to {
+                return;
+            }
+            func(key, value);
+        }
     }
 }
 
-impl OverlayWriteBatch {
+/// The in-memory backend, for tests and simulations.
+pub struct MemoryDB {
+    cols: Vec<BTreeMap<Vec<u8>, Vec<u8>>>,
+}
+
+impl MemoryDB {
     pub fn new() -> Self {
-        OverlayWriteBatch {
-            wb: WriteBatch::new(),
-            cache: HashMap::new(),
+        MemoryDB {
+            cols: vec![BTreeMap::new(); NUM_COLUMNS],
+        }
+    }
+}
+
+impl Default for MemoryDB {
+    fn default() -> Self {
+        MemoryDB::new()
+    }
+}
+
+impl KeyValueDB for MemoryDB {
+    fn get(&self, col: usize, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.cols[col].get(key).cloned())
+    }
+
+    fn write(&mut self, batch: &OverlayWriteBatch) -> io::Result<()> {
+        for (&col, cf) in &batch.cache {
+            for (key, value) in cf {
+                match value {
+                    Some(val) => {
+                        self.cols[col].insert(key.clone(), val.clone());
+                    }
+                    None => {
+                        self.cols[col].remove(key);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn for_each_by_prefix(&self, col: usize, prefix: &[u8], func: &mut dyn FnMut(&[u8], &[u8])) {
+        for (key, value) in self.cols[col].range(prefix.to_vec()..) {
+            if !key.starts_with(prefix) {
+                return;
+            }
+            func(key, value);
         }
     }
 
-    pub fn with_capacity(cap: usize) -> Self {
-        OverlayWriteBatch {
-            wb: WriteBatch::with_reserved_bytes(cap),
-            cache: HashMap::new(),
+    fn for_each_in_range(&self, col: usize, from: &[u8], to: &[u8], func: &mut dyn FnMut(&[u8], &[u8])) {
+        // `BTreeMap::range` panics on an inverted range.
+        if from >= to {
+            return;
+        }
+        for (key, value) in self.cols[col].range(from.to_vec()..to.to_vec()) {
+            func(key, value);
         }
     }
+}
+
+pub struct OverlayWriteBatch {
+    // Col => (Key => Value)
+    // TODO: replace with VecMap
+    cache: HashMap<usize, BTreeMap<Vec<u8>, Option<Vec<u8>>>>,
+}
 
-    pub fn put(&mut self, col: &ColumnFamilyHandle, key: &[u8], value: &[u8]) {
-        self.wb.put_cf(col, key, value);
+impl OverlayWriteBatch {
+    pub fn new() -> Self {
+        OverlayWriteBatch { cache: HashMap::new() }
+    }
+
+    pub fn put(&mut self, col: usize, key: &[u8], value: &[u8]) {
         self.cache
-            .entry(col.id())
+            .entry(col)
             .or_default()
             .insert(key.to_owned(), Some(value.to_owned()));
     }
 
-    pub fn delete(&mut self, col: &ColumnFamilyHandle, key: &[u8]) {
-        self.wb.delete_cf(col, key);
-        self.cache.entry(col.id()).or_default().insert(key.to_owned(), None);
+    pub fn delete(&mut self, col: usize, key: &[u8]) {
+        self.cache.entry(col).or_default().insert(key.to_owned(), None);
+    }
+
+    /// Merge a newer batch into this one, newer values win.
+    pub fn merge(&mut self, newer: OverlayWriteBatch) {
+        for (col, cf) in newer.cache {
+            self.cache.entry(col).or_default().extend(cf);
+        }
     }
 
     // Ok(None) => deleted
     // Err(_)   => non-exist
-    pub fn get(&self, col: &ColumnFamilyHandle, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+    pub fn get(&self, col: usize, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
         self.cache
-            .get(&col.id())
+            .get(&col)
             .and_then(|cf| cf.get(key).cloned())
             .ok_or(io::Error::new(io::ErrorKind::NotFound, ""))
     }
 
     // None => deleted or not-found
-    pub fn get_by_prefix(&self, col: &ColumnFamilyHandle, prefix: &[u8]) -> Option<Box<[u8]>> {
-        self.cache.get(&col.id()).and_then(|cf| {
+    pub fn get_by_prefix(&self, col: usize, prefix: &[u8]) -> Option<Box<[u8]>> {
+        self.cache.get(&col).and_then(|cf| {
             cf.iter()
                 .filter(|(key, value)| key.starts_with(prefix) && value.is_some())
                 .map(|(_, value)| value.clone().unwrap().into_boxed_slice())
@@ -104,9 +214,9 @@ impl OverlayWriteBatch {
         })
     }
 
-    pub fn iter<'a>(&'a self, col: &ColumnFamilyHandle) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+    pub fn iter<'a>(&'a self, col: usize) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
         self.cache
-            .get(&col.id())
+            .get(&col)
             .map(|cf| {
                 Box::new(cf.iter().filter(|(_, value)| value.is_some()).map(|(key, value)| {
                     (
@@ -122,11 +232,11 @@ impl OverlayWriteBatch {
     /// where the key starts with the given prefix.
     pub fn iter_with_prefix<'a>(
         &'a self,
-        col: &ColumnFamilyHandle,
+        col: usize,
         prefix: &'a [u8],
     ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
         self.cache
-            .get(&col.id())
+            .get(&col)
             .map(|cf| {
                 Box::new(
                     cf.iter()
@@ -143,22 +253,25 @@ impl OverlayWriteBatch {
     }
 }
 
-pub struct OverlayDB {
-    inner: DB,
+pub struct OverlayDB<D: KeyValueDB + ?Sized = RocksDB> {
     // Use push_back to add to the queue, and pop_front to remove from the queue.
     // push_back to add a new layer, pop_front to sync a layer to db, clear to discard all layers.
     layers: VecDeque<OverlayWriteBatch>,
+    // NOTE: Must be the last field, so that `OverlayDB<dyn KeyValueDB>` is possible.
+    inner: D,
 }
 
-impl OverlayDB {
-    pub fn new(inner: DB) -> Self {
+impl<D: KeyValueDB> OverlayDB<D> {
+    pub fn new(inner: D) -> Self {
         OverlayDB {
-            inner,
             // ceiling(27 - 27 * 70%) = 9
             layers: VecDeque::with_capacity(9),
+            inner,
         }
     }
+}
 
+impl<D: KeyValueDB + ?Sized> OverlayDB<D> {
     /// Fake `write` an OverlayWriteBath.
     pub fn write(&mut self, wb: OverlayWriteBatch) -> io::Result<()> {
         self.layers.push_back(wb);
@@ -171,7 +284,7 @@ impl OverlayDB {
 
     pub fn solidify_layers(&mut self) -> Result<(), BoxError> {
         for layer in self.layers.drain(..) {
-            self.inner.write(WriteOptions::default_instance(), &layer.wb)?;
+            self.inner.write(&layer)?;
         }
         Ok(())
     }
@@ -182,81 +295,53 @@ impl OverlayDB {
     }
 
     /// Get a value by key.
-    pub fn get(&self, col: &ColumnFamilyHandle, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+    pub fn get(&self, col: usize, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
         for layer in self.layers.iter().rev() {
             if let Ok(val) = layer.get(col, key) {
                 return Ok(val);
             }
         }
-        match self.inner.get_cf(ReadOptions::default_instance(), col, key) {
-            Ok(val) => Ok(Some(val.to_vec())),
-            Err(e) if e.is_not_found() => Ok(None),
-            // Err(e) if e.is_not_found() => Err(io::Error::new(io::ErrorKind::NotFound, "")),
-            Err(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string())),
-        }
+        self.inner.get(col, key)
     }
 
     /// Get a value by key, skip top n layers.
-    pub fn get_skipped(&self, n: usize, col: &ColumnFamilyHandle, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+    pub fn get_skipped(&self, n: usize, col: usize, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
         for layer in self.layers.iter().rev().skip(n) {
             if let Ok(val) = layer.get(col, key) {
                 return Ok(val);
             }
         }
-        match self.inner.get_cf(ReadOptions::default_instance(), col, key) {
-            Ok(val) => Ok(Some(val.to_vec())),
-            Err(e) if e.is_not_found() => Ok(None),
-            // Err(e) if e.is_not_found() => Err(io::Error::new(io::ErrorKind::NotFound, "")),
-            Err(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string())),
-        }
+        self.inner.get(col, key)
     }
 
-    /// Get the first value matching the given prefix.
-    pub fn get_by_prefix(&self, col: &ColumnFamilyHandle, prefix: &[u8]) -> Option<Box<[u8]>> {
-        let mut deleted = HashSet::<&[u8]>::new();
-
-        for layer in self.layers.iter().rev() {
-            if let Some(cache) = layer.cache.get(&col.id()) {
-                for (key, value) in cache.iter().filter(|(key, _)| key.starts_with(prefix)) {
-                    if deleted.contains(&**key) {
-                        continue;
-                    }
-                    match value {
-                        Some(val) => {
-                            return Some(val.clone().into_boxed_slice());
-                        }
-                        None => {
-                            deleted.insert(key);
-                        }
-                    }
-                }
-            }
-        }
+    /// Keys of a column written in layers above the given depth, including deleted ones.
+    pub fn keys_since(&self, depth: usize, col: usize) -> BTreeSet<Vec<u8>> {
+        self.layers
+            .iter()
+            .skip(depth)
+            .filter_map(|layer| layer.cache.get(&col))
+            .flat_map(|cf| cf.keys().cloned())
+            .collect()
+    }
 
-        for (key, value) in self
-            .inner
-            .new_iterator_cf(&ReadOptions::default().iterate_lower_bound(prefix), col)
-        {
-            if !key.starts_with(prefix) {
-                return None;
-            }
-            if deleted.contains(key) {
-                continue;
-            }
-            return Some(value.to_vec().into_boxed_slice());
-        }
-        None
+    pub fn for_each<F>(&self, col: usize, func: F)
+    where
+        F: FnMut(&[u8], &[u8]) -> (),
+    {
+        self.for_each_by_prefix(col, &[], func)
     }
 
-    pub fn for_each<F>(&self, col: &ColumnFamilyHandle, mut func: F)
+    /// Iterate over the data for a given column, returning all key/value pairs
+    /// where the key starts with the given prefix.
+    pub fn for_each_by_prefix<F>(&self, col: usize, prefix: &[u8], mut func: F)
     where
         F: FnMut(&[u8], &[u8]) -> (),
     {
-        let mut visited: HashSet<&[u8]> = HashSet::new();
+        let mut visited = HashSet::<&[u8]>::new();
 
         for layer in self.layers.iter().rev() {
-            if let Some(cache) = layer.cache.get(&col.id()) {
-                for (key, value) in cache.iter() {
+            if let Some(cache) = layer.cache.get(&col) {
+                for (key, value) in cache.iter().filter(|(key, _)| key.starts_with(prefix)) {
                     if visited.contains(&**key) {
                         continue;
                     }
@@ -271,57 +356,48 @@ impl OverlayDB {
             }
         }
 
-        for (key, value) in self.inner.new_iterator_cf(&ReadOptions::default(), col) {
-            if visited.contains(key) {
-                continue;
-            }
-            func(key, value);
-        }
+        self.inner
+            .for_each_by_prefix(col, prefix, &mut |key: &[u8], value: &[u8]| {
+                if !visited.contains(key) {
+                    func(key, value);
+                }
+            });
     }
 
-    /// Iterate over the data for a given column, returning all key/value pairs
-    /// where the key starts with the given prefix.
-    pub fn for_each_by_prefix<F>(&self, col: &ColumnFamilyHandle, prefix: &[u8], mut func: F)
+    /// Visit all entries where `from <= key < to`. Layers are visited first, so entries are not ordered.
+    pub fn for_each_in_range<F>(&self, col: usize, from: &[u8], to: &[u8], mut func: F)
     where
         F: FnMut(&[u8], &[u8]) -> (),
     {
+        // `BTreeMap::range` panics on an inverted range.
+        if from >= to {
+            return;
+        }
         let mut visited = HashSet::<&[u8]>::new();
 
         for layer in self.layers.iter().rev() {
-            if let Some(cache) = layer.cache.get(&col.id()) {
-                for (key, value) in cache.iter().filter(|(key, _)| key.starts_with(prefix)) {
-                    if !key.starts_with(prefix) {
-                        continue;
-                    }
+            if let Some(cache) = layer.cache.get(&col) {
+                for (key, value) in cache.range(from.to_vec()..to.to_vec()) {
                     if visited.contains(&**key) {
                         continue;
                     }
                     visited.insert(key);
-                    match value {
-                        Some(val) => {
-                            func(key, val);
-                        }
-                        None => (),
+                    if let Some(val) = value {
+                        func(key, val);
                     }
                 }
             }
         }
 
-        for (key, value) in self
-            .inner
-            .new_iterator_cf(&ReadOptions::default().iterate_lower_bound(prefix), col)
-        {
-            if !key.starts_with(prefix) {
-                return;
-            }
-            if visited.contains(key) {
-                continue;
-            }
-            func(key, value);
-        }
+        self.inner
+            .for_each_in_range(col, from, to, &mut |key: &[u8], value: &[u8]| {
+                if !visited.contains(key) {
+                    func(key, value);
+                }
+            });
     }
 
-    pub fn delete(&mut self, col: &ColumnFamilyHandle, key: &[u8]) -> io::Result<()> {
+    pub fn delete(&mut self, col: usize, key: &[u8]) -> io::Result<()> {
         let wb = self
             .layers
             .back_mut()
@@ -330,43 +406,11 @@ impl OverlayDB {
         Ok(())
     }
 
-    pub fn delete_by_prefix(&mut self, col: &ColumnFamilyHandle, prefix: &[u8]) -> io::Result<()> {
-        let mut visited = HashSet::<&[u8]>::new();
+    pub fn delete_by_prefix(&mut self, col: usize, prefix: &[u8]) -> io::Result<()> {
         let mut deleted = HashSet::<Vec<u8>>::new();
-
-        for layer in self.layers.iter().rev() {
-            if let Some(cache) = layer.cache.get(&col.id()) {
-                for (key, value) in cache.iter().filter(|(key, _)| key.starts_with(prefix)) {
-                    if !key.starts_with(prefix) {
-                        continue;
-                    }
-                    if visited.contains(&**key) {
-                        continue;
-                    }
-                    visited.insert(key);
-                    match value {
-                        Some(_) => {
-                            deleted.insert(key.to_vec());
-                        }
-                        None => (),
-                    }
-                }
-            }
-        }
-
-        for key in self
-            .inner
-            .new_iterator_cf(&ReadOptions::default().iterate_lower_bound(prefix), col)
-            .keys()
-        {
-            if !key.starts_with(prefix) {
-                return Ok(());
-            }
-            if visited.contains(key) {
-                continue;
-            }
+        self.for_each_by_prefix(col, prefix, |key, _| {
             deleted.insert(key.to_vec());
-        }
+        });
         for key in &deleted {
             self.delete(col, key)?;
         }
@@ -393,14 +437,142 @@ pub const COL_TRANSACTION_LOG: usize = 13;
 pub const COL_ACCOUNT_INDEX: usize = 14;
 pub const COL_VOTER_REWARD: usize = 15;
 pub const COL_EXCHANGE: usize = 16;
+pub const COL_WITNESS_PERFORMANCE: usize = 17;
+pub const COL_MARKET_ORDER: usize = 18;
+pub const COL_MARKET_ACCOUNT: usize = 19;
+/// Price lists of token pairs, and order lists of prices.
+pub const COL_MARKET_ORDER_BOOK: usize = 20;
+pub const COL_PBFT_COMMIT: usize = 21;
+/// Spent notes of shielded TRC10 transfers, nile only.
+pub const COL_SHIELDED_NULLIFIER: usize = 22;
+/// Note commitment tree roots, valid as anchors of shielded spends.
+pub const COL_SHIELDED_ANCHOR: usize = 23;
+/// Transaction logs by contract address and block, for queries without topic0.
+pub const COL_TRANSACTION_LOG_BY_BLOCK: usize = 24;
+/// Number of column families.
+pub const NUM_COLUMNS: usize = 25;
+
+/// Column family name of a column index, for error reporting.
+pub fn col_name(col: usize) -> &'static str {
+    match col {
+        COL_DEFAULT => DEFAULT_COLUMN_FAMILY_NAME,
+        COL_ACCOUNT => "account",
+        COL_RESOURCE_DELEGATION => "resource-delegation",
+        COL_RESOURCE_DELEGATION_INDEX => "resource-delegation-index",
+        COL_VOTES => "account-votes",
+        COL_CONTRACT => "contract",
+        COL_CONTRACT_CODE => "contract-code",
+        COL_CONTRACT_STORAGE => "contract-storage",
+        COL_WITNESS => "witness",
+        COL_PROPOSAL => "proposal",
+        COL_ASSET => "asset",
+        COL_TRANSACTION_RECEIPT => "transaction-receipt",
+        COL_INTERNAL_TRANSACTION => "internal-transaction",
+        COL_TRANSACTION_LOG => "transaction-log",
+        COL_ACCOUNT_INDEX => "account-index",
+        COL_VOTER_REWARD => "voter-reward",
+        COL_EXCHANGE => "exchange",
+        COL_WITNESS_PERFORMANCE => "witness-performance",
+        COL_MARKET_ORDER => "market-order",
+        COL_MARKET_ACCOUNT => "market-account",
+        COL_MARKET_ORDER_BOOK => "market-order-book",
+        COL_PBFT_COMMIT => "pbft-commit",
+        COL_SHIELDED_NULLIFIER => "shielded-nullifier",
+        COL_SHIELDED_ANCHOR => "shielded-anchor",
+        COL_TRANSACTION_LOG_BY_BLOCK => "transaction-log-by-block",
+        _ => "unknown",
+    }
+}
+
+/// Decode a raw value, reporting the column family and key on failure.
+fn parse_value<T, K: keys::Key<T>>(key: &[u8], raw: &[u8]) -> Result<T, StateError> {
+    K::parse_value(raw).map_err(|e| {
+        error!("corrupted value in cf={}: {}", col_name(K::COL), e);
+        StateError::Corrupt {
+            col: col_name(K::COL),
+            key: key.to_vec(),
+        }
+    })
+}
+
+/// A savepoint of the state-db. Changes made after the savepoint are discarded on drop, unless committed.
+///
+/// Savepoints can be nested. Rolling back a savepoint also discards changes committed by its inner savepoints.
+/// The savepoint can wrap anything holding a state-db, e.g. `Manager`.
+pub struct Savepoint<'a, T: AsMut<StateDB<D>> + ?Sized = StateDB, D: KeyValueDB + ?Sized = RocksDB> {
+    inner: &'a mut T,
+    depth: usize,
+    released: bool,
+    _backend: PhantomData<D>,
+}
+
+impl<'a, T: AsMut<StateDB<D>> + ?Sized, D: KeyValueDB + ?Sized> Savepoint<'a, T, D> {
+    pub fn new(inner: &'a mut T) -> Self {
+        let depth = {
+            let db = inner.as_mut();
+            let depth = db.db.layers.len();
+            db.new_layer();
+            depth
+        };
+        Savepoint {
+            inner,
+            depth,
+            released: false,
+            _backend: PhantomData,
+        }
+    }
+
+    /// Keep all changes, they now belong to the enclosing savepoint.
+    ///
+    /// After commit or rollback, the savepoint only forwards to the wrapped value.
+    pub fn commit(&mut self) {
+        self.released = true;
+    }
+
+    /// Discard all changes made after the savepoint.
+    pub fn rollback(&mut self) {
+        if !self.released {
+            self.inner.as_mut().rollback_to(self.depth);
+            self.released = true;
+        }
+    }
+}
+
+impl<T: AsMut<StateDB<D>> + ?Sized, D: KeyValueDB + ?Sized> Drop for Savepoint<'_, T, D> {
+    fn drop(&mut self) {
+        self.rollback();
+    }
+}
+
+impl<T: AsMut<StateDB<D>> + ?Sized, D: KeyValueDB + ?Sized> Deref for Savepoint<'_, T, D> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.inner
+    }
+}
+
+impl<T: AsMut<StateDB<D>> + ?Sized, D: KeyValueDB + ?Sized> DerefMut for Savepoint<'_, T, D> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.inner
+    }
+}
+
+impl<D: KeyValueDB + ?Sized> AsMut<StateDB<D>> for StateDB<D> {
+    fn as_mut(&mut self) -> &mut StateDB<D> {
+        self
+    }
+}
 
 /// The State DB derived from Chain DB.
-pub struct StateDB {
-    db: OverlayDB,
-    cols: Vec<ColumnFamily>,
+///
+/// Generic over the storage backend, RocksDB by default. `StateDB<dyn KeyValueDB>` is for holders that run on
+/// different backends, e.g. the manager on RocksDB, or in memory for tests.
+pub struct StateDB<D: KeyValueDB + ?Sized = RocksDB> {
+    db: OverlayDB<D>,
 }
 
-impl Drop for StateDB {
+impl<D: KeyValueDB + ?Sized> Drop for StateDB<D> {
     fn drop(&mut self) {
         info!("state-db closed successfully, all cached layers will be droped");
     }
@@ -484,10 +656,10 @@ fn col_descs_for_state_db() -> Vec<ColumnFamilyDescriptor> {
             "internal-transaction",
             ColumnFamilyOptions::default().optimize_for_point_lookup(16),
         ),
-        // <<Address, Topic: H256, [IndexedParam]>> => Transaction
+        // <<Address, topic0: H256, block_number: i64, txn_index: i32, log_index: i32>> => TransactionLog
         ColumnFamilyDescriptor::new(
             "transaction-log",
-            ColumnFamilyOptions::default().prefix_extractor_fixed(32),
+            ColumnFamilyOptions::default().prefix_extractor_fixed(21),
         ),
         // <<account_name: str>> => Address
         ColumnFamilyDescriptor::new(
@@ -508,6 +680,57 @@ fn col_descs_for_state_db() -> Vec<ColumnFamilyDescriptor> {
                 .optimize_for_small_db()
                 .optimize_for_point_lookup(16),
         ),
+        // <<Address, epoch: i64>> => WitnessPerformance
+        ColumnFamilyDescriptor::new(
+            "witness-performance",
+            ColumnFamilyOptions::default()
+                .optimize_for_small_db()
+                .prefix_extractor_fixed(21),
+        ),
+        // <<order_id: H256>> => MarketOrder
+        ColumnFamilyDescriptor::new(
+            "market-order",
+            ColumnFamilyOptions::default().optimize_for_point_lookup(16),
+        ),
+        // <<Address>> => MarketAccountOrders
+        ColumnFamilyDescriptor::new(
+            "market-account",
+            ColumnFamilyOptions::default()
+                .optimize_for_small_db()
+                .optimize_for_point_lookup(16),
+        ),
+        // <<sell_token_id: i64, buy_token_id: i64>> => [Price]
+        // <<sell_token_id: i64, buy_token_id: i64, Price>> => [order_id: H256]
+        ColumnFamilyDescriptor::new(
+            "market-order-book",
+            ColumnFamilyOptions::default()
+                .optimize_for_small_db()
+                .optimize_for_point_lookup(16),
+        ),
+        // <<block_number: i64>> => PbftCommitResult
+        ColumnFamilyDescriptor::new(
+            "pbft-commit",
+            ColumnFamilyOptions::default().optimize_for_point_lookup(16),
+        ),
+        // <<nullifier: H256>> => txn_hash: H256
+        ColumnFamilyDescriptor::new(
+            "shielded-nullifier",
+            ColumnFamilyOptions::default()
+                .optimize_for_small_db()
+                .optimize_for_point_lookup(16),
+        ),
+        // <<root: H256>> => block_number: i64
+        ColumnFamilyDescriptor::new(
+            "shielded-anchor",
+            ColumnFamilyOptions::default()
+                .optimize_for_small_db()
+                .optimize_for_point_lookup(16),
+        ),
+        // <<Address, block_number: i64, txn_index: i32, log_index: i32>> => TransactionLog
+        ColumnFamilyDescriptor::new(
+            "transaction-log-by-block",
+            ColumnFamilyOptions::default().prefix_extractor_fixed(21),
+        ),
     ]
 }
 
@@ -524,24 +747,64 @@ impl StateDB {
 
         let (db, cols) = DB::open_with_column_families(&db_options, db_path, column_families).unwrap();
 
-        StateDB {
-            db: OverlayDB::new(db),
-            cols,
-        }
+        StateDB::with_backend(RocksDB { db, cols })
     }
 }
 
-impl StateDB {
+impl StateDB<MemoryDB> {
+    /// Create an empty state-db in memory.
+    pub fn new_in_memory() -> Self {
+        StateDB::with_backend(MemoryDB::new())
+    }
+}
+
+impl<D: KeyValueDB + 'static> StateDB<D> {
+    /// Create a state-db on a custom backend, pending migrations are applied.
+    pub fn with_backend(backend: D) -> Self {
+        let mut db = StateDB {
+            db: OverlayDB::new(backend),
+        };
+        migration::migrate(&mut db).expect("state-db migration failed");
+        db
+    }
+}
+
+impl<D: KeyValueDB + ?Sized> StateDB<D> {
     pub fn new_layer(&mut self) -> &mut OverlayWriteBatch {
-        self.db.push_layer(OverlayWriteBatch::with_capacity(4 * 1024));
+        self.db.push_layer(OverlayWriteBatch::new());
         self.db.layers.back_mut().unwrap()
     }
 
-    pub fn solidify_layer(&mut self) {
+    /// Write the oldest layer to the underlying DB.
+    pub fn solidify_layer(&mut self) -> Result<(), StateError> {
+        if let Some(wb) = self.db.layers.pop_front() {
+            self.db.inner.write(&wb)?;
+        }
+        Ok(())
+    }
+
+    /// Number of layers not yet written to the underlying DB.
+    pub fn num_layers(&self) -> usize {
+        self.db.layers.len()
+    }
+
+    /// Merge all layers above the given depth into one.
+    pub fn squash_layers(&mut self, depth: usize) {
+        if self.db.layers.len() > depth + 1 {
+            let mut squashed = self.db.layers.split_off(depth);
+            let mut wb = squashed.pop_front().unwrap();
+            for newer in squashed {
+                wb.merge(newer);
+            }
+            self.db.layers.push_back(wb);
+        }
+    }
+
+    /// Write all layers to the underlying DB.
+    pub fn flush_layers(&mut self) -> Result<(), StateError> {
         self.db
-            .layers
-            .pop_front()
-            .map(|wb| self.db.inner.write(WriteOptions::default_instance(), &wb));
+            .solidify_layers()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()).into())
     }
 
     pub fn discard_last_layer(&mut self) -> io::Result<()> {
@@ -552,91 +815,314 @@ impl StateDB {
         Ok(())
     }
 
-    pub fn put_key<T, K: keys::Key<T>>(&mut self, key: K, value: T) -> Result<(), BoxError> {
+    /// Create a savepoint on a new layer.
+    pub fn savepoint(&mut self) -> Savepoint<'_, Self, D> {
+        Savepoint::new(self)
+    }
+
+    /// Discard layers above the given depth.
+    fn rollback_to(&mut self, depth: usize) {
+        self.db.layers.truncate(depth);
+    }
+
+    pub fn put_key<T, K: keys::Key<T>>(&mut self, key: K, value: T) -> Result<(), StateError> {
         let wb = self
             .db
             .layers
             .back_mut()
             .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "no db layers found"))?;
-        wb.put(&self.cols[K::COL], key.key().as_ref(), &*K::value(&value));
+        wb.put(K::COL, key.key().as_ref(), &*K::value(&value));
         Ok(())
     }
 
-    pub fn delete_key<T, K: keys::Key<T>>(&mut self, key: &K) -> Result<(), BoxError> {
-        self.db.delete(&self.cols[K::COL], key.key().as_ref())?;
+    /// Put a raw key-value pair, bypassing the key codec. Used by migrations.
+    pub fn put_raw(&mut self, col: usize, key: &[u8], value: &[u8]) -> Result<(), StateError> {
+        let wb = self
+            .db
+            .layers
+            .back_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "no db layers found"))?;
+        wb.put(col, key, value);
         Ok(())
     }
 
-    pub fn delete_by_prefix(&mut self, col: &ColumnFamilyHandle, prefix: &[u8]) -> Result<(), BoxError> {
+    /// Get a raw value, bypassing the key codec.
+    pub fn get_raw(&self, col: usize, key: &[u8]) -> Result<Option<Vec<u8>>, StateError> {
+        Ok(self.db.get(col, key)?)
+    }
+
+    pub fn delete_key<T, K: keys::Key<T>>(&mut self, key: &K) -> Result<(), StateError> {
+        self.db.delete(K::COL, key.key().as_ref())?;
+        Ok(())
+    }
+
+    pub fn delete_by_prefix(&mut self, col: usize, prefix: &[u8]) -> Result<(), StateError> {
         self.db.delete_by_prefix(col, prefix)?;
         Ok(())
     }
 
-    pub fn get<T, K: keys::Key<T>>(&self, key: &K) -> Result<Option<T>, BoxError> {
-        self.db
-            .get(&self.cols[K::COL], key.key().as_ref())
-            .map(|maybe_raw| maybe_raw.map(|raw| K::parse_value(&raw)))
-            .map_err(|e| e.into())
+    pub fn get<T, K: keys::Key<T>>(&self, key: &K) -> Result<Option<T>, StateError> {
+        let raw_key = key.key();
+        match self.db.get(K::COL, raw_key.as_ref())? {
+            Some(raw) => parse_value::<T, K>(raw_key.as_ref(), &raw).map(Some),
+            None => Ok(None),
+        }
     }
 
-    pub fn get_skipped<T, K: keys::Key<T>>(&self, n: usize, key: &K) -> Result<Option<T>, BoxError> {
-        self.db
-            .get_skipped(n, &self.cols[K::COL], key.key().as_ref())
-            .map(|maybe_raw| maybe_raw.map(|raw| K::parse_value(&raw)))
-            .map_err(|e| e.into())
+    pub fn get_skipped<T, K: keys::Key<T>>(&self, n: usize, key: &K) -> Result<Option<T>, StateError> {
+        let raw_key = key.key();
+        match self.db.get_skipped(n, K::COL, raw_key.as_ref())? {
+            Some(raw) => parse_value::<T, K>(raw_key.as_ref(), &raw).map(Some),
+            None => Ok(None),
+        }
     }
 
-    pub fn must_get_skipped<T, K: keys::Key<T>>(&self, n: usize, key: &K) -> T {
-        self.db
-            .get_skipped(n, &self.cols[K::COL], key.key().as_ref())
-            .map(|maybe_raw| maybe_raw.map(|raw| K::parse_value(&raw)))
-            .expect("corrupted db")
-            .expect("key must exist")
+    /// Get a value by key, skip top n layers. Missing key is an error.
+    pub fn must_get_skipped<T, K: keys::Key<T>>(&self, n: usize, key: &K) -> Result<T, StateError> {
+        self.get_skipped(n, key)?.ok_or_else(|| StateError::NotFound {
+            col: col_name(K::COL),
+            key: key.key().as_ref().to_vec(),
+        })
     }
 
-    pub fn must_get<T, K: keys::Key<T>>(&self, key: &K) -> T {
-        self.db
-            .get(&self.cols[K::COL], key.key().as_ref())
-            .map(|maybe_raw| maybe_raw.map(|raw| K::parse_value(&raw)))
-            .expect("corrupted db")
-            .expect("key must exist")
+    /// Raw changes of a column made in layers above the given depth, as `(key, before, after)`.
+    ///
+    /// Entries rewritten with the same value are skipped.
+    pub fn raw_changes_since(
+        &self,
+        depth: usize,
+        col: usize,
+    ) -> Result<Vec<(Vec<u8>, Option<Vec<u8>>, Option<Vec<u8>>)>, StateError> {
+        let num_skipped = self.db.layers.len().saturating_sub(depth);
+        let mut changes = vec![];
+        for key in self.db.keys_since(depth, col) {
+            let before = self.db.get_skipped(num_skipped, col, &key)?;
+            let after = self.db.get(col, &key)?;
+            if before != after {
+                changes.push((key, before, after));
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Changes of a column made in layers above the given depth, as `(key, before, after)`.
+    ///
+    /// Keys that can not be parsed are skipped.
+    pub fn changes_since<T, K: keys::Key<T>>(
+        &self,
+        depth: usize,
+    ) -> Result<Vec<(K, Option<T>, Option<T>)>, StateError> {
+        let mut changes = vec![];
+        for (raw_key, before, after) in self.raw_changes_since(depth, K::COL)? {
+            if let Some(key) = K::parse_key(&raw_key) {
+                let before = before.map(|raw| parse_value::<T, K>(&raw_key, &raw)).transpose()?;
+                let after = after.map(|raw| parse_value::<T, K>(&raw_key, &raw)).transpose()?;
+                changes.push((key, before, after));
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Get a value by key. Missing key is an error.
+    pub fn must_get<T, K: keys::Key<T>>(&self, key: &K) -> Result<T, StateError> {
+        self.get(key)?.ok_or_else(|| StateError::NotFound {
+            col: col_name(K::COL),
+            key: key.key().as_ref().to_vec(),
+        })
     }
 
     /// Increase a i64 key and the return updated value.
-    pub fn incr_key<K: keys::Key<i64>>(&mut self, key: K) -> Result<i64, BoxError> {
-        let old_val = self.get(&key)?.expect("key must be found");
+    pub fn incr_key<K: keys::Key<i64>>(&mut self, key: K) -> Result<i64, StateError> {
+        let old_val = self.must_get(&key)?;
         self.put_key(key, old_val + 1)?;
         Ok(old_val + 1)
     }
 
-    pub fn for_each<T, K: keys::Key<T>, F>(&self, mut func: F)
+    /// Visit all entries of a column. Stops at the first corrupted value.
+    pub fn for_each<T, K: keys::Key<T>, F>(&self, mut func: F) -> Result<(), StateError>
     where
         F: FnMut(&K, &T) -> (),
     {
-        self.db.for_each(&self.cols[K::COL], move |key, value| {
-            if let Some(key) = K::parse_key(key) {
-                func(&key, &K::parse_value(value));
+        let mut result = Ok(());
+        self.db.for_each(K::COL, |raw_key, raw_value| {
+            if result.is_err() {
+                return;
+            }
+            if let Some(key) = K::parse_key(raw_key) {
+                match parse_value::<T, K>(raw_key, raw_value) {
+                    Ok(value) => func(&key, &value),
+                    Err(e) => result = Err(e),
+                }
             }
         });
+        result
     }
 
-    pub fn for_each_by_prefix<T, K: keys::Key<T>, F>(&self, prefix: &[u8], mut func: F)
+    /// Visit all entries with the given key prefix. Stops at the first corrupted value.
+    pub fn for_each_by_prefix<T, K: keys::Key<T>, F>(&self, prefix: &[u8], mut func: F) -> Result<(), StateError>
     where
         F: FnMut(&K, &T) -> (),
     {
+        let mut result = Ok(());
+        self.db.for_each_by_prefix(K::COL, prefix, |raw_key, raw_value| {
+            if result.is_err() {
+                return;
+            }
+            if let Some(key) = K::parse_key(raw_key) {
+                match parse_value::<T, K>(raw_key, raw_value) {
+                    Ok(value) => func(&key, &value),
+                    Err(e) => result = Err(e),
+                }
+            }
+        });
+        result
+    }
+
+    /// Page through storage slots of a contract, ordered by slot. Returns at most `limit` entries after
+    /// the slot `after`.
+    pub fn contract_storage_entries(
+        &self,
+        addr: &Address,
+        after: Option<&H256>,
+        limit: usize,
+    ) -> Result<Vec<(H256, H256)>, StateError> {
+        use super::keys::Key;
+
+        // Keys are of fixed length, so the first key after `after` is `after` followed by a zero byte.
+        let from = match after {
+            Some(slot) => {
+                let mut from = keys::ContractStorage(*addr, *slot).key();
+                from.push(0);
+                from
+            }
+            None => addr.as_bytes().to_vec(),
+        };
+        let to = [addr.as_bytes(), &[0xff; 33][..]].concat();
+
+        // Only keep the smallest `limit` slots, layers are not ordered.
+        let mut entries = BTreeMap::new();
+        let mut result = Ok(());
         self.db
-            .for_each_by_prefix(&self.cols[K::COL], prefix, move |key, value| {
-                if let Some(key) = K::parse_key(key) {
-                    func(&key, &K::parse_value(value));
+            .for_each_in_range(keys::ContractStorage::COL, &from, &to, |raw_key, raw_value| {
+                if result.is_err() || limit == 0 {
+                    return;
+                }
+                let key = match keys::ContractStorage::parse_key(raw_key) {
+                    Some(key) => key,
+                    None => return,
+                };
+                if entries.len() >= limit && entries.keys().next_back().map(|last| key.1 >= *last).unwrap_or(false) {
+                    return;
+                }
+                match parse_value::<H256, keys::ContractStorage>(raw_key, raw_value) {
+                    Ok(value) => {
+                        entries.insert(key.1, value);
+                    }
+                    Err(e) => result = Err(e),
+                }
+                if entries.len() > limit {
+                    let last = *entries.keys().next_back().unwrap();
+                    entries.remove(&last);
                 }
             });
+        result?;
+        Ok(entries.into_iter().collect())
+    }
+
+    /// Index a transaction log, by topic0 and by block.
+    pub fn put_transaction_log(
+        &mut self,
+        key: keys::TransactionLog,
+        log: state_pb::TransactionLog,
+    ) -> Result<(), StateError> {
+        self.put_key(keys::TransactionLogByBlock(key.0, key.2, key.3, key.4), log.clone())?;
+        self.put_key(key, log)
+    }
+
+    /// Indexed logs of a contract in the inclusive block range, ordered by position in chain. Logs of all topics
+    /// are returned if `topic0` is not given.
+    ///
+    /// Only logs accepted by `matches` are collected, and collecting stops at `limit` logs. A full result is
+    /// not necessarily the earliest logs, callers should treat it as truncated.
+    pub fn transaction_logs<F>(
+        &self,
+        addr: &Address,
+        topic0: Option<&H256>,
+        from_block: i64,
+        to_block: i64,
+        limit: usize,
+        mut matches: F,
+    ) -> Result<Vec<(keys::TransactionLog, state_pb::TransactionLog)>, StateError>
+    where
+        F: FnMut(&state_pb::TransactionLog) -> bool,
+    {
+        use super::keys::Key;
+
+        let from_block = from_block.max(0);
+        if from_block > to_block {
+            return Ok(vec![]);
+        }
+        // Inclusive upper bound, longer than any key of `to_block`. No `to_block + 1` overflow.
+        let upper_bound = |prefix: &[u8]| [prefix, &to_block.to_be_bytes()[..], &[0xff; 9][..]].concat();
+
+        let mut logs = vec![];
+        let mut result = Ok(());
+        match topic0 {
+            Some(topic0) => {
+                let prefix = [addr.as_bytes(), topic0.as_bytes()].concat();
+                let from = keys::TransactionLog(*addr, *topic0, from_block, 0, 0).key();
+                let to = upper_bound(&prefix);
+                self.db
+                    .for_each_in_range(keys::TransactionLog::COL, &from, &to, |raw_key, raw_value| {
+                        if result.is_err() || logs.len() >= limit {
+                            return;
+                        }
+                        if let Some(key) = keys::TransactionLog::parse_key(raw_key) {
+                            match parse_value::<state_pb::TransactionLog, keys::TransactionLog>(raw_key, raw_value) {
+                                Ok(value) if matches(&value) => logs.push((key, value)),
+                                Ok(_) => (),
+                                Err(e) => result = Err(e),
+                            }
+                        }
+                    });
+            }
+            None => {
+                let from = keys::TransactionLogByBlock(*addr, from_block, 0, 0).key();
+                let to = upper_bound(addr.as_bytes());
+                self.db
+                    .for_each_in_range(keys::TransactionLogByBlock::COL, &from, &to, |raw_key, raw_value| {
+                        if result.is_err() || logs.len() >= limit {
+                            return;
+                        }
+                        if let Some(keys::TransactionLogByBlock(addr, block, txn_index, log_index)) =
+                            keys::TransactionLogByBlock::parse_key(raw_key)
+                        {
+                            match parse_value::<state_pb::TransactionLog, keys::TransactionLogByBlock>(
+                                raw_key, raw_value,
+                            ) {
+                                Ok(value) if matches(&value) => {
+                                    let topic0 = value.topics.first().map(|t| H256::from_slice(t)).unwrap_or_default();
+                                    logs.push((keys::TransactionLog(addr, topic0, block, txn_index, log_index), value));
+                                }
+                                Ok(_) => (),
+                                Err(e) => result = Err(e),
+                            }
+                        }
+                    });
+            }
+        }
+        result?;
+
+        // Layers are not ordered.
+        logs.sort_by_key(|(key, _)| (key.2, key.3, key.4));
+        Ok(logs)
     }
 
     pub fn init_genesis(&mut self, genesis: &GenesisConfig, chain: &ChainConfig) -> Result<(), BoxError> {
+        // Migrations are already applied when opening.
         if let Some(db_ver) = self.get(&keys::DynamicProperty::DbVersion)? {
-            // TODO: check migration here
-            let latest_block_hash = self.must_get(&keys::LatestBlockHash);
-            let latest_block_numer = self.must_get(&DynamicProperty::LatestBlockNumber);
+            let latest_block_hash = self.must_get(&keys::LatestBlockHash)?;
+            let latest_block_numer = self.must_get(&DynamicProperty::LatestBlockNumber)?;
             info!(
                 "state-db is already inited, db version: {}, block number: {}, block hash: {:?}",
                 db_ver, latest_block_numer, latest_block_hash
@@ -744,20 +1230,178 @@ impl ReadOnlySolidStateDB {
             DB::open_as_secondary_with_column_families(&db_options, db_path, tmp_path, column_families).unwrap();
 
         StateDB {
-            db: OverlayDB::new(db),
-            cols,
+            db: OverlayDB::new(RocksDB { db, cols }),
         }
     }
 
-    pub fn get<T, K: keys::Key<T>>(&self, key: &K) -> Result<Option<T>, BoxError> {
-        self.db
-            .get_cf(ReadOptions::default_instance(), &self.cols[K::COL], key.key().as_ref())
-            .map(|raw| Some(K::parse_value(&raw)))
-            .or_else(|e| if e.is_not_found() { Ok(None) } else { Err(e) })
-            .map_err(|e| e.into())
+    pub fn get<T, K: keys::Key<T>>(&self, key: &K) -> Result<Option<T>, StateError> {
+        let raw_key = key.key();
+        match self
+            .db
+            .get_cf(ReadOptions::default_instance(), &self.cols[K::COL], raw_key.as_ref())
+        {
+            Ok(raw) => parse_value::<T, K>(raw_key.as_ref(), &raw).map(Some),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string()).into()),
+        }
     }
 
     pub fn catch_up_with_primary(&self) {
         let _ = self.db.try_catch_up_with_primary();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_entry(addr: &Address, topic0: &H256, data: u8) -> state_pb::TransactionLog {
+        state_pb::TransactionLog {
+            address: addr.as_bytes().to_vec(),
+            topics: vec![topic0.as_bytes().to_vec()],
+            data: vec![data],
+        }
+    }
+
+    #[test]
+    fn test_transaction_log_index() {
+        let mut db = StateDB::new_in_memory();
+        let addr = *Address::from_bytes(&[0x41; 21]);
+        let other_addr = *Address::from_bytes(&[0x42; 21]);
+        let transfer = H256::repeat_byte(0xdd);
+        let approval = H256::repeat_byte(0x8c);
+        let all_logs = |db: &StateDB<MemoryDB>, addr: &Address, topic0: Option<&H256>, from_block, to_block| {
+            db.transaction_logs(addr, topic0, from_block, to_block, usize::MAX, |_| true)
+                .unwrap()
+        };
+
+        db.new_layer();
+        for &(block, txn_index) in &[(1, 0), (2, 1), (3, 0)] {
+            let key = keys::TransactionLog(addr, transfer, block, txn_index, 0);
+            db.put_transaction_log(key, log_entry(&addr, &transfer, block as u8))
+                .unwrap();
+        }
+        db.solidify_layer().unwrap();
+
+        // Unsolidified writes in a layer must be visible, merged with solidified ones.
+        db.new_layer();
+        db.put_transaction_log(
+            keys::TransactionLog(addr, approval, 2, 0, 1),
+            log_entry(&addr, &approval, 0xff),
+        )
+        .unwrap();
+        db.put_transaction_log(
+            keys::TransactionLog(other_addr, transfer, 2, 0, 0),
+            log_entry(&other_addr, &transfer, 0xee),
+        )
+        .unwrap();
+
+        let logs = all_logs(&db, &addr, Some(&transfer), 2, 3);
+        let blocks: Vec<_> = logs.iter().map(|(key, _)| (key.2, key.3)).collect();
+        assert_eq!(blocks, vec![(2, 1), (3, 0)]);
+        assert_eq!(logs[0].1.data, vec![2]);
+
+        // All topics of the address, ordered by position in chain.
+        let logs = all_logs(&db, &addr, None, 0, 10);
+        let positions: Vec<_> = logs.iter().map(|(key, _)| (key.2, key.3, key.4)).collect();
+        assert_eq!(positions, vec![(1, 0, 0), (2, 0, 1), (2, 1, 0), (3, 0, 0)]);
+        assert_eq!(logs[1].0 .1, approval);
+
+        assert_eq!(all_logs(&db, &addr, None, 2, 2).len(), 2);
+        assert!(all_logs(&db, &other_addr, Some(&transfer), 3, 3).is_empty());
+        assert!(all_logs(&db, &other_addr, Some(&approval), 0, 10).is_empty());
+        assert_eq!(all_logs(&db, &other_addr, None, 0, 10).len(), 1);
+
+        // Unbounded, inverted and negative ranges.
+        assert_eq!(all_logs(&db, &addr, None, 3, i64::MAX).len(), 1);
+        assert_eq!(all_logs(&db, &addr, Some(&transfer), 3, i64::MAX).len(), 1);
+        assert!(all_logs(&db, &addr, None, 3, 2).is_empty());
+        assert!(all_logs(&db, &addr, Some(&transfer), -10, -1).is_empty());
+        assert_eq!(all_logs(&db, &addr, None, i64::MIN, i64::MAX).len(), 4);
+
+        // Filtered, then limited.
+        let logs = db
+            .transaction_logs(&addr, None, 0, 10, 10, |log| log.data != vec![0xff])
+            .unwrap();
+        assert_eq!(logs.len(), 3);
+        let logs = db.transaction_logs(&addr, None, 0, 10, 2, |_| true).unwrap();
+        assert_eq!(logs.len(), 2);
+    }
+
+    #[test]
+    fn test_typed_errors() {
+        let mut db = StateDB::new_in_memory();
+        let addr = *Address::from_bytes(&[0x41; 21]);
+        let other_addr = *Address::from_bytes(&[0x42; 21]);
+
+        let err = db.must_get(&keys::Account(addr)).unwrap_err();
+        assert!(err.is_not_found());
+        assert_eq!(db.get(&keys::Account(addr)).unwrap(), None);
+
+        db.new_layer();
+        db.put_key(keys::Account(addr), state_pb::Account::default()).unwrap();
+        db.put_raw(COL_ACCOUNT, other_addr.as_bytes(), b"\xff\xff").unwrap();
+        db.put_raw(COL_CONTRACT_STORAGE, &[0x41; 21 + 32], &[0; 20]).unwrap();
+
+        match db.get(&keys::Account(other_addr)) {
+            Err(StateError::Corrupt { col, key }) => {
+                assert_eq!(col, "account");
+                assert_eq!(key, other_addr.as_bytes());
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(db.get(&keys::Account(addr)).unwrap().is_some());
+        // Visiting stops at the corrupted value.
+        assert!(matches!(
+            db.for_each(|_: &keys::Account, _: &state_pb::Account| ()),
+            Err(StateError::Corrupt { col: "account", .. })
+        ));
+        assert!(matches!(
+            db.contract_storage_entries(&addr, None, 10),
+            Err(StateError::Corrupt {
+                col: "contract-storage",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_contract_storage_entries() {
+        let mut db = StateDB::new_in_memory();
+        let addr = *Address::from_bytes(&[0x41; 21]);
+        let other_addr = *Address::from_bytes(&[0x42; 21]);
+        let slot = |i: u8| H256::repeat_byte(i);
+
+        db.new_layer();
+        for i in &[1, 3, 5, 7] {
+            db.put_key(keys::ContractStorage(addr, slot(*i)), slot(*i)).unwrap();
+        }
+        db.put_key(keys::ContractStorage(other_addr, slot(2)), slot(2)).unwrap();
+        db.solidify_layer().unwrap();
+
+        // Unsolidified changes are merged with solidified ones.
+        db.new_layer();
+        db.put_key(keys::ContractStorage(addr, slot(4)), slot(4)).unwrap();
+        db.put_key(keys::ContractStorage(addr, slot(0xff)), slot(0xff)).unwrap();
+        db.delete_key(&keys::ContractStorage(addr, slot(5))).unwrap();
+
+        let slots = |entries: Vec<(H256, H256)>| entries.into_iter().map(|(slot, _)| slot).collect::<Vec<_>>();
+
+        let page = db.contract_storage_entries(&addr, None, 2).unwrap();
+        assert_eq!(page, vec![(slot(1), slot(1)), (slot(3), slot(3))]);
+        let page = db.contract_storage_entries(&addr, Some(&slot(3)), 2).unwrap();
+        assert_eq!(slots(page), vec![slot(4), slot(7)]);
+        let page = db.contract_storage_entries(&addr, Some(&slot(7)), 2).unwrap();
+        assert_eq!(slots(page), vec![slot(0xff)]);
+        let page = db.contract_storage_entries(&addr, Some(&slot(0xff)), 2).unwrap();
+        assert!(page.is_empty());
+
+        // Not an existing slot.
+        let page = db.contract_storage_entries(&addr, Some(&slot(2)), 10).unwrap();
+        assert_eq!(slots(page), vec![slot(3), slot(4), slot(7), slot(0xff)]);
+        assert!(db.contract_storage_entries(&addr, None, 0).unwrap().is_empty());
+
+        let page = db.contract_storage_entries(&other_addr, None, 10).unwrap();
+        assert_eq!(slots(page), vec![slot(2)]);
+    }
+}
@@ -0,0 +1,71 @@
+//! State DB errors.
+use std::fmt;
+use std::io;
+
+/// State DB errors.
+#[derive(Debug)]
+pub enum StateError {
+    /// A key that must exist is missing.
+    NotFound { col: &'static str, key: Vec<u8> },
+    /// A value can not be decoded.
+    Corrupt { col: &'static str, key: Vec<u8> },
+    /// Underlying storage error.
+    Io(io::Error),
+    /// DB version can not be migrated to the expected version.
+    Incompatible { found: i64, expected: i64 },
+}
+
+impl StateError {
+    /// Is a missing key.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, StateError::NotFound { .. })
+    }
+}
+
+fn hex_key(key: &[u8]) -> String {
+    key.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StateError::NotFound { col, ref key } => write!(f, "key not found, cf={} key={}", col, hex_key(key)),
+            StateError::Corrupt { col, ref key } => write!(f, "corrupted value, cf={} key={}", col, hex_key(key)),
+            StateError::Io(ref e) => write!(f, "state-db io error: {}", e),
+            StateError::Incompatible { found, expected } => {
+                write!(f, "incompatible state-db version {}, expected {}", found, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl From<io::Error> for StateError {
+    fn from(e: io::Error) -> Self {
+        StateError::Io(e)
+    }
+}
+
+/// Actuators use `String` as error type.
+impl From<StateError> for String {
+    fn from(e: StateError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Malformed raw value, returned by `Key::parse_value`.
+#[derive(Debug)]
+pub struct MalformedValue(pub String);
+
+impl fmt::Display for MalformedValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<prost::DecodeError> for MalformedValue {
+    fn from(e: prost::DecodeError) -> Self {
+        MalformedValue(e.to_string())
+    }
+}
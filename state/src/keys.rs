@@ -9,6 +9,7 @@ use primitive_types::H256;
 use prost::Message;
 use proto::state as pb;
 
+pub use super::error::MalformedValue;
 pub use super::parameter::ChainParameter;
 pub use super::property::DynamicProperty;
 
@@ -25,7 +26,7 @@ pub trait Key<T>: Sized {
     fn value<'a>(val: &'a T) -> Cow<'a, [u8]>;
 
     /// Parse db value.
-    fn parse_value(raw: &[u8]) -> T;
+    fn parse_value(raw: &[u8]) -> Result<T, MalformedValue>;
 
     /// Parse db key.
     fn parse_key(_raw: &[u8]) -> Option<Self> {
@@ -33,6 +34,13 @@ pub trait Key<T>: Sized {
     }
 }
 
+fn parse_i64(raw: &[u8]) -> Result<i64, MalformedValue> {
+    if raw.len() != 8 {
+        return Err(MalformedValue(format!("malformed i64 value of {} bytes", raw.len())));
+    }
+    Ok(BE::read_u64(raw) as _)
+}
+
 impl Key<i64> for ChainParameter {
     type Target = Vec<u8>;
     const COL: usize = super::db::COL_DEFAULT;
@@ -47,12 +55,12 @@ impl Key<i64> for ChainParameter {
         Cow::Owned(val.to_be_bytes().to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> i64 {
-        BE::read_u64(raw) as _
+    fn parse_value(raw: &[u8]) -> Result<i64, MalformedValue> {
+        parse_i64(raw)
     }
 
     fn parse_key(raw: &[u8]) -> Option<Self> {
-        if raw[0] == b'p' {
+        if raw.len() == 9 && raw[0] == b'p' {
             ChainParameter::from_i32(BE::read_u64(&raw[1..]) as i32)
         } else {
             None
@@ -72,8 +80,8 @@ impl Key<i64> for DynamicProperty {
         Cow::Owned(val.to_be_bytes().to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> i64 {
-        BE::read_u64(raw) as _
+    fn parse_value(raw: &[u8]) -> Result<i64, MalformedValue> {
+        parse_i64(raw)
     }
 }
 
@@ -100,19 +108,16 @@ impl Key<Vec<(Address, i64, u8)>> for WitnessSchedule {
             .into()
     }
 
-    fn parse_value(raw: &[u8]) -> Vec<(Address, i64, u8)> {
+    fn parse_value(raw: &[u8]) -> Result<Vec<(Address, i64, u8)>, MalformedValue> {
         if raw.len() % (21 + 1 + 8) != 0 {
-            panic!("malformed kWitnessSchedule");
+            return Err(MalformedValue("malformed kWitnessSchedule".into()));
         }
         raw.chunks(30)
             .map(|wit| {
                 let mut raw_num = [0u8; 8];
                 raw_num.copy_from_slice(&wit[21..29]);
-                (
-                    Address::try_from(&wit[..21]).unwrap(),
-                    i64::from_be_bytes(raw_num),
-                    wit[29],
-                )
+                let addr = Address::try_from(&wit[..21]).map_err(|e| MalformedValue(e.to_string()))?;
+                Ok((addr, i64::from_be_bytes(raw_num), wit[29]))
             })
             .collect()
     }
@@ -134,11 +139,11 @@ impl Key<H256> for LatestBlockHash {
         val.as_bytes().into()
     }
 
-    fn parse_value(raw: &[u8]) -> H256 {
+    fn parse_value(raw: &[u8]) -> Result<H256, MalformedValue> {
         if raw.len() != 32 {
-            panic!("malformed kLatestBlockHash");
+            return Err(MalformedValue("malformed kLatestBlockHash".into()));
         }
-        H256::from_slice(raw)
+        Ok(H256::from_slice(raw))
     }
 }
 
@@ -159,8 +164,8 @@ impl Key<Vec<u8>> for BlockFilledSlots {
         (&val[..]).into()
     }
 
-    fn parse_value(raw: &[u8]) -> Vec<u8> {
-        raw.to_vec()
+    fn parse_value(raw: &[u8]) -> Result<Vec<u8>, MalformedValue> {
+        Ok(raw.to_vec())
     }
 }
 
@@ -181,8 +186,8 @@ impl Key<pb::Witness> for Witness {
         Cow::from(buf.to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> pb::Witness {
-        pb::Witness::decode(raw).unwrap()
+    fn parse_value(raw: &[u8]) -> Result<pb::Witness, MalformedValue> {
+        Ok(pb::Witness::decode(raw)?)
     }
 
     fn parse_key(raw: &[u8]) -> Option<Self> {
@@ -212,8 +217,55 @@ impl Key<pb::WitnessVoterReward> for VoterReward {
         Cow::from(buf.to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> pb::WitnessVoterReward {
-        pb::WitnessVoterReward::decode(raw).unwrap()
+    fn parse_value(raw: &[u8]) -> Result<pb::WitnessVoterReward, MalformedValue> {
+        Ok(pb::WitnessVoterReward::decode(raw)?)
+    }
+
+    fn parse_key(raw: &[u8]) -> Option<Self> {
+        if raw.len() != 8 + 20 {
+            return None;
+        }
+        Some(VoterReward(
+            BE::read_u64(&raw[..8]) as _,
+            Address::from_tvm_bytes(&raw[8..]),
+        ))
+    }
+}
+
+/// Block producing performance of a witness in one epoch.
+/// `<<Address, epoch: i64>> => WitnessPerformance`
+#[derive(Debug)]
+pub struct WitnessPerformance(pub Address, pub i64);
+
+impl Key<pb::WitnessPerformance> for WitnessPerformance {
+    type Target = Vec<u8>;
+    const COL: usize = super::db::COL_WITNESS_PERFORMANCE;
+
+    fn key(&self) -> Self::Target {
+        let mut raw = Vec::with_capacity(21 + 8);
+        raw.extend_from_slice(self.0.as_bytes());
+        raw.extend_from_slice(&self.1.to_be_bytes()[..]);
+        raw
+    }
+
+    fn value(val: &pb::WitnessPerformance) -> Cow<[u8]> {
+        let mut buf = BytesMut::with_capacity(val.encoded_len());
+        val.encode(&mut buf).unwrap();
+        Cow::from(buf.to_vec())
+    }
+
+    fn parse_value(raw: &[u8]) -> Result<pb::WitnessPerformance, MalformedValue> {
+        Ok(pb::WitnessPerformance::decode(raw)?)
+    }
+
+    fn parse_key(raw: &[u8]) -> Option<Self> {
+        if raw.len() != 21 + 8 {
+            return None;
+        }
+        Some(WitnessPerformance(
+            *Address::from_bytes(&raw[..21]),
+            BE::read_u64(&raw[21..]) as _,
+        ))
     }
 }
 
@@ -234,8 +286,8 @@ impl Key<pb::Account> for Account {
         Cow::from(buf.to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> pb::Account {
-        pb::Account::decode(raw).unwrap()
+    fn parse_value(raw: &[u8]) -> Result<pb::Account, MalformedValue> {
+        Ok(pb::Account::decode(raw)?)
     }
 
     fn parse_key(raw: &[u8]) -> Option<Self> {
@@ -258,8 +310,8 @@ impl Key<Address> for AccountIndex {
         Cow::from(val.as_bytes())
     }
 
-    fn parse_value(raw: &[u8]) -> Address {
-        *Address::from_bytes(raw)
+    fn parse_value(raw: &[u8]) -> Result<Address, MalformedValue> {
+        Address::try_from(raw).map_err(|e| MalformedValue(e.to_string()))
     }
 }
 
@@ -281,8 +333,8 @@ impl Key<pb::ResourceDelegation> for ResourceDelegation {
         Cow::from(buf.to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> pb::ResourceDelegation {
-        pb::ResourceDelegation::decode(raw).unwrap()
+    fn parse_value(raw: &[u8]) -> Result<pb::ResourceDelegation, MalformedValue> {
+        Ok(pb::ResourceDelegation::decode(raw)?)
     }
 }
 
@@ -306,14 +358,14 @@ impl Key<Vec<Address>> for ResourceDelegationIndex {
             .into()
     }
 
-    fn parse_value(raw: &[u8]) -> Vec<Address> {
+    fn parse_value(raw: &[u8]) -> Result<Vec<Address>, MalformedValue> {
         if raw.len() % 21 != 0 {
-            panic!("malformed ResourceDelegationIndex db")
+            return Err(MalformedValue("malformed ResourceDelegationIndex db".into()));
         }
         raw.chunks(21)
             .map(Address::try_from)
             .collect::<Result<Vec<_>, _>>()
-            .unwrap()
+            .map_err(|e| MalformedValue(e.to_string()))
     }
 }
 
@@ -335,8 +387,8 @@ impl Key<pb::Votes> for Votes {
         Cow::from(buf.to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> pb::Votes {
-        pb::Votes::decode(raw).unwrap()
+    fn parse_value(raw: &[u8]) -> Result<pb::Votes, MalformedValue> {
+        Ok(pb::Votes::decode(raw)?)
     }
 }
 
@@ -358,8 +410,8 @@ impl Key<pb::SmartContract> for Contract {
         Cow::from(buf.to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> pb::SmartContract {
-        pb::SmartContract::decode(raw).unwrap()
+    fn parse_value(raw: &[u8]) -> Result<pb::SmartContract, MalformedValue> {
+        Ok(pb::SmartContract::decode(raw)?)
     }
 }
 
@@ -379,8 +431,8 @@ impl Key<Vec<u8>> for ContractCode {
         Cow::Borrowed(val)
     }
 
-    fn parse_value(raw: &[u8]) -> Vec<u8> {
-        raw.to_vec()
+    fn parse_value(raw: &[u8]) -> Result<Vec<u8>, MalformedValue> {
+        Ok(raw.to_vec())
     }
 }
 
@@ -400,11 +452,17 @@ impl Key<H256> for ContractStorage {
         Cow::Borrowed(val.as_bytes())
     }
 
-    fn parse_value(raw: &[u8]) -> H256 {
-        H256::from_slice(raw)
+    fn parse_value(raw: &[u8]) -> Result<H256, MalformedValue> {
+        if raw.len() != 32 {
+            return Err(MalformedValue("malformed storage value".into()));
+        }
+        Ok(H256::from_slice(raw))
     }
 
     fn parse_key(raw: &[u8]) -> Option<Self> {
+        if raw.len() != 21 + 32 {
+            return None;
+        }
         let addr = *Address::from_bytes(&raw[..21]);
         let index = H256::from_slice(&raw[21..]);
         Some(ContractStorage(addr, index))
@@ -428,8 +486,8 @@ impl Key<pb::Proposal> for Proposal {
         Cow::from(buf.to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> pb::Proposal {
-        pb::Proposal::decode(raw).unwrap()
+    fn parse_value(raw: &[u8]) -> Result<pb::Proposal, MalformedValue> {
+        Ok(pb::Proposal::decode(raw)?)
     }
 }
 
@@ -451,11 +509,14 @@ impl Key<pb::Asset> for Asset {
         Cow::from(buf.to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> pb::Asset {
-        pb::Asset::decode(raw).unwrap()
+    fn parse_value(raw: &[u8]) -> Result<pb::Asset, MalformedValue> {
+        Ok(pb::Asset::decode(raw)?)
     }
 
     fn parse_key(raw: &[u8]) -> Option<Self> {
+        if raw.len() != 8 {
+            return None;
+        }
         let mut bytes = [0u8; 8];
         bytes.copy_from_slice(raw);
         Some(Asset(u64::from_be_bytes(bytes) as i64))
@@ -480,17 +541,232 @@ impl Key<pb::Exchange> for Exchange {
         Cow::from(buf.to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> pb::Exchange {
-        pb::Exchange::decode(raw).unwrap()
+    fn parse_value(raw: &[u8]) -> Result<pb::Exchange, MalformedValue> {
+        Ok(pb::Exchange::decode(raw)?)
     }
 
     fn parse_key(raw: &[u8]) -> Option<Self> {
+        if raw.len() != 8 {
+            return None;
+        }
         let mut bytes = [0u8; 8];
         bytes.copy_from_slice(raw);
         Some(Exchange(u64::from_be_bytes(bytes) as i64))
     }
 }
 
+/// `<<order_id: H256>> => MarketOrder`
+#[derive(Debug)]
+pub struct MarketOrder(pub H256);
+
+impl Key<pb::MarketOrder> for MarketOrder {
+    type Target = Vec<u8>;
+    const COL: usize = super::db::COL_MARKET_ORDER;
+
+    fn key(&self) -> Self::Target {
+        self.0.as_bytes().to_vec()
+    }
+
+    fn value(val: &pb::MarketOrder) -> Cow<[u8]> {
+        let mut buf = BytesMut::with_capacity(val.encoded_len());
+        val.encode(&mut buf).unwrap();
+        Cow::from(buf.to_vec())
+    }
+
+    fn parse_value(raw: &[u8]) -> Result<pb::MarketOrder, MalformedValue> {
+        Ok(pb::MarketOrder::decode(raw)?)
+    }
+}
+
+/// `<<Address>> => MarketAccountOrders`
+#[derive(Debug)]
+pub struct MarketAccount(pub Address);
+
+impl Key<pb::MarketAccountOrders> for MarketAccount {
+    type Target = Vec<u8>;
+    const COL: usize = super::db::COL_MARKET_ACCOUNT;
+
+    fn key(&self) -> Self::Target {
+        self.0.as_bytes().to_vec()
+    }
+
+    fn value(val: &pb::MarketAccountOrders) -> Cow<[u8]> {
+        let mut buf = BytesMut::with_capacity(val.encoded_len());
+        val.encode(&mut buf).unwrap();
+        Cow::from(buf.to_vec())
+    }
+
+    fn parse_value(raw: &[u8]) -> Result<pb::MarketAccountOrders, MalformedValue> {
+        Ok(pb::MarketAccountOrders::decode(raw)?)
+    }
+}
+
+/// Prices of a token pair which have active orders, best price first.
+///
+/// `<<sell_token_id: i64, buy_token_id: i64>> => [(sell_quantity: i64, buy_quantity: i64)]`
+#[derive(Debug)]
+pub struct MarketPairPrices(pub i64, pub i64);
+
+impl Key<Vec<(i64, i64)>> for MarketPairPrices {
+    type Target = Vec<u8>;
+    const COL: usize = super::db::COL_MARKET_ORDER_BOOK;
+
+    fn key(&self) -> Self::Target {
+        [self.0.to_be_bytes(), self.1.to_be_bytes()].concat()
+    }
+
+    fn value(val: &Vec<(i64, i64)>) -> Cow<[u8]> {
+        let mut raw = Vec::with_capacity(val.len() * 16);
+        for (sell_quantity, buy_quantity) in val {
+            raw.extend_from_slice(&sell_quantity.to_be_bytes());
+            raw.extend_from_slice(&buy_quantity.to_be_bytes());
+        }
+        raw.into()
+    }
+
+    fn parse_value(raw: &[u8]) -> Result<Vec<(i64, i64)>, MalformedValue> {
+        if raw.len() % 16 != 0 {
+            return Err(MalformedValue("malformed MarketPairPrices db".into()));
+        }
+        Ok(raw
+            .chunks(16)
+            .map(|chunk| (BE::read_u64(&chunk[..8]) as i64, BE::read_u64(&chunk[8..]) as i64))
+            .collect())
+    }
+}
+
+/// Active orders of a price, in placing order. Price is normalized by GCD.
+///
+/// `<<sell_token_id: i64, buy_token_id: i64, sell_quantity: i64, buy_quantity: i64>> => [order_id: H256]`
+#[derive(Debug)]
+pub struct MarketPriceOrders(pub i64, pub i64, pub i64, pub i64);
+
+impl Key<Vec<H256>> for MarketPriceOrders {
+    type Target = Vec<u8>;
+    const COL: usize = super::db::COL_MARKET_ORDER_BOOK;
+
+    fn key(&self) -> Self::Target {
+        [
+            self.0.to_be_bytes(),
+            self.1.to_be_bytes(),
+            self.2.to_be_bytes(),
+            self.3.to_be_bytes(),
+        ]
+        .concat()
+    }
+
+    fn value(val: &Vec<H256>) -> Cow<[u8]> {
+        val.iter()
+            .map(|order_id| order_id.as_bytes())
+            .collect::<Vec<_>>()
+            .concat()
+            .into()
+    }
+
+    fn parse_value(raw: &[u8]) -> Result<Vec<H256>, MalformedValue> {
+        if raw.len() % 32 != 0 {
+            return Err(MalformedValue("malformed MarketPriceOrders db".into()));
+        }
+        Ok(raw.chunks(32).map(H256::from_slice).collect())
+    }
+}
+
+/// PBFT commit certificate of a confirmed block.
+///
+/// `<<block_number: i64>> => PbftCommitResult`
+#[derive(Debug)]
+pub struct PbftCommit(pub i64);
+
+impl Key<pb::PbftCommitResult> for PbftCommit {
+    type Target = Vec<u8>;
+    const COL: usize = super::db::COL_PBFT_COMMIT;
+
+    fn key(&self) -> Self::Target {
+        (self.0 as u64).to_be_bytes().to_vec()
+    }
+
+    fn value(val: &pb::PbftCommitResult) -> Cow<[u8]> {
+        let mut buf = BytesMut::with_capacity(val.encoded_len());
+        val.encode(&mut buf).unwrap();
+        Cow::from(buf.to_vec())
+    }
+
+    fn parse_value(raw: &[u8]) -> Result<pb::PbftCommitResult, MalformedValue> {
+        Ok(pb::PbftCommitResult::decode(raw)?)
+    }
+}
+
+/// Incremental note commitment tree of shielded TRC10 transfers, serialized by `ztron`.
+#[derive(Debug)]
+pub struct ShieldedNoteCommitmentTree;
+
+impl Key<Vec<u8>> for ShieldedNoteCommitmentTree {
+    type Target = &'static str;
+    const COL: usize = super::db::COL_DEFAULT;
+
+    // Same as DynamicProperty
+    fn key(&self) -> Self::Target {
+        "kShieldedNoteCommitmentTree"
+    }
+
+    fn value(val: &Vec<u8>) -> Cow<[u8]> {
+        (&val[..]).into()
+    }
+
+    fn parse_value(raw: &[u8]) -> Result<Vec<u8>, MalformedValue> {
+        Ok(raw.to_vec())
+    }
+}
+
+/// A spent note, by the spending transaction.
+///
+/// `<<nullifier: H256>> => txn_hash: H256`
+#[derive(Debug)]
+pub struct ShieldedNullifier(pub H256);
+
+impl Key<H256> for ShieldedNullifier {
+    type Target = Vec<u8>;
+    const COL: usize = super::db::COL_SHIELDED_NULLIFIER;
+
+    fn key(&self) -> Self::Target {
+        self.0.as_bytes().to_vec()
+    }
+
+    fn value(val: &H256) -> Cow<[u8]> {
+        val.as_bytes().into()
+    }
+
+    fn parse_value(raw: &[u8]) -> Result<H256, MalformedValue> {
+        if raw.len() != 32 {
+            return Err(MalformedValue("malformed nullifier transaction hash".into()));
+        }
+        Ok(H256::from_slice(raw))
+    }
+}
+
+/// Root of the note commitment tree at the end of a block.
+///
+/// `<<root: H256>> => block_number: i64`
+#[derive(Debug)]
+pub struct ShieldedAnchor(pub H256);
+
+impl Key<i64> for ShieldedAnchor {
+    type Target = Vec<u8>;
+    const COL: usize = super::db::COL_SHIELDED_ANCHOR;
+
+    fn key(&self) -> Self::Target {
+        self.0.as_bytes().to_vec()
+    }
+
+    fn value(val: &i64) -> Cow<[u8]> {
+        Cow::Owned(val.to_be_bytes().to_vec())
+    }
+
+    fn parse_value(raw: &[u8]) -> Result<i64, MalformedValue> {
+        parse_i64(raw)
+    }
+}
+
 #[derive(Debug)]
 pub struct TransactionReceipt(pub H256);
 
@@ -508,8 +784,8 @@ impl Key<pb::TransactionReceipt> for TransactionReceipt {
         Cow::from(buf.to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> pb::TransactionReceipt {
-        pb::TransactionReceipt::decode(raw).unwrap()
+    fn parse_value(raw: &[u8]) -> Result<pb::TransactionReceipt, MalformedValue> {
+        Ok(pb::TransactionReceipt::decode(raw)?)
     }
 }
 
@@ -530,24 +806,71 @@ impl Key<pb::InternalTransaction> for InternalTransaction {
         Cow::from(buf.to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> pb::InternalTransaction {
-        pb::InternalTransaction::decode(raw).unwrap()
+    fn parse_value(raw: &[u8]) -> Result<pb::InternalTransaction, MalformedValue> {
+        Ok(pb::InternalTransaction::decode(raw)?)
     }
 }
 
+/// Log index of contracts, by the first topic.
+/// `<<address: Address, topic0: H256, block_number: i64, txn_index: i32, log_index: i32>> => TransactionLog`
+///
+/// Logs without topics are indexed under a zero topic0.
 #[derive(Debug)]
-pub struct TransactionLog(pub Address, Vec<H256>);
+pub struct TransactionLog(pub Address, pub H256, pub i64, pub i32, pub i32);
 
 impl Key<pb::TransactionLog> for TransactionLog {
     type Target = Vec<u8>;
     const COL: usize = super::db::COL_TRANSACTION_LOG;
 
     fn key(&self) -> Self::Target {
-        let mut raw = Vec::with_capacity(21 + 32 * self.1.len());
-        raw.extend_from_slice(self.0.as_bytes());
-        for topic in &self.1 {
-            raw.extend_from_slice(topic.as_bytes())
+        let mut raw = vec![0u8; 21 + 32 + 8 + 4 + 4];
+        raw[..21].copy_from_slice(self.0.as_bytes());
+        raw[21..53].copy_from_slice(self.1.as_bytes());
+        raw[53..61].copy_from_slice(&self.2.to_be_bytes()[..]);
+        raw[61..65].copy_from_slice(&self.3.to_be_bytes()[..]);
+        raw[65..].copy_from_slice(&self.4.to_be_bytes()[..]);
+        raw
+    }
+
+    fn value(val: &pb::TransactionLog) -> Cow<[u8]> {
+        let mut buf = BytesMut::with_capacity(val.encoded_len());
+        val.encode(&mut buf).unwrap();
+        Cow::from(buf.to_vec())
+    }
+
+    fn parse_value(raw: &[u8]) -> Result<pb::TransactionLog, MalformedValue> {
+        Ok(pb::TransactionLog::decode(raw)?)
+    }
+
+    fn parse_key(raw: &[u8]) -> Option<Self> {
+        if raw.len() != 21 + 32 + 8 + 4 + 4 {
+            return None;
         }
+        Some(TransactionLog(
+            *Address::from_bytes(&raw[..21]),
+            H256::from_slice(&raw[21..53]),
+            BE::read_u64(&raw[53..61]) as _,
+            BE::read_u32(&raw[61..65]) as _,
+            BE::read_u32(&raw[65..]) as _,
+        ))
+    }
+}
+
+/// Secondary index of transaction logs, for queries without topic0.
+/// `<<address: Address, block_number: i64, txn_index: i32, log_index: i32>> => TransactionLog`
+#[derive(Debug)]
+pub struct TransactionLogByBlock(pub Address, pub i64, pub i32, pub i32);
+
+impl Key<pb::TransactionLog> for TransactionLogByBlock {
+    type Target = Vec<u8>;
+    const COL: usize = super::db::COL_TRANSACTION_LOG_BY_BLOCK;
+
+    fn key(&self) -> Self::Target {
+        let mut raw = vec![0u8; 21 + 8 + 4 + 4];
+        raw[..21].copy_from_slice(self.0.as_bytes());
+        raw[21..29].copy_from_slice(&self.1.to_be_bytes()[..]);
+        raw[29..33].copy_from_slice(&self.2.to_be_bytes()[..]);
+        raw[33..].copy_from_slice(&self.3.to_be_bytes()[..]);
         raw
     }
 
@@ -557,7 +880,19 @@ impl Key<pb::TransactionLog> for TransactionLog {
         Cow::from(buf.to_vec())
     }
 
-    fn parse_value(raw: &[u8]) -> pb::TransactionLog {
-        pb::TransactionLog::decode(raw).unwrap()
+    fn parse_value(raw: &[u8]) -> Result<pb::TransactionLog, MalformedValue> {
+        Ok(pb::TransactionLog::decode(raw)?)
+    }
+
+    fn parse_key(raw: &[u8]) -> Option<Self> {
+        if raw.len() != 21 + 8 + 4 + 4 {
+            return None;
+        }
+        Some(TransactionLogByBlock(
+            *Address::from_bytes(&raw[..21]),
+            BE::read_u64(&raw[21..29]) as _,
+            BE::read_u32(&raw[29..33]) as _,
+            BE::read_u32(&raw[33..]) as _,
+        ))
     }
 }
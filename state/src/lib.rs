@@ -1,7 +1,10 @@
+pub use error::StateError;
 pub use parameter::ChainParameter;
 pub use property::DynamicProperty;
 
 pub mod db;
+pub mod error;
 pub mod keys;
+pub mod migration;
 pub mod parameter;
 mod property;
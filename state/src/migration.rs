@@ -0,0 +1,347 @@
+//! State DB schema migrations.
+//!
+//! The schema version is stored as `DynamicProperty::DbVersion`. When a state-db is opened, all registered
+//! migrations between the stored version and `CURRENT_DB_VERSION` are applied in order.
+//!
+//! Each migration consists of steps. A step and its progress record (`DynamicProperty::DbMigrationStep`) are
+//! written in one batch, so an interrupted migration resumes from the first unfinished step.
+//!
+//! NOTE: New column families are created on open(`create_missing_column_families`), a step only needs to
+//! backfill them.
+use log::info;
+use proto::state::TransactionLog;
+
+use super::db::{KeyValueDB, StateDB};
+use super::error::StateError;
+use super::keys;
+use super::property::CURRENT_DB_VERSION;
+use super::{ChainParameter, DynamicProperty};
+
+/// A migration step, runs inside a fresh layer of the state-db, on any backend.
+pub type MigrationStep = fn(&mut StateDB<dyn KeyValueDB>) -> Result<(), StateError>;
+
+/// Migrates the state-db from `version - 1` to `version`.
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub steps: &'static [MigrationStep],
+}
+
+/// Registered migrations, ordered by version.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 2,
+        description: "add market chain parameters",
+        steps: &[add_market_parameters],
+    },
+    Migration {
+        version: 3,
+        description: "add pbft chain parameter and confirmed block number",
+        steps: &[add_pbft_properties],
+    },
+    Migration {
+        version: 4,
+        description: "add fee pool and blackhole optimization chain parameters",
+        steps: &[add_fee_properties],
+    },
+    Migration {
+        version: 5,
+        description: "add shielded pool value",
+        steps: &[add_shielded_pool_value],
+    },
+    Migration {
+        version: 6,
+        description: "start transaction log index",
+        steps: &[add_transaction_log_index_start],
+    },
+    Migration {
+        version: 7,
+        description: "index transaction logs by block",
+        steps: &[index_transaction_logs_by_block],
+    },
+];
+
+/// Run all pending migrations. A new db(without `DbVersion`) is left for `init_genesis`.
+pub fn migrate(db: &mut StateDB<dyn KeyValueDB>) -> Result<(), StateError> {
+    run_migrations(db, MIGRATIONS, CURRENT_DB_VERSION)
+}
+
+pub fn run_migrations(
+    db: &mut StateDB<dyn KeyValueDB>,
+    migrations: &[Migration],
+    target_version: i64,
+) -> Result<(), StateError> {
+    let mut db_ver = match db.get(&DynamicProperty::DbVersion)? {
+        Some(ver) => ver,
+        None => return Ok(()),
+    };
+    if db_ver > target_version {
+        return Err(StateError::Incompatible {
+            found: db_ver,
+            expected: target_version,
+        });
+    }
+
+    while db_ver < target_version {
+        let migration =
+            migrations
+                .iter()
+                .find(|m| m.version == db_ver + 1)
+                .ok_or_else(|| StateError::Incompatible {
+                    found: db_ver,
+                    expected: target_version,
+                })?;
+        let done = db.get(&DynamicProperty::DbMigrationStep)?.unwrap_or(0) as usize;
+        if done > 0 {
+            info!(
+                "resume state-db migration v{}: {}, {}/{} steps done",
+                migration.version,
+                migration.description,
+                done,
+                migration.steps.len()
+            );
+        } else {
+            info!("state-db migration v{}: {}", migration.version, migration.description);
+        }
+
+        for (i, step) in migration.steps.iter().enumerate().skip(done) {
+            db.new_layer();
+            let ret = step(db).and_then(|_| {
+                if i + 1 == migration.steps.len() {
+                    db.put_key(DynamicProperty::DbMigrationStep, 0)?;
+                    db.put_key(DynamicProperty::DbVersion, migration.version)
+                } else {
+                    db.put_key(DynamicProperty::DbMigrationStep, i as i64 + 1)
+                }
+            });
+            if let Err(e) = ret {
+                db.discard_last_layer()?;
+                return Err(e);
+            }
+            db.flush_layers()?;
+            info!("state-db migration v{} step {} done", migration.version, i + 1);
+        }
+        // A migration without steps only bumps the version.
+        if migration.steps.is_empty() {
+            db.new_layer();
+            db.put_key(DynamicProperty::DbVersion, migration.version)?;
+            db.flush_layers()?;
+        }
+        db_ver = migration.version;
+    }
+    Ok(())
+}
+
+/// Step helper: re-encode all values of a column family with the current `Key::value`.
+pub fn rewrite_column<T, K: keys::Key<T>>(db: &mut StateDB<dyn KeyValueDB>) -> Result<(), StateError> {
+    let mut entries = vec![];
+    db.for_each(|key: &K, value: &T| entries.push((key.key(), K::value(value).into_owned())))?;
+    for (key, value) in entries {
+        db.put_raw(K::COL, key.as_ref(), &value)?;
+    }
+    Ok(())
+}
+
+/// v2: Market parameters are missing in state-dbs initialized before market support.
+fn add_market_parameters(db: &mut StateDB<dyn KeyValueDB>) -> Result<(), StateError> {
+    for &param in &[
+        ChainParameter::AllowMarketTransaction,
+        ChainParameter::MarketSellFee,
+        ChainParameter::MarketCancelFee,
+    ] {
+        if db.get(&param)?.is_none() {
+            db.put_key(param, 0)?;
+        }
+    }
+    Ok(())
+}
+
+/// v3: PBFT parameter and property are missing in state-dbs initialized before PBFT support.
+fn add_pbft_properties(db: &mut StateDB<dyn KeyValueDB>) -> Result<(), StateError> {
+    if db.get(&ChainParameter::AllowPbft)?.is_none() {
+        db.put_key(ChainParameter::AllowPbft, 0)?;
+    }
+    if db.get(&DynamicProperty::LatestPbftBlockNumber)?.is_none() {
+        db.put_key(DynamicProperty::LatestPbftBlockNumber, 0)?;
+    }
+    Ok(())
+}
+
+/// v4: Fee related parameters and properties of 4.1.2 are missing in state-dbs initialized before.
+fn add_fee_properties(db: &mut StateDB<dyn KeyValueDB>) -> Result<(), StateError> {
+    for &(param, value) in &[
+        (ChainParameter::MaxFeeLimit, 1_000_000_000),
+        (ChainParameter::AllowTransactionFeePool, 0),
+        (ChainParameter::AllowBlackholeOptimization, 0),
+    ] {
+        if db.get(&param)?.is_none() {
+            db.put_key(param, value)?;
+        }
+    }
+    for &prop in &[DynamicProperty::BurnedTrx, DynamicProperty::TransactionFeePool] {
+        if db.get(&prop)?.is_none() {
+            db.put_key(prop, 0)?;
+        }
+    }
+    Ok(())
+}
+
+/// v5: Shielded pool value is missing in state-dbs initialized before shielded TRC10 support.
+///
+/// NOTE: Nile state-dbs synced with the former dummy shielded transfer implementation must be re-synced.
+fn add_shielded_pool_value(db: &mut StateDB<dyn KeyValueDB>) -> Result<(), StateError> {
+    if db.get(&DynamicProperty::TotalShieldedPoolValue)?.is_none() {
+        db.put_key(DynamicProperty::TotalShieldedPoolValue, 0)?;
+    }
+    Ok(())
+}
+
+/// v6: Transaction logs of already applied blocks are not indexed, the index starts from the next block.
+fn add_transaction_log_index_start(db: &mut StateDB<dyn KeyValueDB>) -> Result<(), StateError> {
+    if db.get(&DynamicProperty::TransactionLogIndexStart)?.is_none() {
+        let latest_block_number = db.get(&DynamicProperty::LatestBlockNumber)?.unwrap_or(-1);
+        db.put_key(DynamicProperty::TransactionLogIndexStart, latest_block_number + 1)?;
+    }
+    Ok(())
+}
+
+/// v7: Logs indexed by v6 are missing in the `TransactionLogByBlock` index.
+fn index_transaction_logs_by_block(db: &mut StateDB<dyn KeyValueDB>) -> Result<(), StateError> {
+    let mut logs = vec![];
+    db.for_each(|key: &keys::TransactionLog, log: &TransactionLog| {
+        logs.push((keys::TransactionLogByBlock(key.0, key.2, key.3, key.4), log.clone()))
+    })?;
+    for (key, log) in logs {
+        db.put_key(key, log)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::db::MemoryDB;
+    use super::*;
+
+    fn db_with_version(version: i64) -> StateDB<MemoryDB> {
+        let mut db = StateDB::new_in_memory();
+        db.new_layer();
+        db.put_key(DynamicProperty::DbVersion, version).unwrap();
+        db.put_key(DynamicProperty::LatestBlockNumber, 100).unwrap();
+        db.flush_layers().unwrap();
+        db
+    }
+
+    fn count_step(db: &mut StateDB<dyn KeyValueDB>) -> Result<(), StateError> {
+        let count = db.get(&DynamicProperty::BurnedTrx)?.unwrap_or(0);
+        db.put_key(DynamicProperty::BurnedTrx, count + 1)
+    }
+
+    fn requires_pbft_step(db: &mut StateDB<dyn KeyValueDB>) -> Result<(), StateError> {
+        db.must_get(&ChainParameter::AllowPbft)?;
+        db.put_key(DynamicProperty::LatestPbftBlockNumber, 1)
+    }
+
+    const TEST_MIGRATIONS: &[Migration] = &[Migration {
+        version: 2,
+        description: "test",
+        steps: &[count_step, requires_pbft_step, count_step],
+    }];
+
+    #[test]
+    fn test_fresh_db_is_left_for_genesis() {
+        let mut db = StateDB::new_in_memory();
+        migrate(&mut db).unwrap();
+        assert_eq!(db.get(&DynamicProperty::DbVersion).unwrap(), None);
+        assert_eq!(db.get(&ChainParameter::AllowMarketTransaction).unwrap(), None);
+        assert_eq!(db.num_layers(), 0);
+    }
+
+    #[test]
+    fn test_upgrade_from_v1() {
+        let mut db = db_with_version(1);
+        migrate(&mut db).unwrap();
+
+        assert_eq!(db.get(&DynamicProperty::DbVersion).unwrap(), Some(CURRENT_DB_VERSION));
+        assert_eq!(db.get(&DynamicProperty::DbMigrationStep).unwrap(), Some(0));
+        assert_eq!(db.get(&ChainParameter::AllowMarketTransaction).unwrap(), Some(0));
+        assert_eq!(db.get(&ChainParameter::AllowPbft).unwrap(), Some(0));
+        assert_eq!(db.get(&ChainParameter::MaxFeeLimit).unwrap(), Some(1_000_000_000));
+        assert_eq!(db.get(&DynamicProperty::TotalShieldedPoolValue).unwrap(), Some(0));
+        assert_eq!(db.get(&DynamicProperty::TransactionLogIndexStart).unwrap(), Some(101));
+        assert_eq!(db.num_layers(), 0);
+
+        // Already up to date.
+        migrate(&mut db).unwrap();
+        assert_eq!(db.get(&DynamicProperty::DbVersion).unwrap(), Some(CURRENT_DB_VERSION));
+    }
+
+    #[test]
+    fn test_index_transaction_logs_by_block() {
+        let mut db = db_with_version(6);
+        let addr = *::keys::Address::from_bytes(&[0x41; 21]);
+        let topic0 = primitive_types::H256::repeat_byte(0xdd);
+        let log = TransactionLog {
+            address: addr.as_bytes().to_vec(),
+            topics: vec![topic0.as_bytes().to_vec()],
+            data: vec![1],
+        };
+        db.new_layer();
+        db.put_key(keys::TransactionLog(addr, topic0, 5, 1, 0), log.clone())
+            .unwrap();
+        db.flush_layers().unwrap();
+
+        migrate(&mut db).unwrap();
+        assert_eq!(db.get(&keys::TransactionLogByBlock(addr, 5, 1, 0)).unwrap(), Some(log));
+        let logs = db.transaction_logs(&addr, None, 0, 10, 10, |_| true).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].0 .1, topic0);
+    }
+
+    #[test]
+    fn test_resume_partially_applied_migration() {
+        let mut db = db_with_version(1);
+
+        let err = run_migrations(&mut db, TEST_MIGRATIONS, 2).unwrap_err();
+        assert!(err.is_not_found());
+        // The first step is done, the failed one is discarded.
+        assert_eq!(db.get(&DynamicProperty::DbVersion).unwrap(), Some(1));
+        assert_eq!(db.get(&DynamicProperty::DbMigrationStep).unwrap(), Some(1));
+        assert_eq!(db.get(&DynamicProperty::BurnedTrx).unwrap(), Some(1));
+        assert_eq!(db.get(&DynamicProperty::LatestPbftBlockNumber).unwrap(), None);
+        assert_eq!(db.num_layers(), 0);
+
+        db.new_layer();
+        db.put_key(ChainParameter::AllowPbft, 0).unwrap();
+        db.flush_layers().unwrap();
+
+        run_migrations(&mut db, TEST_MIGRATIONS, 2).unwrap();
+        assert_eq!(db.get(&DynamicProperty::DbVersion).unwrap(), Some(2));
+        assert_eq!(db.get(&DynamicProperty::DbMigrationStep).unwrap(), Some(0));
+        // Finished steps are not re-run.
+        assert_eq!(db.get(&DynamicProperty::BurnedTrx).unwrap(), Some(2));
+        assert_eq!(db.get(&DynamicProperty::LatestPbftBlockNumber).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_refuse_newer_version() {
+        let mut db = db_with_version(CURRENT_DB_VERSION + 1);
+        match migrate(&mut db) {
+            Err(StateError::Incompatible { found, expected }) => {
+                assert_eq!(found, CURRENT_DB_VERSION + 1);
+                assert_eq!(expected, CURRENT_DB_VERSION);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(
+            db.get(&DynamicProperty::DbVersion).unwrap(),
+            Some(CURRENT_DB_VERSION + 1)
+        );
+
+        // No registered migration to the target version.
+        let mut db = db_with_version(2);
+        assert!(matches!(
+            run_migrations(&mut db, TEST_MIGRATIONS, 3),
+            Err(StateError::Incompatible { found: 2, expected: 3 })
+        ));
+    }
+}
@@ -46,6 +46,13 @@ pub fn default_parameters() -> impl IntoIterator<Item = (ChainParameter, i64)> {
         (AllowTvmShieldedUpgrade, 0),
         (AllowTvmIstanbulUpgrade, 0),
         (AllowProtoFilterNum, 0),
+        (AllowPbft, 0),
+        (AllowMarketTransaction, 0),
+        (MarketSellFee, 0),
+        (MarketCancelFee, 0),
+        (MaxFeeLimit, 1_000_000_000),
+        (AllowTransactionFeePool, 0),
+        (AllowBlackholeOptimization, 0),
     ];
 }
 
@@ -97,5 +104,12 @@ pub fn default_parameters_from_config(
         (AllowTvmShieldedUpgrade, config.allow_tvm_shielded_upgrade as i64),
         (AllowTvmIstanbulUpgrade, config.allow_tvm_istanbul_upgrade as i64),
         (AllowProtoFilterNum, 0),
+        (AllowPbft, 0),
+        (AllowMarketTransaction, 0),
+        (MarketSellFee, 0),
+        (MarketCancelFee, 0),
+        (MaxFeeLimit, 1_000_000_000),
+        (AllowTransactionFeePool, 0),
+        (AllowBlackholeOptimization, 0),
     ];
 }
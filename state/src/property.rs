@@ -3,13 +3,15 @@ use std::collections::HashMap;
 use super::ChainParameter;
 
 /// Used for DB migrations. Corresponding key is `DynamicProperty::DbVersion`.
-const CURRENT_DB_VERSION: i64 = 1;
+pub(crate) const CURRENT_DB_VERSION: i64 = 7;
 
 /// Dynamic properties of a living chain.
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub enum DynamicProperty {
     /// For migration.
     DbVersion,
+    /// Finished steps of the running migration, for resuming.
+    DbMigrationStep,
 
     // * Global IDs
     /// 1000000 (start, never used)
@@ -24,6 +26,8 @@ pub enum DynamicProperty {
     LatestBlockNumber,
     // LatestBlockHash,
     LatestSolidBlockNumber,
+    /// Latest block confirmed by PBFT commits, since `AllowPbft`.
+    LatestPbftBlockNumber,
 
     IsMaintenance,
     NextMaintenanceTime,
@@ -62,6 +66,24 @@ pub enum DynamicProperty {
     GlobalFreeBandwidthUsed,
     /// Renamed: PublicNetTime = 0
     GlobalFreeBandwidthLatestSlot,
+
+    // * Fee
+    /// Renamed: BurnTrxAmount
+    ///
+    /// Burnt TRX, in place of the Blackhole account balance since `AllowBlackholeOptimization`.
+    BurnedTrx,
+    /// Transaction fees not yet paid to witnesses, since `AllowTransactionFeePool`.
+    TransactionFeePool,
+
+    // * Shielded TRC10, nile only
+    /// TRZ value in the shielded pool.
+    TotalShieldedPoolValue,
+
+    // * Index
+    /// First block whose transaction logs are indexed by `TransactionLog` and `TransactionLogByBlock`. Earlier
+    /// blocks must be scanned.
+    TransactionLogIndexStart,
+
     // * Unused and deprecated
     // ! Why a block scoped variable is saved to store?
     // BlockEnergyUsage
@@ -85,8 +107,6 @@ pub enum DynamicProperty {
     //
     // Default: 7fff1fc0033e0000000000000000000000000000000000000000000000000000
     // ActivePermissionMask,
-
-    // Unused in mainnet: TotalShieldedPoolValue
 }
 
 impl DynamicProperty {
@@ -102,6 +122,7 @@ impl DynamicProperty {
             // will be overwriten when apply genesis block
             (LatestBlockNumber, -1),
             (LatestSolidBlockNumber, -1),
+            (LatestPbftBlockNumber, 0),
             // * maintenance
             (IsMaintenance, 0),
             // FIXME: should be after genesis timestamp
@@ -120,6 +141,11 @@ impl DynamicProperty {
             // (TotalEnergyTargetLimit, 90_000_000_000 / 14400)
             (TotalEnergyAverageUsage, 0),
             (TotalEnergyAverageSlot, 0),
+            // * fee
+            (BurnedTrx, 0),
+            (TransactionFeePool, 0),
+            (TotalShieldedPoolValue, 0),
+            (TransactionLogIndexStart, 0),
         ];
     }
 
@@ -40,7 +40,11 @@ impl TvmUpgrade {
     pub fn precompile(
         &self,
     ) -> fn(H160, &[u8], Option<usize>, &dyn Backend) -> Option<Result<(ExitSucceed, Vec<u8>, usize), ExitError>> {
-        return self::precompile::tron_precompile;
+        if self.istanbul {
+            self::precompile::tron_precompile_istanbul
+        } else {
+            self::precompile::tron_precompile
+        }
     }
 
     pub fn to_tvm_config(&self) -> Config {
@@ -60,6 +64,11 @@ impl TvmUpgrade {
         if self.solidity059 {
             config.allow_tvm_solidity059();
         }
+        if self.istanbul {
+            // CHAINID and SELFBALANCE. EIP-1108 energy costs are handled by `precompile()`.
+            config.has_chain_id = true;
+            config.has_self_balance = true;
+        }
         // TODO: handle 4.1 update.
         config
     }
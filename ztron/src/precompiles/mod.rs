@@ -22,15 +22,15 @@ use self::helper::AbiArgIterator;
 pub mod helper;
 
 #[allow(dead_code)]
-struct SaplingParameters {
-    spend_vk: PreparedVerifyingKey<Bls12>,
-    output_vk: PreparedVerifyingKey<Bls12>,
+pub(crate) struct SaplingParameters {
+    pub spend_vk: PreparedVerifyingKey<Bls12>,
+    pub output_vk: PreparedVerifyingKey<Bls12>,
     spend_params: Parameters<Bls12>,
     output_params: Parameters<Bls12>,
 }
 
 lazy_static! {
-    static ref SAPLING_PARAMETERS: SaplingParameters = {
+    pub(crate) static ref SAPLING_PARAMETERS: SaplingParameters = {
         use std::path::Path;
 
         eprintln!("loading sapling parameters ...");
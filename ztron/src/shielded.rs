@@ -0,0 +1,170 @@
+//! Shielded TRC10 transfer, the `ShieldedTransferContract`. Only available in nile testnet.
+//!
+//! Same checks as `librustzcashSaplingCheckSpend`, `librustzcashSaplingCheckOutput` and
+//! `librustzcashSaplingFinalCheck` in java-tron.
+
+use std::convert::TryInto;
+
+use bellman::groth16::Proof;
+use bls12_381::Bls12;
+use group::GroupEncoding;
+use zcash_primitives::merkle_tree::{CommitmentTree, Hashable};
+use zcash_primitives::redjubjub::{PublicKey, Signature};
+use zcash_primitives::sapling::Node;
+use zcash_primitives::transaction::components::Amount;
+use zcash_proofs::sapling::SaplingVerificationContext;
+
+use crate::precompiles::{Error, SAPLING_PARAMETERS};
+
+/// Size of `c_enc` of a receive description.
+pub const NOTE_CIPHERTEXT_SIZE: usize = 580;
+/// Size of `c_out` of a receive description.
+pub const OUT_CIPHERTEXT_SIZE: usize = 80;
+
+/// Spend description, in raw bytes.
+pub struct SpendDescription<'a> {
+    pub value_commitment: &'a [u8],
+    pub anchor: &'a [u8],
+    pub nullifier: &'a [u8],
+    pub rk: &'a [u8],
+    pub zkproof: &'a [u8],
+    pub spend_authority_signature: &'a [u8],
+}
+
+/// Receive description, in raw bytes. Ciphertexts are not verified.
+pub struct ReceiveDescription<'a> {
+    pub value_commitment: &'a [u8],
+    pub note_commitment: &'a [u8],
+    pub epk: &'a [u8],
+    pub zkproof: &'a [u8],
+}
+
+fn to_array32(raw: &[u8]) -> Result<[u8; 32], Error> {
+    raw.try_into().map_err(|_| Error::InvalidValue)
+}
+
+fn read_point(raw: &[u8]) -> Result<jubjub::ExtendedPoint, Error> {
+    Option::from(jubjub::ExtendedPoint::from_bytes(&to_array32(raw)?)).ok_or(Error::InvalidValue)
+}
+
+fn read_scalar(raw: &[u8]) -> Result<bls12_381::Scalar, Error> {
+    Option::from(bls12_381::Scalar::from_bytes(&to_array32(raw)?)).ok_or(Error::InvalidValue)
+}
+
+/// Verify spend proofs and spend authority signatures, output proofs, then the binding signature.
+///
+/// `value_balance` is the value leaving the shielded pool, i.e. `to_amount + fee - from_amount`.
+pub fn verify_transfer(
+    spends: &[SpendDescription],
+    receives: &[ReceiveDescription],
+    binding_sig: &[u8],
+    value_balance: i64,
+    sighash: &[u8; 32],
+) -> Result<(), Error> {
+    let mut ctx = SaplingVerificationContext::new();
+
+    for spend in spends {
+        let cv = read_point(spend.value_commitment)?;
+        let anchor = read_scalar(spend.anchor)?;
+        let nullifier = to_array32(spend.nullifier)?;
+        let rk = PublicKey::read(spend.rk)?;
+        let spend_auth_sig = Signature::read(spend.spend_authority_signature)?;
+        let zkproof = Proof::<Bls12>::read(spend.zkproof)?;
+
+        if !ctx.check_spend(
+            cv,
+            anchor,
+            &nullifier,
+            rk,
+            sighash,
+            spend_auth_sig,
+            zkproof,
+            &SAPLING_PARAMETERS.spend_vk,
+        ) {
+            return Err(Error::CheckSpend);
+        }
+    }
+
+    for receive in receives {
+        let cv = read_point(receive.value_commitment)?;
+        let cm = read_scalar(receive.note_commitment)?;
+        let epk = read_point(receive.epk)?;
+        let zkproof = Proof::<Bls12>::read(receive.zkproof)?;
+
+        if !ctx.check_output(cv, cm, epk, zkproof, &SAPLING_PARAMETERS.output_vk) {
+            return Err(Error::ChecknOutput);
+        }
+    }
+
+    let value_balance = Amount::from_i64(value_balance).map_err(|_| Error::InvalidValue)?;
+    let binding_sig = Signature::read(binding_sig)?;
+    if !ctx.final_check(value_balance, sighash, binding_sig) {
+        return Err(Error::FinalCheck);
+    }
+    Ok(())
+}
+
+/// Incremental merkle tree of note commitments, of depth 32.
+pub struct NoteCommitmentTree(CommitmentTree<Node>);
+
+impl Default for NoteCommitmentTree {
+    fn default() -> Self {
+        NoteCommitmentTree(CommitmentTree::new())
+    }
+}
+
+impl NoteCommitmentTree {
+    pub fn from_bytes(raw: &[u8]) -> Result<Self, Error> {
+        Ok(NoteCommitmentTree(CommitmentTree::read(raw)?))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(1024);
+        self.0.write(&mut buf).expect("write to vec");
+        buf
+    }
+
+    /// Number of note commitments.
+    pub fn size(&self) -> usize {
+        self.0.size()
+    }
+
+    pub fn append(&mut self, cm: &[u8]) -> Result<(), Error> {
+        let node = Node::read(&to_array32(cm)?[..])?;
+        self.0
+            .append(node)
+            .map_err(|_| Error::Runtime("note commitment tree is full"))
+    }
+
+    /// Merkle root, used as anchor of spends.
+    pub fn root(&self) -> [u8; 32] {
+        let mut raw = [0u8; 32];
+        self.0.root().write(&mut raw[..]).expect("length is 32 bytes");
+        raw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_commitment_tree() {
+        let mut tree = NoteCommitmentTree::default();
+        let mut empty_root = [0u8; 32];
+        Node::empty_root(32).write(&mut empty_root[..]).unwrap();
+        assert_eq!(tree.root(), empty_root);
+
+        let cm = hex::decode("05655316a07e6ec8c9769af54ef98b30667bfb6302b32987d552227dae86a087").unwrap();
+        tree.append(&cm).unwrap();
+        tree.append(&cm).unwrap();
+        assert_eq!(tree.size(), 2);
+        assert_ne!(tree.root(), empty_root);
+
+        let restored = NoteCommitmentTree::from_bytes(&tree.to_bytes()).unwrap();
+        assert_eq!(restored.size(), 2);
+        assert_eq!(restored.root(), tree.root());
+
+        assert!(tree.append(&cm[1..]).is_err());
+    }
+}